@@ -1,3 +1,59 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
 fn main() {
-    askama::rerun_if_templates_changed();
+    println!("cargo:rerun-if-changed=templates");
+    println!("cargo:rerun-if-changed=packs");
+
+    let out = PathBuf::from(env::var("OUT_DIR").unwrap());
+
+    let root = env::var("CARGO_MANIFEST_DIR").unwrap();
+
+    // list the built-in templates, so that they can be listed alongside their overrides, and embed
+    // their sources for template packs to render
+    let templates = find_files(Path::new("templates")).expect("unable to list templates");
+
+    let sources: String = templates.iter()
+        .map(|t| format!("    ({:?}, include_str!({:?})),\n", t, Path::new(&root).join("templates").join(t)))
+        .collect();
+
+    fs::write(out.join("templates.rs"), format!("/// The path of every template compiled into nfty, within `templates/`.\n\
+        pub static BUILTIN: &[&str] = &{:?};\n\n\
+        /// The source of every template compiled into nfty, by its path within `templates/`.\n\
+        static SOURCES: &[(&str, &str)] = &[\n{}];\n", templates, sources)).expect("unable to write template list");
+
+    // embed the built-in template packs, which are read like packs on disk
+    let packs = find_files(Path::new("packs")).expect("unable to list template packs");
+
+    let entries: String = packs.iter()
+        .map(|p| format!("    ({:?}, include_str!({:?})),\n", p, Path::new(&root).join("packs").join(p)))
+        .collect();
+
+    fs::write(out.join("packs.rs"), format!("/// Every file in the template packs compiled into nfty, by its path within `packs/`.\n\
+        pub static BUILTIN: &[(&str, &str)] = &[\n{}];\n", entries)).expect("unable to write template packs");
+}
+
+/// Find every file beneath a directory, by its path relative to the directory, in order.
+fn find_files(root: &Path) -> io::Result<Vec<String>> {
+    fn walk(root: &Path, dir: &Path, found: &mut Vec<String>) -> io::Result<()> {
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+
+            if path.is_dir() {
+                walk(root, &path, found)?;
+            } else {
+                found.push(path.strip_prefix(root).unwrap().to_string_lossy().to_string());
+            }
+        }
+
+        Ok(())
+    }
+
+    let mut found = Vec::new();
+    walk(root, root, &mut found)?;
+    found.sort();
+
+    Ok(found)
 }
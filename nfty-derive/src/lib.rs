@@ -7,27 +7,281 @@ extern crate quote;
 extern crate syn;
 
 use crate::proc_macro::TokenStream;
-use syn::DeriveInput;
+use syn::{Attribute, Data, DeriveInput, Fields, Lit, Meta, NestedMeta};
 
-#[proc_macro_derive(WritableTemplate)]
+/// Options given in `#[writable(...)]` attributes, along with the source given in the
+/// `#[template(...)]` attribute.
+#[derive(Default)]
+struct WritableOptions {
+    /// The line comment syntax used to delimit a managed block, e.g. `#`.
+    comment: Option<String>,
+    /// The path of the template within `templates/`, from `#[template(path = "...")]`.
+    path: Option<String>,
+    /// The source of an inline template, from `#[template(source = "...")]`.
+    source: Option<String>,
+    /// The permissions of the rendered file, e.g. `0o755`.
+    mode: Option<u64>,
+    /// The path the template is rendered to relative to the project root.
+    default_path: Option<String>,
+    /// How the rendered file ends, `trim` or `ensure`.
+    newline: Option<String>,
+    /// When an existing file may be overwritten, `always`, `if-unmodified` or `never`.
+    overwrite: Option<String>,
+    /// The format rendered contents are validated as, `yaml` or `ini`.
+    format: Option<String>,
+    /// The type in `templates::validate` which rendered YAML must deserialize into.
+    schema: Option<String>,
+}
+
+impl WritableOptions {
+    fn parse(attrs: &[Attribute]) -> Self {
+        let mut result = Self::default();
+
+        for meta in attrs.iter().filter_map(|a| a.interpret_meta()) {
+            let list = match meta {
+                Meta::List(ref list) if list.ident == "template" => {
+                    for nested in list.nested.iter() {
+                        match nested {
+                            NestedMeta::Meta(Meta::NameValue(ref nv)) if nv.ident == "path" => match nv.lit {
+                                Lit::Str(ref s) => result.path = Some(s.value()),
+                                _ => panic!("#[template(path = ...)] must be a string"),
+                            },
+                            NestedMeta::Meta(Meta::NameValue(ref nv)) if nv.ident == "source" => match nv.lit {
+                                Lit::Str(ref s) => result.source = Some(s.value()),
+                                _ => panic!("#[template(source = ...)] must be a string"),
+                            },
+                            _ => panic!("unknown #[template] option"),
+                        }
+                    }
+
+                    continue;
+                }
+                Meta::List(ref list) if list.ident == "writable" => list,
+                _ => continue,
+            };
+
+            for nested in list.nested.iter() {
+                match nested {
+                    NestedMeta::Meta(Meta::NameValue(ref nv)) if nv.ident == "comment" => match nv.lit {
+                        Lit::Str(ref s) => result.comment = Some(s.value()),
+                        _ => panic!("#[writable(comment = ...)] must be a string"),
+                    },
+                    NestedMeta::Meta(Meta::NameValue(ref nv)) if nv.ident == "mode" => match nv.lit {
+                        Lit::Int(ref i) => result.mode = Some(i.value()),
+                        _ => panic!("#[writable(mode = ...)] must be an integer, e.g. 0o755"),
+                    },
+                    NestedMeta::Meta(Meta::NameValue(ref nv)) if nv.ident == "default_path" => match nv.lit {
+                        Lit::Str(ref s) => result.default_path = Some(s.value()),
+                        _ => panic!("#[writable(default_path = ...)] must be a string"),
+                    },
+                    NestedMeta::Meta(Meta::NameValue(ref nv)) if nv.ident == "newline" => match nv.lit {
+                        Lit::Str(ref s) => result.newline = Some(s.value()),
+                        _ => panic!("#[writable(newline = ...)] must be a string"),
+                    },
+                    NestedMeta::Meta(Meta::NameValue(ref nv)) if nv.ident == "overwrite" => match nv.lit {
+                        Lit::Str(ref s) => result.overwrite = Some(s.value()),
+                        _ => panic!("#[writable(overwrite = ...)] must be a string"),
+                    },
+                    NestedMeta::Meta(Meta::NameValue(ref nv)) if nv.ident == "format" => match nv.lit {
+                        Lit::Str(ref s) => result.format = Some(s.value()),
+                        _ => panic!("#[writable(format = ...)] must be a string"),
+                    },
+                    NestedMeta::Meta(Meta::NameValue(ref nv)) if nv.ident == "schema" => match nv.lit {
+                        Lit::Str(ref s) => result.schema = Some(s.value()),
+                        _ => panic!("#[writable(schema = ...)] must be a string"),
+                    },
+                    _ => panic!("unknown #[writable] option"),
+                }
+            }
+        }
+
+        result
+    }
+}
+
+/// The doc comment among the given attributes, joined into a single line.
+fn doc(attrs: &[Attribute]) -> String {
+    attrs.iter()
+        .filter_map(|a| a.interpret_meta())
+        .filter_map(|meta| match meta {
+            Meta::NameValue(ref nv) if nv.ident == "doc" => match nv.lit {
+                Lit::Str(ref s) => Some(s.value().trim().to_string()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[proc_macro_derive(WritableTemplate, attributes(template, writable))]
 pub fn writable_template_derive(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
+    let options = WritableOptions::parse(&input.attrs);
 
     // type name
     let name = &input.ident;
 
+    let description = doc(&input.attrs);
+
+    // every named field is a variable of the template
+    let fields = match input.data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => fields.named.iter().collect(),
+            _ => Vec::new(),
+        },
+        _ => panic!("WritableTemplate can only be derived for structs"),
+    };
+
+    let fields = fields.into_iter().map(|field| {
+        let field_name = field.ident.as_ref().unwrap().to_string();
+        let ty = &field.ty;
+        let ty_name = quote!(#ty).to_string().replace(' ', "");
+        let field_description = doc(&field.attrs);
+
+        // the kind is found through the type system, so aliases and qualified paths resolve
+        quote! {
+            crate::project::templates::Field {
+                name: #field_name,
+                ty: #ty_name,
+                kind: <#ty as crate::project::templates::FieldKind>::KIND,
+                description: #field_description,
+            }
+        }
+    });
+
     // generics
     let generics = input.generics;
     let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
+    // only templates declaring their comment syntax have managed blocks
+    let comment = match options.comment {
+        Some(comment) => quote! {
+            fn comment(&self) -> ::std::option::Option<&'static str> {
+                ::std::option::Option::Some(#comment)
+            }
+        },
+        None => quote! {},
+    };
+
+    let mode = match options.mode {
+        // only permission bits, which also keeps the mode within a u32
+        Some(mode) if mode > 0o7777 => panic!("#[writable(mode = {:#o})] must be at most 0o7777", mode),
+        Some(mode) => {
+            let mode = mode as u32;
+
+            quote! {
+                fn mode(&self) -> ::std::option::Option<u32> {
+                    ::std::option::Option::Some(#mode)
+                }
+            }
+        }
+        None => quote! {},
+    };
+
+    let default_path = match options.default_path {
+        Some(path) => quote! {
+            fn default_path() -> ::std::option::Option<&'static str> {
+                ::std::option::Option::Some(#path)
+            }
+        },
+        None => quote! {},
+    };
+
+    let newline = match options.newline.as_deref() {
+        Some("trim") => quote! { crate::project::templates::Newline::Trim },
+        Some("ensure") => quote! { crate::project::templates::Newline::Ensure },
+        Some(other) => panic!("#[writable(newline = {:?})] must be \"trim\" or \"ensure\"", other),
+        None => quote! { crate::project::templates::Newline::default() },
+    };
+
+    let overwrite = match options.overwrite.as_deref() {
+        Some("always") => quote! { crate::project::templates::Overwrite::Always },
+        Some("if-unmodified") => quote! { crate::project::templates::Overwrite::IfUnmodified },
+        Some("never") => quote! { crate::project::templates::Overwrite::Never },
+        Some(other) => panic!("#[writable(overwrite = {:?})] must be \"always\", \"if-unmodified\" or \"never\"", other),
+        None => quote! { crate::project::templates::Overwrite::default() },
+    };
+
+    // schemas are types in templates::validate, and only apply to YAML
+    let schema = options.schema.as_ref().map(|schema| {
+        let schema: syn::Type = syn::parse_str(schema)
+            .unwrap_or_else(|_| panic!("#[writable(schema = {:?})] must be a type", schema));
+
+        quote! { crate::project::templates::validate::#schema }
+    });
+
+    let validate = match (options.format.as_deref(), schema) {
+        (Some("yaml"), schema) => {
+            let schema = schema.unwrap_or_else(|| quote! { ::serde_yaml::Value });
+
+            quote! {
+                fn validate(&self, contents: &str)
+                        -> ::std::result::Result<(), crate::project::templates::validate::Invalid> {
+                    crate::project::templates::validate::yaml::<#schema>(contents)
+                }
+            }
+        }
+        (Some("ini"), None) => quote! {
+            fn validate(&self, contents: &str)
+                    -> ::std::result::Result<(), crate::project::templates::validate::Invalid> {
+                crate::project::templates::validate::ini(contents)
+            }
+        },
+        (Some("ini"), Some(_)) => panic!("#[writable(schema = ...)] only applies to yaml"),
+        (Some(other), _) => panic!("#[writable(format = {:?})] must be \"yaml\" or \"ini\"", other),
+        (None, Some(_)) => panic!("#[writable(schema = ...)] requires format = \"yaml\""),
+        (None, None) => quote! {},
+    };
+
+    // templates in templates/ are compiled in, and rendered with the same engine as overrides
+    let source = match (options.path.as_ref(), options.source.as_ref()) {
+        (Some(path), None) => quote! {
+            include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/templates/", #path))
+        },
+        (None, Some(source)) => quote! { #source },
+        _ => panic!("WritableTemplate requires one of #[template(path = ...)] or #[template(source = ...)]"),
+    };
+
+    // templates are named by their path, falling back to the type name for inline sources
+    let template_name = options.path.unwrap_or_else(|| name.to_string());
+
     let expanded = quote! {
         impl #impl_generics crate::project::templates::WritableTemplate for #name #ty_generics #where_clause {
-            fn write(&self, dest: &::std::path::Path) -> ::std::io::Result<()> {
-                let mut file = ::std::io::BufWriter::new(::std::fs::File::create(dest)?);
-                file.write(self.render().unwrap().trim().as_bytes())?;
+            fn name(&self) -> &'static str {
+                #template_name
+            }
+
+            fn description() -> &'static str {
+                #description
+            }
+
+            fn fields() -> &'static [crate::project::templates::Field] {
+                const FIELDS: &[crate::project::templates::Field] = &[#(#fields),*];
+                FIELDS
+            }
+
+            fn contents(&self) -> ::std::io::Result<::std::string::String> {
+                crate::project::templates::render_str(#source, self).map_err(|e| {
+                    ::std::io::Error::new(::std::io::ErrorKind::InvalidData, format!("{}: {}", #template_name, e))
+                })
+            }
+
+            fn newline(&self) -> crate::project::templates::Newline {
+                #newline
+            }
 
-                Ok(())
+            fn overwrite(&self) -> crate::project::templates::Overwrite {
+                #overwrite
             }
+
+            #comment
+
+            #mode
+
+            #default_path
+
+            #validate
         }
     };
 
@@ -1,4 +1,6 @@
+pub mod hook;
 pub mod project;
+pub mod template;
 
 use crate::logging;
 
@@ -52,14 +54,22 @@ impl Opt {
         self.configure_logging();
 
         match self.command {
+            Subcommand::Hook(ref c) => c.execute(),
             Subcommand::Project(ref c) => c.execute(),
+            Subcommand::Template(ref c) => c.execute(),
         }
     }
 }
 
 #[derive(Debug, StructOpt)]
 pub enum Subcommand {
+    /// Run Git hooks and hook actions.
+    #[structopt(name = "hook")]
+    Hook(crate::cli::hook::Hook),
     /// Manage software projects.
     #[structopt(name = "project")]
     Project(crate::cli::project::Project),
+    /// Inspect the templates projects are rendered from.
+    #[structopt(name = "template")]
+    Template(crate::cli::template::Template),
 }
@@ -0,0 +1,47 @@
+mod branch_clean;
+mod commit_msg;
+mod dependencies;
+mod pre_commit;
+mod pre_push;
+mod run;
+
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub enum Hook {
+    /// Delete local branches which have been merged into the current branch.
+    #[structopt(name = "branch-clean")]
+    BranchClean(branch_clean::BranchClean),
+    /// Check a commit message against the configured commit message policy.
+    #[structopt(name = "commit-msg")]
+    CommitMsg(commit_msg::CommitMsg),
+    /// Point out dependency manifests changed by a merge or checkout, optionally installing them.
+    #[structopt(name = "dependencies")]
+    Dependencies(dependencies::Dependencies),
+    /// Check staged files for whitespace errors, large files, conflict markers and secrets.
+    #[structopt(name = "pre-commit")]
+    PreCommit(pre_commit::PreCommit),
+    /// Check pushed refs against the protected branches and warn about unfinished commits.
+    #[structopt(name = "pre-push")]
+    PrePush(pre_push::PrePush),
+    /// Fill in the issue key from the branch name when preparing a commit message.
+    #[structopt(name = "prepare-commit-msg")]
+    PrepareCommitMsg(commit_msg::PrepareCommitMsg),
+    /// Run the scripts installed for a Git hook. Installed hooks call this automatically.
+    #[structopt(name = "run")]
+    Run(run::Run),
+}
+
+impl Hook {
+    pub fn execute(&self) {
+        match self {
+            Hook::BranchClean(ref c) => c.execute(),
+            Hook::CommitMsg(ref c) => c.execute(),
+            Hook::Dependencies(ref c) => c.execute(),
+            Hook::PreCommit(ref c) => c.execute(),
+            Hook::PrePush(ref c) => c.execute(),
+            Hook::PrepareCommitMsg(ref c) => c.execute(),
+            Hook::Run(ref c) => c.execute(),
+        }
+    }
+}
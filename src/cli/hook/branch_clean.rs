@@ -0,0 +1,38 @@
+use crate::config::Config;
+use crate::project::branches::{self, PruneOptions};
+
+use git2::Repository;
+
+use log::{error, warn};
+
+use std::process::exit;
+
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub struct BranchClean {}
+
+impl BranchClean {
+    pub fn execute(&self) {
+        let repo = Repository::open_from_env().map_err(|e| {
+            error!("Unable to open a Git repository from the current directory: {}", e);
+            exit(1)
+        }).unwrap();
+
+        let config = Config::for_repository(&repo).map_err(|e| {
+            error!("Unable to load configuration: {}", e);
+            exit(1)
+        }).unwrap();
+
+        let options = PruneOptions {
+            protected: config.branches.protected,
+            dry_run: false,
+            prune_gone: config.branches.prune_gone,
+        };
+
+        // a failure to clean up shouldn't fail the merge that triggered it
+        if let Err(e) = branches::prune(&repo, &options) {
+            warn!("Unable to clean up merged branches: {}", e);
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use crate::config::Config;
+use crate::project::hooks::commit_msg::Policy;
+
+use git2::Repository;
+
+use log::{debug, error};
+
+use std::fs;
+use std::path::PathBuf;
+use std::process::exit;
+
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub struct CommitMsg {
+    /// The file containing the proposed commit message.
+    #[structopt(parse(from_os_str))]
+    pub message_file: PathBuf,
+}
+
+impl CommitMsg {
+    pub fn execute(&self) {
+        let repo = Repository::open_from_env().map_err(|e| {
+            error!("Unable to open a Git repository from the current directory: {}", e);
+            exit(1)
+        }).unwrap();
+
+        let config = Config::for_repository(&repo).map_err(|e| {
+            error!("Unable to load configuration: {}", e);
+            exit(1)
+        }).unwrap();
+
+        let policy = Policy::new(&config.commit_msg).map_err(|e| {
+            error!("Unable to load commit message policy: {}", e);
+            exit(1)
+        }).unwrap();
+
+        let message = fs::read_to_string(&self.message_file).map_err(|e| {
+            error!("Unable to read commit message from {}: {}", self.message_file.display(), e);
+            exit(1)
+        }).unwrap();
+
+        let violations = policy.check(&message);
+
+        if violations.is_empty() {
+            debug!("Commit message conforms to policy.");
+            return;
+        }
+
+        error!("Commit message does not conform to policy:");
+
+        for violation in violations {
+            error!("  - {}", violation);
+        }
+
+        exit(1);
+    }
+}
+
+#[derive(Debug, StructOpt)]
+pub struct PrepareCommitMsg {
+    /// The file containing the commit message being prepared.
+    #[structopt(parse(from_os_str))]
+    pub message_file: PathBuf,
+    /// Where the message came from: message, template, merge, squash or commit.
+    pub source: Option<String>,
+    /// The commit being amended, if any.
+    pub commit: Option<String>,
+}
+
+impl PrepareCommitMsg {
+    pub fn execute(&self) {
+        // merges, squashes and amends already have a message worth keeping
+        match self.source.as_deref() {
+            Some("merge") | Some("squash") | Some("commit") => return,
+            _ => (),
+        }
+
+        let repo = match Repository::open_from_env() {
+            Ok(repo) => repo,
+            Err(_) => return,
+        };
+
+        let config = match Config::for_repository(&repo) {
+            Ok(config) => config,
+            Err(e) => {
+                debug!("Not preparing commit message, unable to load configuration: {}", e);
+                return;
+            }
+        };
+
+        let branch = match repo.head().ok().and_then(|h| h.shorthand().map(|s| s.to_string())) {
+            Some(branch) => branch,
+            None => return,
+        };
+
+        let policy = match Policy::new(&config.commit_msg) {
+            Ok(policy) => policy,
+            Err(e) => {
+                debug!("Not preparing commit message: {}", e);
+                return;
+            }
+        };
+
+        let message = fs::read_to_string(&self.message_file).unwrap_or_default();
+
+        if let Some(prepared) = policy.prefill(&message, &branch) {
+            if let Err(e) = fs::write(&self.message_file, prepared) {
+                error!("Unable to write commit message to {}: {}", self.message_file.display(), e);
+                exit(1);
+            }
+        }
+    }
+}
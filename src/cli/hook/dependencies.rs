@@ -0,0 +1,92 @@
+use crate::config::Config;
+use crate::project::hooks::dependencies;
+
+use git2::{Oid, Repository};
+
+use log::{debug, error, info, warn};
+
+use std::process::exit;
+
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub struct Dependencies {
+    /// The commit checked out before, as passed to the post-checkout hook. Without it, the changes
+    /// made by the last merge are used, i.e. `ORIG_HEAD..HEAD`.
+    pub old: Option<String>,
+    /// The commit checked out now, as passed to the post-checkout hook.
+    pub new: Option<String>,
+    /// Whether the checkout changed branches (1) or only checked out files (0), as passed to the
+    /// post-checkout hook.
+    pub branch_checkout: Option<u8>,
+}
+
+impl Dependencies {
+    pub fn execute(&self) {
+        let repo = Repository::open_from_env().map_err(|e| {
+            error!("Unable to open a Git repository from the current directory: {}", e);
+            exit(1)
+        }).unwrap();
+
+        let workdir = match repo.workdir() {
+            Some(dir) => dir.to_path_buf(),
+            None => return,
+        };
+
+        let (old, new) = match self.range(&repo) {
+            Some(range) => range,
+            None => return,
+        };
+
+        let config = Config::for_repository(&repo).map_err(|e| {
+            error!("Unable to load configuration: {}", e);
+            exit(1)
+        }).unwrap();
+
+        let changes = dependencies::changed(&repo, old, new).map_err(|e| {
+            error!("Unable to compare {} with {}: {}", old, new, e);
+            exit(1)
+        }).unwrap();
+
+        // a failure here shouldn't fail the merge or checkout that triggered it
+        for change in changes {
+            if !config.dependencies.install {
+                warn!("{} changed, run `{}` in {} to update {} dependencies.", change.path.display(),
+                    change.ecosystem.command, workdir.join(change.dir()).display(), change.ecosystem.name);
+                continue;
+            }
+
+            info!("{} changed, running `{}`", change.path.display(), change.ecosystem.command);
+
+            match change.install(&workdir) {
+                Ok(status) if status.success() => (),
+                Ok(status) => warn!("Unable to update {} dependencies, `{}` failed with {}",
+                    change.ecosystem.name, change.ecosystem.command, status),
+                Err(e) => warn!("Unable to update {} dependencies: {}", change.ecosystem.name, e),
+            }
+        }
+    }
+
+    /// Resolve the commits to compare, or `None` if there is nothing to compare.
+    fn range(&self, repo: &Repository) -> Option<(Oid, Oid)> {
+        if self.branch_checkout == Some(0) {
+            debug!("Ignoring a checkout of files.");
+            return None;
+        }
+
+        let (old, new) = match (&self.old, &self.new) {
+            (Some(old), Some(new)) => (Oid::from_str(old).ok()?, Oid::from_str(new).ok()?),
+            _ => (
+                repo.revparse_single("ORIG_HEAD").ok()?.peel_to_commit().ok()?.id(),
+                repo.head().ok()?.peel_to_commit().ok()?.id(),
+            ),
+        };
+
+        // the old commit is zero when checking out a fresh clone
+        if old.is_zero() || old == new {
+            return None;
+        }
+
+        Some((old, new))
+    }
+}
@@ -0,0 +1,45 @@
+use crate::config::Config;
+use crate::project::hooks::pre_commit;
+
+use git2::Repository;
+
+use log::{debug, error};
+
+use std::process::exit;
+
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub struct PreCommit {}
+
+impl PreCommit {
+    pub fn execute(&self) {
+        let repo = Repository::open_from_env().map_err(|e| {
+            error!("Unable to open a Git repository from the current directory: {}", e);
+            exit(1)
+        }).unwrap();
+
+        let config = Config::for_repository(&repo).map_err(|e| {
+            error!("Unable to load configuration: {}", e);
+            exit(1)
+        }).unwrap();
+
+        let findings = pre_commit::check(&repo, &config.pre_commit).map_err(|e| {
+            error!("Unable to check staged files: {}", e);
+            exit(1)
+        }).unwrap();
+
+        if findings.is_empty() {
+            debug!("Staged files passed pre-commit checks.");
+            return;
+        }
+
+        error!("Staged files failed pre-commit checks:");
+
+        for finding in findings {
+            error!("  - {}", finding);
+        }
+
+        exit(1);
+    }
+}
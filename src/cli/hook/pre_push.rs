@@ -0,0 +1,76 @@
+use crate::config::Config;
+use crate::project::hooks::pre_push::{Guard, RefUpdate, SKIP_VARIABLE};
+
+use git2::Repository;
+
+use log::{debug, error, warn};
+
+use std::env;
+use std::io::{self, Read};
+use std::process::exit;
+
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub struct PrePush {
+    /// The name of the remote being pushed to, or its URL if it isn't a named remote.
+    pub remote: String,
+    /// The URL being pushed to.
+    pub url: Option<String>,
+}
+
+impl PrePush {
+    pub fn execute(&self) {
+        if env::var(SKIP_VARIABLE).is_ok_and(|v| v == "1") {
+            warn!("Skipping pre-push checks, {} is set.", SKIP_VARIABLE);
+            return;
+        }
+
+        let repo = Repository::open_from_env().map_err(|e| {
+            error!("Unable to open a Git repository from the current directory: {}", e);
+            exit(1)
+        }).unwrap();
+
+        let config = Config::for_repository(&repo).map_err(|e| {
+            error!("Unable to load configuration: {}", e);
+            exit(1)
+        }).unwrap();
+
+        let guard = Guard::new(&config).map_err(|e| {
+            error!("Unable to load pre-push configuration: {}", e);
+            exit(1)
+        }).unwrap();
+
+        let mut input = String::new();
+
+        let updates = io::stdin().read_to_string(&mut input).map_err(|e| e.into())
+            .and_then(|_| RefUpdate::parse_all(&input))
+            .map_err(|e| {
+                error!("Unable to read the refs being pushed: {}", e);
+                exit(1)
+            }).unwrap();
+
+        let report = guard.check(&repo, &self.remote, &updates).map_err(|e| {
+            error!("Unable to check the refs being pushed: {}", e);
+            exit(1)
+        }).unwrap();
+
+        for warning in &report.warnings {
+            warn!("{}", warning);
+        }
+
+        if report.errors.is_empty() {
+            debug!("Push passed pre-push checks.");
+            return;
+        }
+
+        error!("Push rejected by pre-push checks:");
+
+        for e in &report.errors {
+            error!("  - {}", e);
+        }
+
+        error!("Set {}=1 to push anyway.", SKIP_VARIABLE);
+        exit(1);
+    }
+}
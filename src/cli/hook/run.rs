@@ -0,0 +1,54 @@
+use crate::project::hooks::{self, HOOK_TYPES};
+use crate::project::hooks::dispatch::Dispatcher;
+
+use git2::Repository;
+
+use log::error;
+
+use std::ffi::OsString;
+use std::process::exit;
+
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub struct Run {
+    /// The type of Git hook to run, e.g. `pre-push`.
+    pub hook_type: String,
+    /// Arguments passed to the hook by Git, forwarded verbatim to each script.
+    #[structopt(last = true, parse(from_os_str))]
+    pub args: Vec<OsString>,
+}
+
+impl Run {
+    pub fn execute(&self) {
+        if !HOOK_TYPES.contains(&self.hook_type.as_str()) {
+            error!("Unsupported hook type: {}", self.hook_type);
+            exit(1);
+        }
+
+        // git sets GIT_DIR when running hooks, so this finds the right repository even in worktrees,
+        // whose hooks are those of the repository they belong to
+        let repo = Repository::open_from_env().map_err(|e| {
+            error!("Unable to open a Git repository from the current directory: {}", e);
+            exit(1)
+        }).unwrap();
+
+        let hooks_dir = hooks::hooks_path(&repo).map_err(|e| {
+            error!("Unable to find the hooks directory of the repository: {}", e);
+            exit(1)
+        }).unwrap();
+
+        let dispatcher = Dispatcher::new(&hooks_dir, &self.hook_type);
+
+        let results = dispatcher.run(&self.args).map_err(|e| {
+            error!("[ERROR] git:{} - unable to run hooks: {}", dispatcher.hook_type(), e);
+            exit(1)
+        }).unwrap();
+
+        if let Some(failed) = results.iter().find(|r| !r.status.success()) {
+            error!("[ERROR] git:{} - hook {}.d/{} failed with {} after {:.3}s", dispatcher.hook_type(),
+                dispatcher.hook_type(), failed.name(), failed.status, failed.elapsed.as_secs_f64());
+            exit(failed.code());
+        }
+    }
+}
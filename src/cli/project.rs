@@ -1,7 +1,10 @@
 mod bring;
 mod conform;
 mod engage;
+mod hooks;
 mod license;
+mod new;
+mod prune_branches;
 
 use structopt::StructOpt;
 
@@ -16,9 +19,19 @@ pub enum Project {
     /// Manage software projects.
     #[structopt(name = "engage")]
     Engage(engage::Engage),
+    /// Inspect and manage the Git hooks installed by nfty.
+    #[structopt(name = "hooks")]
+    Hooks(hooks::Hooks),
     /// Generate software licenses for a project.
     #[structopt(name = "license")]
     License(license::License),
+    /// Create a new project under the project root from a project template, with its origin remote
+    /// set, hooks installed and everything rendered in its initial commit.
+    #[structopt(name = "new")]
+    New(new::New),
+    /// Delete local branches which have been merged into the current branch.
+    #[structopt(name = "prune-branches")]
+    PruneBranches(prune_branches::PruneBranches),
 }
 
 impl Project {
@@ -27,7 +40,10 @@ impl Project {
             Project::Bring(ref c) => c.execute(),
             Project::Conform(ref c) => c.execute(),
             Project::Engage(ref c) => c.execute(),
+            Project::Hooks(ref c) => c.execute(),
             Project::License(ref c) => c.execute(),
+            Project::New(ref c) => c.execute(),
+            Project::PruneBranches(ref c) => c.execute(),
         }
     }
 }
@@ -1,35 +1,185 @@
 mod ansible;
+mod batch;
+mod pack;
 
+#[cfg(test)]
+mod tests;
+
+use crate::config::PROJECT_CONFIG_DIR;
+use crate::project::backup::Snapshot;
+use crate::project::commit;
+use crate::project::render::{Change, PlannedFile, RenderPlan};
+use crate::project::state::State;
+use crate::project::templates::answers::{Answers, Prompt, Terminal};
 use crate::project::templates::license;
+use crate::project::Project;
+
+use chrono::Local;
 
 use git2::Repository;
 
-use log::error;
+use log::{debug, error, info, warn};
+
+use serde_yaml::{Mapping, Value};
 
-use std::path::PathBuf;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
 use std::process::exit;
 
 use structopt::StructOpt;
 
-#[derive(Debug, StructOpt)]
+#[derive(Clone, Debug, StructOpt)]
 pub struct Conform {
     /// Project should be assumed to be private rather than the default, which is public.
     #[structopt(short = "P", long = "private")]
     pub private: bool,
+    /// Report what would be created, changed or removed without writing anything.
+    #[structopt(short = "n", long = "dry-run")]
+    pub dry_run: bool,
+    /// Show a unified diff of each file which is created, changed or removed.
+    #[structopt(long = "diff")]
+    pub diff: bool,
+    /// Write nothing, and exit with an error listing every file which differs from the template.
+    #[structopt(long = "check")]
+    pub check: bool,
+    /// Overwrite files which were edited since they were last rendered, rather than skipping or merging them,
+    /// and conform a working tree with uncommitted changes. With undo, restore files edited since the conform.
+    #[structopt(short = "f", long = "force")]
+    pub force: bool,
+    /// Stage every rendered file along with conform's own files in .nfty, and commit them with a message listing
+    /// the template and its options.
+    #[structopt(long = "commit")]
+    pub commit: bool,
+    /// Commit on a fresh nfty/conform-<date> branch rather than the current one.
+    #[structopt(long = "branch", requires = "commit")]
+    pub branch: bool,
+    /// Conform every local project under the project root in parallel rather than the current repository, skipping
+    /// those with uncommitted changes unless forced, and summarize what became of each.
+    #[structopt(short = "a", long = "all")]
+    pub all: bool,
+    /// Only conform the local projects whose host/owner/repository matches this glob pattern, e.g.
+    /// 'github.com/naftulikay/ansible-role-*'.
+    #[structopt(long = "filter", requires = "all")]
+    pub filter: Option<String>,
     #[structopt(subcommand)]
     pub template: ProjectTemplate,
 }
 
-#[derive(Debug, StructOpt)]
+#[derive(Clone, Debug, StructOpt)]
 pub enum ProjectTemplate {
     /// Conform and render an Ansible role project.
     #[structopt(name = "ansible")]
     Ansible(ansible::Ansible),
+    /// Conform and render a project from a template pack.
+    #[structopt(name = "pack")]
+    Pack(pack::PackTemplate),
+    /// Re-render the template and options the project was last conformed with, three-way merging
+    /// template changes into files edited since.
+    #[structopt(name = "upgrade")]
+    Upgrade,
+    /// Restore the files conform last changed from the snapshot taken before it changed them,
+    /// removing files it created. Each undo goes back one more conform.
+    #[structopt(name = "undo")]
+    Undo,
+}
+
+impl ProjectTemplate {
+    /// The name of the project template.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ProjectTemplate::Ansible(_) => "ansible",
+            ProjectTemplate::Pack(_) => "pack",
+            ProjectTemplate::Upgrade => "upgrade",
+            ProjectTemplate::Undo => "undo",
+        }
+    }
+
+    /// The options given to the project template.
+    pub fn options(&self) -> Value {
+        match self {
+            ProjectTemplate::Ansible(ref t) => serde_yaml::to_value(t).unwrap_or(Value::Null),
+            ProjectTemplate::Pack(ref t) => serde_yaml::to_value(t).unwrap_or(Value::Null),
+            ProjectTemplate::Upgrade | ProjectTemplate::Undo => Value::Null,
+        }
+    }
+
+    /// Restore the project template and options recorded in a project's conform state.
+    fn restore(state: &State) -> Result<(ProjectTemplate, bool), String> {
+        let private = state.options.get("private").and_then(Value::as_bool).unwrap_or(false);
+
+        match state.template.as_deref() {
+            Some("ansible") => serde_yaml::from_value(state.options.clone())
+                .map(|t| (ProjectTemplate::Ansible(t), private))
+                .map_err(|e| format!("invalid options for the ansible template: {}", e)),
+            Some("pack") => serde_yaml::from_value(state.options.clone())
+                .map(|t| (ProjectTemplate::Pack(t), private))
+                .map_err(|e| format!("invalid options for the template pack: {}", e)),
+            Some(name) => Err(format!("unknown project template {}", name)),
+            None => Err("project was never conformed".to_string()),
+        }
+    }
+}
+
+/// What conforming a single project came to.
+#[derive(Debug, PartialEq)]
+pub enum Outcome {
+    /// Files were created, changed or removed, or would have been in a dry run or check.
+    Changed(usize),
+    /// The project conforms to its template already.
+    Unchanged,
+    /// The working tree has uncommitted changes, so the project was left alone.
+    Dirty(Vec<String>),
+}
+
+/// Why conforming a single project failed, along with anything to list under it.
+#[derive(Debug)]
+pub struct Failure {
+    pub message: String,
+    pub details: Vec<String>,
+}
+
+impl Failure {
+    fn new<S>(message: S) -> Self where S: Into<String> {
+        Failure { message: message.into(), details: Vec::new() }
+    }
+
+    fn with_details<S>(message: S, details: Vec<String>) -> Self where S: Into<String> {
+        Failure { message: message.into(), details }
+    }
+
+    /// Log the failure, listing its details under it.
+    pub fn log(&self) {
+        if self.details.is_empty() {
+            error!("{}", self.message);
+            return;
+        }
+
+        error!("{}:", self.message);
+
+        for detail in &self.details {
+            error!("  - {}", detail);
+        }
+    }
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.details.is_empty() {
+            true => write!(f, "{}", self.message),
+            false => write!(f, "{}: {}", self.message, self.details.join(", ")),
+        }
+    }
 }
 
 impl Conform {
 
     pub fn execute(&self) {
+        if self.all {
+            batch::execute(self);
+            return;
+        }
+
         // discover the root directory of the current git repository
         let repo: Repository = Repository::open_from_env().map_err(|e| {
             error!("Unable to open a Git repository from the current directory: {}", e);
@@ -41,15 +191,326 @@ impl Conform {
             exit(1)
         }).into();
 
+        if let ProjectTemplate::Undo = self.template {
+            undo(&root, self.dry_run, self.force);
+            return;
+        }
+
+        // variables which aren't given or answered already are asked for, if there's anyone to ask
+        let mut prompt = Prompt::terminal();
+
+        match self.conform(&repo, &root, prompt.as_mut()) {
+            Ok(Outcome::Dirty(paths)) => {
+                Failure::with_details("The working tree has uncommitted changes, commit or stash them first, or use --force",
+                    paths).log();
+                exit(1)
+            }
+            // a check fails when the project doesn't conform, once every difference is listed
+            Ok(Outcome::Changed(_)) if self.check => exit(1),
+            Ok(_) => (),
+            Err(failure) => {
+                failure.log();
+                exit(1)
+            }
+        }
+    }
+
+    /// Conform the project at the given root of a repository, or only report or check what would
+    /// change. Nothing is written to a working tree with uncommitted changes unless forced.
+    pub fn conform(&self, repo: &Repository, root: &Path, prompt: Option<&mut Terminal>) -> Result<Outcome, Failure> {
+        // writing over uncommitted changes would leave nothing to tell them apart from the template's
+        if !self.dry_run && !self.check && !self.force {
+            let dirty = commit::dirty(repo)
+                .map_err(|e| Failure::new(format!("Unable to read the status of the working tree: {}", e)))?;
+
+            if !dirty.is_empty() {
+                return Ok(Outcome::Dirty(dirty));
+            }
+        }
+
+        let mut state = State::load(root)
+            .map_err(|e| Failure::new(format!("Unable to load conform state: {}", e)))?;
+
+        let mut answers = Answers::load(root)
+            .map_err(|e| Failure::new(format!("Unable to load conform answers: {}", e)))?;
+
+        let upgrade = match self.template {
+            ProjectTemplate::Upgrade => Some(ProjectTemplate::restore(&state)
+                .map_err(|e| Failure::new(format!("Unable to upgrade project: {}", e)))?),
+            _ => None,
+        };
+
+        let (template, private) = match upgrade {
+            Some((ref template, private)) => (template, private),
+            None => (&self.template, self.private),
+        };
+
+        let mut plan = RenderPlan::new(root).with_state(&state);
+
         // apply licensing
-        license::apply(&root, self.private).map_err(|e| {
-            error!("Unable to apply licensing: {}", e);
-            exit(1)
-        }).ok();
+        license::apply(&mut plan, private)
+            .map_err(|e| Failure::new(format!("Unable to apply licensing: {}", e)))?;
 
         // do the actual thing
-        match self.template {
-            ProjectTemplate::Ansible(ref t) => t.execute(&repo, &root),
+        let (errors, rendered) = match template {
+            ProjectTemplate::Ansible(ref t) => match t.answer(&mut answers, prompt) {
+                Ok(t) => (t.plan(repo, &mut plan, root), pack::Rendered::default()),
+                Err(e) => (vec![e], pack::Rendered::default()),
+            },
+            ProjectTemplate::Pack(ref t) => t.plan(&mut plan, root, Project::from_repository(repo).as_ref(), &mut answers,
+                prompt),
+            ProjectTemplate::Upgrade | ProjectTemplate::Undo => (Vec::new(), pack::Rendered::default()),
+        };
+
+        if let (Some(before), Some(after)) = (&state.source, &rendered.source) {
+            if before.commit != after.commit {
+                info!("Upgrading templates from {} to {}", before.commit, after.commit);
+            }
+        }
+
+        let errors = match errors.is_empty() {
+            true => None,
+            false => Some(Failure::with_details("Unable to render project",
+                errors.iter().map(|e| e.to_string()).collect())),
+        };
+
+        let conflicts = if upgrade.is_some() && !self.force {
+            plan.merge(&state).map_err(|e| Failure::new(format!("Unable to merge template changes: {}", e)))?
+        } else {
+            plan.protect(&state, self.force);
+            Vec::new()
+        };
+
+        if self.check {
+            let outcome = check(&plan, self.diff, errors.is_none());
+
+            return match errors {
+                Some(failure) => Err(failure),
+                None => Ok(outcome),
+            };
+        }
+
+        if let Some(failure) = errors {
+            return Err(failure);
+        }
+
+        let outcome = match plan.is_unchanged() {
+            true => Outcome::Unchanged,
+            false => Outcome::Changed(changed(&plan).len()),
+        };
+
+        if !self.dry_run && !plan.is_unchanged() {
+            snapshot(root, &plan)?;
+        }
+
+        apply(&plan, self.dry_run, self.diff).map_err(|e| Failure::new(format!("Unable to render project: {}", e)))?;
+
+        if self.dry_run {
+            return Ok(outcome);
+        }
+
+        state.template = Some(template.name().to_string());
+        state.options = options(template, private);
+        state.source = rendered.source;
+        state.record(&plan);
+
+        state.save(root).map_err(|e| Failure::new(format!("Unable to save conform state: {}", e)))?;
+        answers.save(root).map_err(|e| Failure::new(format!("Unable to save conform answers: {}", e)))?;
+
+        if !pack::run(root, &rendered.commands) {
+            return Err(Failure::new("Unable to run the commands of the template pack"));
+        }
+
+        if !conflicts.is_empty() {
+            return Err(Failure::with_details("Template changes conflict with local edits, resolve the conflict markers",
+                conflicts.iter().map(|p| plan.relative(p).display().to_string()).collect()));
+        }
+
+        if self.commit {
+            commit(repo, &plan, template, private, self.branch)?;
+        }
+
+        Ok(outcome)
+    }
+}
+
+/// Every file a plan creates, changes or removes.
+fn changed(plan: &RenderPlan) -> Vec<&PlannedFile> {
+    plan.files().iter().filter(|f| f.change != Change::Unchanged && f.change != Change::Skip).collect()
+}
+
+/// Commit everything a plan changed along with conform's own files, optionally on a fresh branch.
+fn commit(repo: &Repository, plan: &RenderPlan, template: &ProjectTemplate, private: bool, branch: bool)
+        -> Result<(), Failure> {
+    if branch {
+        let name = commit::branch_name(repo, Local::today().naive_local());
+
+        commit::switch(repo, &name).map_err(|e| Failure::new(format!("Unable to switch to branch {}: {}", name, e)))?;
+
+        info!("Switched to branch {}", name);
+    }
+
+    let paths: Vec<&Path> = changed(plan).into_iter().map(|f| plan.relative(&f.path)).collect();
+    let message = commit::message(&format!("Conform project to the {} template", template.name()),
+        &options(template, private));
+
+    let committed = commit::commit(repo, &paths, &message)
+        .map_err(|e| Failure::new(format!("Unable to commit the conformed project: {}", e)))?;
+
+    match committed {
+        Some(id) => info!("Committed {} {}", &id.to_string()[..7], message.lines().next().unwrap_or_default()),
+        None => info!("Nothing to commit, the project was conformed already."),
+    }
+
+    Ok(())
+}
+
+/// Snapshot every file a plan touches before it is applied, along with conform's own record of
+/// what it rendered, so that the plan can be undone.
+fn snapshot(root: &Path, plan: &RenderPlan) -> Result<(), Failure> {
+    let mut files: Vec<PathBuf> = vec![
+        plan.relative(&State::path(root)).to_path_buf(),
+        plan.relative(&Answers::path(root)).to_path_buf(),
+    ];
+
+    for file in changed(plan) {
+        let relative = plan.relative(&file.path).to_path_buf();
+
+        files.push(plan.relative(&State::base_path(root, &relative)).to_path_buf());
+        files.push(relative);
+    }
+
+    let directories: Vec<PathBuf> = plan.directories().iter().map(|d| plan.relative(d).to_path_buf()).collect();
+
+    let dir = Snapshot::take(root, &files, &directories)
+        .map_err(|e| Failure::new(format!("Unable to snapshot the project before changing it: {}", e)))?;
+
+    info!("Saved a snapshot to {}, use nfty project conform undo to restore it.", plan.relative(&dir).display());
+
+    Ok(())
+}
+
+/// Restore the most recent snapshot, or only report what it would restore in a dry run. Files
+/// which were edited since they were conformed are only overwritten or removed when forced.
+fn undo(root: &Path, dry_run: bool, force: bool) {
+    let dir = match Snapshot::latest(root) {
+        Ok(Some(dir)) => dir,
+        Ok(None) => {
+            error!("There is nothing to undo, conform has no snapshot of this project.");
+            exit(1)
+        }
+        Err(e) => {
+            error!("Unable to find snapshots of the project: {}", e);
+            exit(1)
+        }
+    };
+
+    let snapshot = Snapshot::load(&dir).map_err(|e| {
+        error!("Unable to restore {}: {}", dir.display(), e);
+        exit(1)
+    }).unwrap();
+
+    let edited = State::load(root).and_then(|state| snapshot.edited(root, &state)).map_err(|e| {
+        error!("Unable to find files edited since they were conformed: {}", e);
+        exit(1)
+    }).unwrap();
+
+    if !edited.is_empty() {
+        match dry_run || force {
+            true => edited.iter().for_each(|path| warn!("{} was edited since it was conformed, undoing loses its edits.", path)),
+            false => {
+                Failure::with_details("Files were edited since they were conformed, undo would lose the edits, use --force to undo anyway",
+                    edited).log();
+                exit(1)
+            }
         }
     }
+
+    if !dry_run {
+        Snapshot::restore(root, &dir).map_err(|e| {
+            error!("Unable to restore {}: {}", dir.display(), e);
+            exit(1)
+        }).unwrap();
+    }
+
+    let (restore, remove) = match dry_run {
+        true => ("Would restore", "Would remove"),
+        false => ("Restored", "Removed"),
+    };
+
+    // conform's own files are restored too, but only mentioned when debugging
+    let log = |verb: &str, path: &String| match Path::new(path).starts_with(PROJECT_CONFIG_DIR) {
+        true => debug!("{} {}", verb, path),
+        false => info!("{} {}", verb, path),
+    };
+
+    snapshot.saved.iter().for_each(|path| log(restore, path));
+    snapshot.created.iter().for_each(|path| log(remove, path));
+}
+
+/// The options used to conform the project, including those given to its template.
+pub fn options(template: &ProjectTemplate, private: bool) -> Value {
+    let mut options = match template.options() {
+        Value::Mapping(m) => m,
+        _ => Mapping::new(),
+    };
+
+    options.insert(Value::String("private".into()), Value::Bool(private));
+
+    Value::Mapping(options)
+}
+
+/// Apply a render plan, or only report it in a dry run, exiting if it can't be applied.
+pub fn apply_or_report(plan: &RenderPlan, dry_run: bool, diff: bool) {
+    apply(plan, dry_run, diff).map_err(|e| {
+        error!("Unable to render project: {}", e);
+        exit(1)
+    }).unwrap();
+}
+
+/// Apply a render plan, or only report it in a dry run. Diffs are shown before anything is written.
+fn apply(plan: &RenderPlan, dry_run: bool, diff: bool) -> io::Result<()> {
+    if dry_run {
+        plan.report(diff);
+        return Ok(());
+    }
+
+    if diff {
+        print!("{}", plan.diff());
+    }
+
+    plan.apply()
+}
+
+/// Compare a render plan with what is on disk, listing every file which differs from its template.
+fn check(plan: &RenderPlan, diff: bool, rendered: bool) -> Outcome {
+    if diff {
+        print!("{}", plan.diff());
+    }
+
+    let drifted = plan.files().iter().filter(|f| f.change != Change::Unchanged).collect::<Vec<_>>();
+
+    if plan.is_unchanged() {
+        if rendered {
+            info!("Project conforms to its template.");
+        }
+
+        return Outcome::Unchanged;
+    }
+
+    error!("Project does not conform to its template:");
+
+    for dir in plan.directories() {
+        error!("  - would create {}/", plan.relative(dir).display());
+    }
+
+    for file in &drifted {
+        match file.change {
+            Change::Skip => error!("  - {} was edited since it was last rendered",
+                plan.relative(&file.path).display()),
+            change => error!("  - would {} {}", change, plan.relative(&file.path).display()),
+        }
+    }
+
+    Outcome::Changed(drifted.len() + plan.directories().len())
 }
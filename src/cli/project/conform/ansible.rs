@@ -1,22 +1,29 @@
 use crate::project::parse;
+use crate::project::render::RenderPlan;
 use crate::project::templates;
 use crate::project::templates::WritableTemplate;
+use crate::project::templates::answers::{self, Answers, Terminal};
+use crate::project::templates::pack::{self, Variable, VariableType, Variables};
 use crate::project::templates::ansible::DockerComposeMachine;
 use crate::project::templates::ansible::GalaxyRole;
 
 use git2::Repository;
 
-use log::{error, debug, info, warn};
+use serde_derive::{Deserialize, Serialize};
 
+use log::{debug, info, warn};
+
+use serde_yaml::Value;
+
+use std::collections::BTreeMap;
 use std::fs;
 use std::io;
 use std::iter::Iterator;
 use std::path::Path;
-use std::process::exit;
 
 use structopt::StructOpt;
 
-#[derive(Debug, StructOpt)]
+#[derive(Clone, Debug, Deserialize, Serialize, StructOpt)]
 pub struct Ansible {
     /// Disable support for Ubuntu 18.04.
     #[structopt(long = "disable-bionic")]
@@ -39,87 +46,132 @@ pub struct Ansible {
     /// Enable docker-in-docker support for Docker containers.
     #[structopt(long = "enable-dind")]
     pub enable_dind: bool,
-    /// The version of Python to use.
-    #[structopt(long = "python-version", default_value = "3.6.8")]
-    pub python_version: String,
+    /// The version of Python to use, 3.6.8 unless given.
+    #[structopt(long = "python-version")]
+    pub python_version: Option<String>,
+    /// Set an option, as name=value, e.g. disable_bionic=no to turn an option recorded in the
+    /// project's answers back off. Options set take precedence over flags.
+    #[structopt(short = "s", long = "set", number_of_values = 1)]
+    #[serde(skip)]
+    pub set: Vec<String>,
 }
 
+/// The version of Python to use when none is given.
+static DEFAULT_PYTHON_VERSION: &str = "3.6.8";
+
 impl Ansible {
-    pub fn execute(&self, _repo: &Repository, root: &Path) {
-        info!("Conforming Ansible role project.");
+    /// The options of the template as variables, which are asked for when they aren't given.
+    fn variables() -> Variables {
+        let disable = |name: &str| Variable::new(VariableType::Bool, Some(Value::Bool(false)),
+            &format!("Disable support for {}?", name));
+
+        vec![
+            ("disable_bionic", disable("Ubuntu 18.04")),
+            ("disable_centos7", disable("CentOS 7")),
+            ("disable_juno", disable("elementary 5.0 Juno")),
+            ("disable_loki", disable("elementary 0.4 Loki")),
+            ("disable_trusty", disable("Ubuntu 14.04")),
+            ("disable_xenial", disable("Ubuntu 16.04")),
+            ("enable_dind", Variable::new(VariableType::Bool, Some(Value::Bool(false)),
+                "Enable docker-in-docker support for Docker containers?")),
+            ("python_version", Variable::new(VariableType::String, Some(Value::from(DEFAULT_PYTHON_VERSION)),
+                "The version of Python to use")),
+        ].into_iter().map(|(name, variable)| (name.to_string(), variable)).collect()
+    }
 
-        // render standard vagrant stuff
-        self.render_vagrantfile(root);
-        self.render_playbook(root);
-        self.render_ansible_cfg(root);
-        self.render_galaxy_reqs(root);
+    /// Fill in the options which weren't given from the project's answers, or by asking for them,
+    /// recording them in the answers. Flags which weren't passed count as not given, so an option
+    /// is only turned off again by setting it.
+    pub fn answer(&self, answers: &mut Answers, prompt: Option<&mut Terminal>) -> io::Result<Ansible> {
+        let mut given: BTreeMap<String, Value> = match serde_yaml::to_value(self) {
+            Ok(Value::Mapping(m)) => m.into_iter()
+                .filter(|(_, v)| v.as_bool() != Some(false) && !v.is_null())
+                .filter_map(|(k, v)| k.as_str().map(|k| (k.to_string(), v)))
+                .collect(),
+            _ => BTreeMap::new(),
+        };
 
-        // docker compose for vms
-        self.render_docker_compose(root);
+        let variables = Ansible::variables();
 
-        // git ignore
-        self.render_gitignore(root);
+        let set = pack::parse_assignments("the ansible template", &variables, &self.set)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, format!("{:#}", e)))?;
+        given.extend(set);
 
-        // python requirements (basically include the ansible egg)
-        self.render_python_requirements(root);
+        answers::answer(&variables, &mut given, answers.templates.get("ansible"), prompt)?;
 
-        // travis
-        self.render_travis_cfg(root);
+        let resolved = pack::resolve("the ansible template", &variables, &given).map_err(|e| io::Error::other(e.to_string()))?;
+        answers.templates.insert("ansible".to_string(), given);
 
-        // makefile
-        self.render_makefile(root);
+        serde_yaml::from_value(Value::Mapping(resolved)).map_err(io::Error::other)
+    }
 
-        // alright time for tests/
-        if !root.join("tests").is_dir() {
-            info!("Creating tests/ directory.");
-            fs::create_dir(root.join("tests")).map_err(|e| {
-                error!("Unable to create tests/ directory: {}", e);
-                exit(1)
-            }).unwrap();
-        }
+    /// Plan every file in an Ansible role project, returning every error encountered rather than
+    /// stopping at the first.
+    pub fn plan(&self, _repo: &Repository, plan: &mut RenderPlan, root: &Path) -> Vec<io::Error> {
+        info!("Conforming Ansible role project.");
 
-        self.render_test_ansible_cfg(root);
-        self.render_test_inventory(root);
-        self.render_test_makefile(root);
-        self.render_test_galaxy_reqs(root);
-        self.render_test_gitignore(root);
-    }
+        vec![
+            // render standard vagrant stuff
+            self.render_vagrantfile(plan),
+            self.render_playbook(plan),
+            self.render_ansible_cfg(plan),
+            self.render_galaxy_reqs(plan),
 
-    fn write_or_die<T>(&self, root: &Path, dest: &Path, template: T) where T: WritableTemplate {
-        let base = dest.strip_prefix(root).unwrap();
+            // docker compose for vms
+            self.render_docker_compose(plan),
 
-        info!("Rendering {}", base.display());
+            // git ignore
+            self.render_gitignore(plan),
 
-        template.write(dest).map_err(|e| {
-            error!("Unable to write {}: {}", dest.strip_prefix(root).unwrap().display(), e);
-            exit(1)
-        }).unwrap();
+            // python requirements (basically include the ansible egg)
+            self.render_python_requirements(plan),
+
+            // travis
+            self.render_travis_cfg(plan),
+
+            // makefile
+            self.render_makefile(plan),
+
+            // alright time for tests/, which is created when the plan is applied
+            self.render_test_ansible_cfg(plan),
+            self.render_test_inventory(plan),
+            self.render_test_makefile(plan),
+            self.render_test_galaxy_reqs(plan, root),
+            self.render_test_gitignore(plan),
+        ].into_iter().filter_map(|r| r.err()).collect()
+    }
+
+    /// Add a template at its default path.
+    fn add_default<T>(&self, plan: &mut RenderPlan, template: T) -> io::Result<()> where T: WritableTemplate {
+        plan.add_default(&template).map_err(|e| {
+            io::Error::new(e.kind(), format!("unable to render {}: {}", T::default_path().unwrap_or(template.name()), e))
+        })
     }
 
-    fn render_vagrantfile(&self, root: &Path) {
-        self.write_or_die(root, &root.join("Vagrantfile"), templates::vagrant::Vagrantfile::new());
+    fn render_vagrantfile(&self, plan: &mut RenderPlan) -> io::Result<()> {
+        self.add_default(plan, templates::vagrant::Vagrantfile::new())
     }
 
-    fn render_playbook(&self, root: &Path) {
-        self.write_or_die(root, &root.join("vagrant.yml"), templates::vagrant::VagrantPlaybook::new()
+    fn render_playbook(&self, plan: &mut RenderPlan) -> io::Result<()> {
+        self.add_default(plan, templates::vagrant::VagrantPlaybook::new()
             .docker()
-            .python(self.python_version.as_str())
-        );
+            .python(self.python_version.as_deref().unwrap_or(DEFAULT_PYTHON_VERSION))
+        )
     }
 
-    fn render_ansible_cfg(&self, root: &Path) {
-        self.write_or_die(root, &root.join("ansible.cfg"), templates::ansible::AnsibleConfig::new()
-            .role_path(".ansible/galaxy-roles".to_string()));
+    fn render_ansible_cfg(&self, plan: &mut RenderPlan) -> io::Result<()> {
+        self.add_default(plan, templates::ansible::AnsibleConfig::new()
+            .role_path(".ansible/galaxy-roles".to_string()))
     }
 
-    fn render_galaxy_reqs(&self, root: &Path) {
-        self.write_or_die(root, &root.join("requirements.yml"), templates::ansible::AnsibleRequirements::new()
+    fn render_galaxy_reqs(&self, plan: &mut RenderPlan) -> io::Result<()> {
+        self.add_default(plan, templates::ansible::AnsibleRequirements::new()
             .role(GalaxyRole::new("naftulikay.vagrant-docker", Some("vagrant-docker"), None))
             .role(GalaxyRole::new("naftulikay.vagrant-python-dev", Some("vagrant-python-dev"), None))
-        );
+        )
     }
 
-    fn render_docker_compose(&self, root: &Path) {
+    fn render_docker_compose(&self, plan: &mut RenderPlan) -> io::Result<()> {
         let mut cfg = templates::ansible::DockerComposeConfig::new();
 
         if self.enable_dind {
@@ -151,61 +203,49 @@ impl Ansible {
         }
 
         if cfg.machines.len() == 0 {
-            error!("No machines found to add to docker-compose.yml.");
-            exit(1)
+            return Err(io::Error::other("no machines found to add to docker-compose.yml"));
         }
 
-        cfg.write(&root.join("docker-compose.yml")).map_err(|e| {
-            error!("Unable to render docker-compose.yml: {}", e);
-            exit(1)
-        }).unwrap();
+        self.add_default(plan, cfg)
     }
 
-    fn render_gitignore(&self, root: &Path) {
-        self.write_or_die(root, &root.join(".gitignore"), templates::git::GitIgnore::new().native().python());
+    fn render_gitignore(&self, plan: &mut RenderPlan) -> io::Result<()> {
+        self.add_default(plan, templates::git::GitIgnore::new().native().python())
     }
 
-    fn render_python_requirements(&self, root: &Path) {
-        let dest = root.join("requirements.txt");
+    fn render_python_requirements(&self, plan: &mut RenderPlan) -> io::Result<()> {
+        let template = templates::python::PythonRequirements::new();
+        let dest = plan.default_dest(&template)?;
 
-        self.write_or_die(root, &root.join("requirements.txt"), templates::python::PythonRequirements::load(&dest)
-            .unwrap_or(templates::python::PythonRequirements::new())
+        self.add_default(plan, templates::python::PythonRequirements::load(&dest)
+            .unwrap_or(template)
             .requirement("ansible".to_string())
-        );
+        )
     }
 
-    fn render_travis_cfg(&self, root: &Path) {
-        self.write_or_die(root, &root.join(".travis.yml"), templates::ansible::TravisConfig::new());
+    fn render_travis_cfg(&self, plan: &mut RenderPlan) -> io::Result<()> {
+        self.add_default(plan, templates::ansible::TravisConfig::new())
     }
 
-    fn render_makefile(&self, root: &Path) {
-        self.write_or_die(root, &root.join("Makefile"), templates::ansible::Makefile::new());
+    fn render_makefile(&self, plan: &mut RenderPlan) -> io::Result<()> {
+        self.add_default(plan, templates::ansible::Makefile::new())
     }
 
-    fn render_test_makefile(&self, root: &Path) {
-        self.write_or_die(root, &root.join("tests").join("Makefile"), templates::ansible::TestsMakefile::new());
+    fn render_test_makefile(&self, plan: &mut RenderPlan) -> io::Result<()> {
+        self.add_default(plan, templates::ansible::TestsMakefile::new())
     }
 
-    fn render_test_ansible_cfg(&self, root: &Path) {
-        self.write_or_die(root, &root.join("tests").join("ansible.cfg"), templates::ansible::AnsibleConfig::new()
+    fn render_test_ansible_cfg(&self, plan: &mut RenderPlan) -> io::Result<()> {
+        self.add_default(plan, templates::ansible::TestsAnsibleConfig::new()
             .role_path(".ansible/roles".to_string())
-            .role_path(".ansible/galaxy-roles".to_string()));
+            .role_path(".ansible/galaxy-roles".to_string()))
     }
 
-    fn render_test_gitignore(&self, root: &Path) {
-        self.write_or_die(root, &root.join("tests").join(".gitignore"), templates::ansible::TestsGitIgnore::new());
+    fn render_test_gitignore(&self, plan: &mut RenderPlan) -> io::Result<()> {
+        self.add_default(plan, templates::ansible::TestsGitIgnore::new())
     }
 
-    fn render_test_inventory(&self, root: &Path) {
-        let dir = &root.join("tests").join("inventory");
-
-        if !dir.is_dir() {
-            fs::create_dir(dir).map_err(|e| {
-                error!("Unable to create {}: {}", dir.display(), e);
-                exit(1)
-            }).unwrap();
-        }
-
+    fn render_test_inventory(&self, plan: &mut RenderPlan) -> io::Result<()> {
         let mut template = templates::ansible::InventoryYaml::new();
 
         if !self.disable_bionic {
@@ -233,11 +273,10 @@ impl Ansible {
         }
 
         if template.hosts.len() == 0 {
-            error!("No hosts found to render into tests/inventory/hosts.yml.");
-            exit(1)
+            return Err(io::Error::other("no hosts found to render into tests/inventory/hosts.yml"));
         }
 
-        self.write_or_die(root, &dir.join("hosts.yml"), template);
+        self.add_default(plan, template)
     }
 
     fn load_role_reqs(&self, root: &Path) -> Vec<String> {
@@ -262,14 +301,12 @@ impl Ansible {
         }
     }
 
-    fn render_test_galaxy_reqs(&self, root: &Path) {
+    fn render_test_galaxy_reqs(&self, plan: &mut RenderPlan, root: &Path) -> io::Result<()> {
         let mut roles = self.load_role_reqs(root);
         roles.push("naftulikay.degoss".to_string());
 
-        self.write_or_die(root, &root.join("tests").join("requirements.yml"),
-            templates::ansible::AnsibleRequirements::new().with_roles(roles.iter().map(|s| {
-                templates::ansible::GalaxyRole::from_src(s.clone())
-            }).collect())
-        );
+        self.add_default(plan, templates::ansible::TestsAnsibleRequirements::new().with_roles(roles.iter().map(|s| {
+            templates::ansible::GalaxyRole::from_src(s.clone())
+        }).collect()))
     }
 }
@@ -0,0 +1,113 @@
+#[cfg(test)]
+mod tests;
+
+use super::{Conform, Failure, Outcome, ProjectTemplate};
+
+use crate::project::Project;
+
+use git2::Repository;
+
+use glob::Pattern;
+
+use log::{error, info};
+
+use rayon::prelude::*;
+
+use std::path::Path;
+use std::process::exit;
+
+/// Conform every local project matching the filter in parallel, then summarize what became of each.
+pub fn execute(conform: &Conform) {
+    if let ProjectTemplate::Undo = conform.template {
+        error!("Undo restores one project at a time, run it within the project rather than with --all.");
+        exit(1);
+    }
+
+    let filter = conform.filter.as_ref().map(|f| Pattern::new(f).map_err(|e| {
+        error!("Invalid filter {}: {}", f, e);
+        exit(1)
+    }).unwrap());
+
+    let projects: Vec<Project> = Project::all_local().map_err(|e| {
+        error!("Unable to list local projects: {}", e);
+        exit(1)
+    }).unwrap().into_iter().filter(|p| filter.as_ref().is_none_or(|f| p.matches(f))).collect();
+
+    if projects.is_empty() {
+        info!("No local projects to conform.");
+        return;
+    }
+
+    info!("Conforming {} projects.", projects.len());
+
+    // a pack in a repository is fetched once for every project, rather than by each of them at once
+    let mut conform = conform.clone();
+
+    if let ProjectTemplate::Pack(ref pack) = conform.template {
+        conform.template = ProjectTemplate::Pack(pack.fetched().map_err(|e| {
+            error!("Unable to fetch the template pack: {:#}", e);
+            exit(1)
+        }).unwrap());
+    }
+
+    let results: Vec<(String, Result<Outcome, Failure>)> = projects.par_iter()
+        .map(|p| (p.name(), conform_at(&conform, &p.name(), &p.dir())))
+        .collect();
+
+    print!("{}", summary(&results, conform.dry_run || conform.check));
+
+    let failed = results.iter().any(|(_, r)| r.is_err());
+    let drifted = conform.check && results.iter().any(|(_, r)| matches!(r, Ok(Outcome::Changed(_))));
+
+    if failed || drifted {
+        exit(1);
+    }
+}
+
+/// Conform a single project. Nothing is asked for, as there would be no telling which project a
+/// question is about, so variables which aren't given or answered already take their defaults.
+fn conform_at(conform: &Conform, name: &str, dir: &Path) -> Result<Outcome, Failure> {
+    info!("Conforming {}", name);
+
+    let repo = Repository::open(dir).map_err(|e| Failure::new(format!("Unable to open the Git repository: {}", e)))?;
+
+    conform.conform(&repo, dir, None)
+}
+
+/// A table of what became of each project, followed by how many projects came to each outcome.
+fn summary(results: &[(String, Result<Outcome, Failure>)], hypothetical: bool) -> String {
+    let width = results.iter().map(|(name, _)| name.len()).chain(Some("PROJECT".len())).max().unwrap_or(0);
+
+    let mut table = format!("\n{:<width$}  RESULT\n", "PROJECT", width = width);
+    let (mut changed, mut unchanged, mut skipped, mut failed) = (0, 0, 0, 0);
+
+    for (name, result) in results {
+        let status = match result {
+            Ok(Outcome::Changed(files)) => {
+                changed += 1;
+
+                format!("{}, {} {}", if hypothetical { "would change" } else { "changed" }, files,
+                    if *files == 1 { "file" } else { "files" })
+            }
+            Ok(Outcome::Unchanged) => {
+                unchanged += 1;
+                "unchanged".to_string()
+            }
+            Ok(Outcome::Dirty(_)) => {
+                skipped += 1;
+                "skipped, uncommitted changes".to_string()
+            }
+            Err(failure) => {
+                failed += 1;
+                format!("failed, {}", failure)
+            }
+        };
+
+        table.push_str(&format!("{:<width$}  {}\n", name, status, width = width));
+    }
+
+    table.push_str(&format!("\n{} {}, {} unchanged, {} skipped, {} failed\n", changed,
+        if hypothetical { "would change" } else { "changed" }, unchanged, skipped, failed));
+
+    table
+}
@@ -0,0 +1,41 @@
+use super::*;
+
+fn results() -> Vec<(String, Result<Outcome, Failure>)> {
+    vec![
+        ("github.com/naftulikay/nfty".to_string(), Ok(Outcome::Changed(1))),
+        ("github.com/naftulikay/ansible-role-rust-dev".to_string(), Ok(Outcome::Changed(3))),
+        ("github.com/naftulikay/titan".to_string(), Ok(Outcome::Unchanged)),
+        ("github.com/naftulikay/vim".to_string(), Ok(Outcome::Dirty(vec!["README.md".to_string()]))),
+        ("github.com/naftulikay/broken".to_string(), Err(Failure::new("Unable to render project"))),
+    ]
+}
+
+#[test]
+fn test_summary() {
+    assert_eq!("
+PROJECT                                      RESULT
+github.com/naftulikay/nfty                   changed, 1 file
+github.com/naftulikay/ansible-role-rust-dev  changed, 3 files
+github.com/naftulikay/titan                  unchanged
+github.com/naftulikay/vim                    skipped, uncommitted changes
+github.com/naftulikay/broken                 failed, Unable to render project
+
+2 changed, 1 unchanged, 1 skipped, 1 failed
+", summary(&results(), false));
+}
+
+#[test]
+fn test_summary_hypothetical() {
+    let summary = summary(&results(), true);
+
+    // dry runs and checks only say what would change
+    assert!(summary.contains("github.com/naftulikay/nfty                   would change, 1 file\n"));
+    assert!(summary.ends_with("\n2 would change, 1 unchanged, 1 skipped, 1 failed\n"));
+}
+
+#[test]
+fn test_summary_short_names() {
+    // the column is at least as wide as its heading
+    assert_eq!("\nPROJECT  RESULT\na/b/c    unchanged\n\n0 changed, 1 unchanged, 0 skipped, 0 failed\n",
+        summary(&[("a/b/c".to_string(), Ok(Outcome::Unchanged))], false));
+}
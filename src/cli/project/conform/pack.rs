@@ -0,0 +1,178 @@
+use crate::project::hooks::packs;
+use crate::project::render::RenderPlan;
+use crate::project::state::SourceState;
+use crate::project::templates::answers::{self, Answers, Terminal};
+use crate::project::templates::pack::{self, Pack};
+use crate::project::templates::pack::remote::{Checkout, PackRepository, PACK_CACHE};
+use crate::project::Project;
+
+use log::{error, info, warn};
+
+use serde_derive::{Deserialize, Serialize};
+
+use std::io;
+use std::path::Path;
+use std::process::Command;
+
+use structopt::StructOpt;
+
+#[derive(Clone, Debug, Deserialize, Serialize, StructOpt)]
+pub struct PackTemplate {
+    /// The name of a template pack, or the path of a directory containing a pack.yml. Packs fetched
+    /// with --from are found by their directory within the repository.
+    pub pack: String,
+    /// Fetch the pack from a git repository, given as owner/repository or a URL, optionally followed
+    /// by @ and a branch, tag or commit. Clones are cached in ~/.cache/nfty/packs.
+    #[structopt(long = "from")]
+    #[serde(default)]
+    pub from: Option<String>,
+    /// Set a variable of the pack, as name=value. Lists are comma separated.
+    #[structopt(short = "s", long = "set", number_of_values = 1)]
+    #[serde(default)]
+    pub set: Vec<String>,
+    /// Run the pack's commands even though it comes from the project or a remote repository and the
+    /// project isn't trusted by the user's hook pack. Never recorded, so upgrades don't run them.
+    #[structopt(long = "trust")]
+    #[serde(skip)]
+    pub trust: bool,
+    /// The checkout of the repository given with --from, if it was fetched beforehand.
+    #[structopt(skip)]
+    #[serde(skip)]
+    pub checkout: Option<Checkout>,
+}
+
+/// What is left to do once the plan of a pack is applied.
+#[derive(Default)]
+pub struct Rendered {
+    /// The commands to run in the project root.
+    pub commands: Vec<String>,
+    /// The revision of the repository the pack was fetched from, if it was fetched.
+    pub source: Option<SourceState>,
+}
+
+impl PackTemplate {
+    /// Fetch the repository given with --from now, so that the pack can be loaded for any number of
+    /// projects from the one checkout.
+    pub fn fetched(&self) -> anyhow::Result<PackTemplate> {
+        let mut template = self.clone();
+
+        if let (Some(from), None) = (self.from.as_ref(), self.checkout.as_ref()) {
+            template.checkout = Some(fetch(from)?);
+        }
+
+        Ok(template)
+    }
+
+    /// Plan every file in the pack, returning every error encountered rather than stopping at the
+    /// first, along with what is left to do once the plan is applied.
+    pub fn plan(&self, plan: &mut RenderPlan, root: &Path, project: Option<&Project>, answers: &mut Answers,
+            prompt: Option<&mut Terminal>) -> (Vec<io::Error>, Rendered) {
+        // projects conformed in parallel share a clone, so nothing else checks it out until the pack is read
+        let lock = match (self.from.as_ref(), self.checkout.as_ref()) {
+            (Some(from), None) => PackRepository::parse(from).ok().map(|r| r.lock(&PACK_CACHE)),
+            _ => None,
+        };
+
+        let _guard = lock.as_ref().map(|l| l.lock());
+
+        let (pack, variables, source) = match self.load(root, answers, prompt) {
+            Ok(loaded) => loaded,
+            Err(e) => return (vec![io::Error::other(format!("{:#}", e))], Rendered::default()),
+        };
+
+        info!("Conforming project to the {} template pack.", pack.name);
+
+        let errors = pack.plan(plan, &variables).into_iter().map(|e| io::Error::other(format!("{:#}", e))).collect();
+
+        let commands = match pack.commands(root, &variables) {
+            Ok(commands) => commands,
+            Err(e) => return (vec![io::Error::other(format!("{:#}", e))], Rendered::default()),
+        };
+
+        match self.permits(&pack, project) {
+            Ok(true) => (errors, Rendered { commands, source }),
+            Ok(false) => {
+                if !commands.is_empty() {
+                    warn!("Not running the commands of the {} template pack, it isn't one of nfty's or your own and \
+                        the project isn't trusted, pass --trust to run them:", pack.name);
+
+                    for command in &commands {
+                        warn!("  {}", command);
+                    }
+                }
+
+                (errors, Rendered { commands: Vec::new(), source })
+            }
+            Err(e) => (vec![e], Rendered::default()),
+        }
+    }
+
+    /// Determine whether the commands of the pack may be run in the given project: those of nfty's
+    /// and the user's own packs always are, those of any other only with --trust or in projects the
+    /// user's hook pack trusts.
+    fn permits(&self, pack: &Pack, project: Option<&Project>) -> io::Result<bool> {
+        if pack.is_trusted() || self.trust {
+            return Ok(true);
+        }
+
+        packs::is_trusted(project)
+    }
+
+    /// Load the pack, and resolve its variables from those set, the project's answers, and the
+    /// answers to any prompt, recording them in the answers.
+    fn load(&self, root: &Path, answers: &mut Answers, prompt: Option<&mut Terminal>)
+            -> anyhow::Result<(Pack, serde_yaml::Mapping, Option<SourceState>)> {
+        let (pack, source) = match self.from {
+            Some(ref from) => {
+                let checkout = match self.checkout {
+                    Some(ref checkout) => checkout.clone(),
+                    None => fetch(from)?,
+                };
+
+                let source = SourceState { repository: from.clone(), commit: checkout.commit };
+                // the pack is somewhere within the repository, never outside of the clone
+                (Pack::load(&pack::within(&checkout.dir, &self.pack)?)?, Some(source))
+            }
+            None => (Pack::find(&self.pack, root)?, None),
+        };
+
+        let mut given = pack.parse_assignments(&self.set)?;
+        answers::answer(&pack.variables, &mut given, answers.packs.get(&pack.name), prompt)?;
+
+        let variables = pack.resolve(&given)?;
+        answers.packs.insert(pack.name.clone(), given);
+
+        Ok((pack, variables, source))
+    }
+}
+
+/// Fetch the repository a pack is in, given with --from.
+fn fetch(from: &str) -> anyhow::Result<Checkout> {
+    let repository = PackRepository::parse(from)?;
+    let checkout = repository.fetch(&PACK_CACHE)?;
+
+    info!("Fetched {} at {}", repository, checkout.commit);
+
+    Ok(checkout)
+}
+
+/// Run the commands of a rendered pack in the project root, stopping at the first which fails.
+pub fn run(root: &Path, commands: &[String]) -> bool {
+    for command in commands {
+        info!("Running {}", command);
+
+        match Command::new("sh").arg("-c").arg(command).current_dir(root).status() {
+            Ok(status) if status.success() => (),
+            Ok(status) => {
+                error!("{} failed: {}", command, status);
+                return false;
+            }
+            Err(e) => {
+                error!("Unable to run {}: {}", command, e);
+                return false;
+            }
+        }
+    }
+
+    true
+}
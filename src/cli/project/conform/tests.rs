@@ -0,0 +1,289 @@
+use super::*;
+
+use crate::project::templates::pack::Pack;
+use crate::project::templates::pack::remote::Checkout;
+use crate::testing::{self, Scratch};
+
+use std::fs;
+
+/// A project repository and a template pack rendering a greeting into it.
+struct Fixture {
+    _scratch: Scratch,
+    repo: Repository,
+    root: PathBuf,
+    pack: PathBuf,
+}
+
+impl Fixture {
+    fn new(test: &str, template: &str) -> Self {
+        let scratch = Scratch::new(test);
+        let root = scratch.join("project");
+        let pack = scratch.join("pack");
+
+        let repo = testing::repository(&root);
+
+        fs::create_dir_all(&pack).unwrap();
+        fs::write(pack.join("pack.yml"), "name: greeting\nfiles:\n  - template: greeting.txt.j2\n").unwrap();
+        fs::write(pack.join("greeting.txt.j2"), template).unwrap();
+
+        Fixture { _scratch: scratch, repo, root, pack }
+    }
+
+    /// Conform the project to the pack, or only check it.
+    fn conform(&self, check: bool) -> Result<Outcome, Failure> {
+        self.conform_trusting(check, false)
+    }
+
+    /// Conform the project to the pack, or only check it, optionally trusting the pack's commands.
+    fn conform_trusting(&self, check: bool, trust: bool) -> Result<Outcome, Failure> {
+        let conform = Conform {
+            private: true,
+            dry_run: false,
+            diff: false,
+            check,
+            force: true,
+            commit: false,
+            branch: false,
+            all: false,
+            filter: None,
+            template: ProjectTemplate::Pack(pack::PackTemplate {
+                pack: self.pack.display().to_string(),
+                from: None,
+                set: Vec::new(),
+                trust,
+                checkout: None,
+            }),
+        };
+
+        conform.conform(&self.repo, &self.root, None)
+    }
+}
+
+#[test]
+fn test_check_conforming() {
+    let fixture = Fixture::new("conform-check-conforming", "hello\n");
+
+    // checking writes nothing
+    assert_eq!(Outcome::Changed(1), fixture.conform(true).unwrap());
+    assert!(!fixture.root.join("greeting.txt").exists());
+
+    assert_eq!(Outcome::Changed(1), fixture.conform(false).unwrap());
+    assert_eq!(Outcome::Unchanged, fixture.conform(true).unwrap());
+}
+
+#[test]
+fn test_check_drifted() {
+    let fixture = Fixture::new("conform-check-drifted", "hello\n");
+    fixture.conform(false).unwrap();
+
+    // a file removed since it was rendered would be created again
+    fs::remove_file(fixture.root.join("greeting.txt")).unwrap();
+    assert_eq!(Outcome::Changed(1), fixture.conform(true).unwrap());
+}
+
+#[test]
+fn test_check_edited() {
+    let fixture = Fixture::new("conform-check-edited", "hello\n");
+    fixture.conform(false).unwrap();
+
+    // a file edited since it was rendered is skipped rather than overwritten, but still differs
+    fs::write(fixture.root.join("greeting.txt"), "hello there\n").unwrap();
+    assert_eq!(Outcome::Changed(1), fixture.conform(true).unwrap());
+    assert_eq!("hello there\n", fs::read_to_string(fixture.root.join("greeting.txt")).unwrap());
+}
+
+#[test]
+fn test_check_render_errors() {
+    let fixture = Fixture::new("conform-check-errors", "{{ greeting | no_such_filter }}\n");
+
+    let failure = fixture.conform(true).unwrap_err();
+
+    assert_eq!("Unable to render project", failure.message);
+    assert_eq!(1, failure.details.len());
+}
+
+#[test]
+fn test_untrusted_pack_commands() {
+    let fixture = Fixture::new("conform-untrusted-pack-commands", "Hello\n");
+    fs::write(fixture.pack.join("pack.yml"),
+        "name: greeting\nfiles:\n  - template: greeting.txt.j2\ncommands:\n  - run: touch ran\n").unwrap();
+
+    // a pack loaded by its path is neither nfty's nor the user's own
+    assert!(fixture.conform(false).is_ok());
+    assert!(fixture.root.join("greeting.txt").is_file());
+    assert!(!fixture.root.join("ran").exists());
+
+    assert!(fixture.conform_trusting(false, true).is_ok());
+    assert!(fixture.root.join("ran").is_file());
+}
+
+#[test]
+fn test_remote_pack_stays_within_checkout() {
+    let fixture = Fixture::new("conform-remote-pack", "pwned\n");
+    fs::write(fixture.pack.join("pack.yml"), "name: greeting\nfiles:\n  - template: greeting.txt.j2\n    dest: ../pwned.txt\n")
+        .unwrap();
+
+    let template = |pack: &str| pack::PackTemplate {
+        pack: pack.to_string(),
+        from: Some("naftulikay/packs".to_string()),
+        set: Vec::new(),
+        trust: false,
+        checkout: Some(Checkout { dir: fixture.root.join("clone"), commit: "0f3c8b6".to_string() }),
+    };
+
+    let plan = |template: pack::PackTemplate| {
+        let mut plan = RenderPlan::new(&fixture.root);
+        let (errors, _) = template.plan(&mut plan, &fixture.root, None, &mut Answers::default(), None);
+        (errors, plan)
+    };
+
+    // the pack can't be outside of the clone
+    let (errors, _) = plan(template("../pack"));
+    assert!(errors[0].to_string().contains("is outside of"), "{:?}", errors);
+    assert_eq!(1, plan(template(&fixture.pack.display().to_string())).0.len());
+
+    // nor through a symlink the repository has
+    fs::create_dir_all(fixture.root.join("clone")).unwrap();
+    std::os::unix::fs::symlink(&fixture.pack, fixture.root.join("clone/linked")).unwrap();
+    assert!(plan(template("linked")).0[0].to_string().contains("is outside of"));
+
+    // nor can what it renders be outside of the project
+    fs::create_dir_all(fixture.root.join("clone/pack")).unwrap();
+
+    for name in ["pack.yml", "greeting.txt.j2"] {
+        fs::copy(fixture.pack.join(name), fixture.root.join("clone/pack").join(name)).unwrap();
+    }
+
+    let (errors, plan) = plan(template("pack"));
+    assert!(errors[0].to_string().contains("is outside of"), "{:?}", errors);
+    assert!(plan.files().iter().all(|f| f.path.starts_with(&fixture.root)));
+}
+
+#[test]
+fn test_ansible_plan_paths() {
+    let scratch = Scratch::new("conform-ansible-paths");
+    let repo = testing::repository(&scratch);
+
+    fs::write(scratch.join("requirements.txt"), "requests\n").unwrap();
+
+    let ansible = ansible::Ansible {
+        disable_bionic: false,
+        disable_centos7: false,
+        disable_juno: false,
+        disable_loki: false,
+        disable_trusty: false,
+        disable_xenial: false,
+        enable_dind: false,
+        python_version: None,
+        set: Vec::new(),
+    };
+
+    let mut plan = RenderPlan::new(&scratch);
+    assert!(ansible.plan(&repo, &mut plan, &scratch).is_empty());
+
+    let planned = |path: &str| plan.files().iter()
+        .find(|f| f.path == scratch.join(path))
+        .and_then(|f| f.planned.clone())
+        .unwrap_or_else(|| panic!("{} isn't planned", path));
+
+    // the tests have their own configuration and requirements, rendered to their default paths
+    assert!(planned("tests/ansible.cfg").contains("roles_path = .ansible/roles:.ansible/galaxy-roles"));
+    assert!(planned("tests/requirements.yml").contains("- src: naftulikay.degoss"));
+    assert!(planned("ansible.cfg").contains("roles_path = .ansible/galaxy-roles\n"));
+
+    // requirements are added to those the project has already
+    assert_eq!("ansible\nrequests\n", planned("requirements.txt"));
+}
+
+#[test]
+fn test_ansible_pack_renders_as_builtin() {
+    let scratch = Scratch::new("conform-ansible-pack");
+    let repo = testing::repository(&scratch);
+
+    fs::create_dir_all(scratch.join("meta")).unwrap();
+    fs::write(scratch.join("meta/main.yml"), "galaxy_info:\n  name: example\n  author: naftulikay\n  src: naftulikay.example\n\
+        dependencies:\n  - naftulikay.python\n  - role: naftulikay.docker\n").unwrap();
+    fs::write(scratch.join("requirements.txt"), "requests\n# pinned\nboto3\n").unwrap();
+    fs::write(scratch.join(".gitignore"), "/local\n").unwrap();
+
+    let ansible = |enable_dind: bool, disable_juno: bool, python_version: Option<&str>| ansible::Ansible {
+        disable_bionic: false,
+        disable_centos7: false,
+        disable_juno,
+        disable_loki: false,
+        disable_trusty: false,
+        disable_xenial: false,
+        enable_dind,
+        python_version: python_version.map(|v| v.to_string()),
+        set: Vec::new(),
+    };
+
+    let options = [
+        (ansible(false, false, None), Vec::new()),
+        (ansible(true, true, Some("3.7.2")), vec!["enable_dind=yes", "machines=bionic,centos7,loki,trusty,xenial",
+            "python_version=3.7.2"]),
+    ];
+
+    for (ansible, set) in options {
+        let mut builtin = RenderPlan::new(&scratch);
+        assert!(ansible.plan(&repo, &mut builtin, &scratch).is_empty());
+
+        let pack = Pack::builtin("ansible").unwrap();
+        let set: Vec<String> = set.into_iter().map(|s| s.to_string()).collect();
+
+        let mut packed = RenderPlan::new(&scratch);
+        let errors = pack.plan(&mut packed, &pack.resolve(&pack.parse_assignments(&set).unwrap()).unwrap());
+        assert!(errors.is_empty(), "{:?}", errors);
+
+        assert_eq!(builtin.files().len(), packed.files().len());
+
+        // the tests depend on the role's dependencies, and existing requirements are kept
+        let planned = |path: &str| packed.files().iter().find(|f| f.path == scratch.join(path)).unwrap().planned.clone();
+
+        assert!(planned("tests/requirements.yml").unwrap().contains("- src: naftulikay.docker\n"));
+        assert_eq!(Some("ansible\nboto3\nrequests\n"), planned("requirements.txt").as_deref());
+
+        for expected in builtin.files() {
+            let actual = packed.files().iter().find(|f| f.path == expected.path)
+                .unwrap_or_else(|| panic!("the pack doesn't render {}", expected.path.display()));
+
+            assert_eq!(expected.planned, actual.planned, "{} differs", expected.path.display());
+            assert_eq!(expected.mode, actual.mode, "{} has another mode", expected.path.display());
+            assert_eq!(expected.overwrite, actual.overwrite, "{} is overwritten differently", expected.path.display());
+            assert_eq!(expected.template, actual.template);
+        }
+    }
+}
+
+#[test]
+fn test_ansible_answer_set() {
+    let mut answers = Answers::default();
+    answers.templates.insert("ansible".into(), vec![("disable_bionic".to_string(), Value::Bool(true))].into_iter().collect());
+
+    let ansible = |set: &[&str]| ansible::Ansible {
+        disable_bionic: false,
+        disable_centos7: false,
+        disable_juno: false,
+        disable_loki: false,
+        disable_trusty: false,
+        disable_xenial: false,
+        enable_dind: false,
+        python_version: None,
+        set: set.iter().map(|s| s.to_string()).collect(),
+    };
+
+    // replaying the answers keeps the option, as a flag which wasn't passed isn't given
+    assert!(ansible(&[]).answer(&mut answers, None).unwrap().disable_bionic);
+
+    // setting it turns it back off, and the answers record that
+    let answered = ansible(&["disable_bionic=no", "python_version=3.7"]).answer(&mut answers, None).unwrap();
+
+    assert!(!answered.disable_bionic);
+    assert_eq!(Some("3.7"), answered.python_version.as_deref());
+    assert_eq!(Some(&Value::Bool(false)), answers.templates["ansible"].get("disable_bionic"));
+
+    assert!(!ansible(&[]).answer(&mut answers, None).unwrap().disable_bionic);
+
+    assert!(ansible(&["disable_fedora=yes"]).answer(&mut answers, None).is_err());
+    assert!(ansible(&["disable_bionic=maybe"]).answer(&mut answers, None).is_err());
+}
@@ -0,0 +1,127 @@
+use crate::project::Project;
+use crate::project::hooks;
+
+use git2::Repository;
+
+use log::{error, info};
+
+use std::path::PathBuf;
+use std::process::exit;
+
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub enum Hooks {
+    /// Show the state of every hook nfty manages.
+    #[structopt(name = "status")]
+    Status(Scope),
+    /// Install or repair hooks.
+    #[structopt(name = "install")]
+    Install(Scope),
+    /// Remove hooks installed by nfty, restoring any hooks which were there before.
+    #[structopt(name = "uninstall")]
+    Uninstall(Scope),
+    /// Exit non-zero if any hooks are missing or differ from what nfty would install.
+    #[structopt(name = "verify")]
+    Verify(Scope),
+}
+
+#[derive(Debug, StructOpt)]
+pub struct Scope {
+    /// Run against every local project under the project root rather than the current repository.
+    #[structopt(short = "a", long = "all")]
+    pub all: bool,
+}
+
+impl Scope {
+    /// Resolve the working directories to operate on, along with their projects if known.
+    fn targets(&self) -> Vec<(PathBuf, Option<Project>)> {
+        if self.all {
+            return Project::all_local().map_err(|e| {
+                error!("Unable to list local projects: {}", e);
+                exit(1)
+            }).unwrap().into_iter().map(|p| (p.dir(), Some(p))).collect();
+        }
+
+        let repo = Repository::open_from_env().map_err(|e| {
+            error!("Unable to open a Git repository from the current directory: {}", e);
+            exit(1)
+        }).unwrap();
+
+        let dir = repo.workdir().unwrap_or_else(|| {
+            error!("Unable to find the root directory of this Git repository");
+            exit(1)
+        }).into();
+
+        vec![(dir, Project::from_repository(&repo))]
+    }
+}
+
+impl Hooks {
+    pub fn execute(&self) {
+        match self {
+            Hooks::Status(ref s) => self.status(s, false),
+            Hooks::Install(ref s) => self.install(s),
+            Hooks::Uninstall(ref s) => self.uninstall(s),
+            Hooks::Verify(ref s) => self.status(s, true),
+        }
+    }
+
+    fn install(&self, scope: &Scope) {
+        let mut failed = false;
+
+        for (dir, project) in scope.targets() {
+            info!("Installing hooks in {}", dir.display());
+
+            if let Err(e) = hooks::install(&dir, project.as_ref()) {
+                error!("Failed to install Git hooks in {}: {}", dir.display(), e);
+                failed = true;
+            }
+        }
+
+        if failed {
+            exit(1);
+        }
+    }
+
+    fn uninstall(&self, scope: &Scope) {
+        let mut failed = false;
+
+        for (dir, _) in scope.targets() {
+            info!("Uninstalling hooks from {}", dir.display());
+
+            if let Err(e) = hooks::uninstall(&dir) {
+                error!("Failed to uninstall Git hooks from {}: {}", dir.display(), e);
+                failed = true;
+            }
+        }
+
+        if failed {
+            exit(1);
+        }
+    }
+
+    /// Print the state of each hook. In quiet mode, only problems are printed and the process exits
+    /// non-zero if there were any.
+    fn status(&self, scope: &Scope, quiet: bool) {
+        let mut drifted = 0;
+
+        for (dir, project) in scope.targets() {
+            let statuses = hooks::status(&dir, project.as_ref()).map_err(|e| {
+                error!("Unable to read hooks in {}: {}", dir.display(), e);
+                exit(1)
+            }).unwrap();
+
+            drifted += statuses.iter().filter(|s| !s.is_current()).count();
+
+            for status in statuses.iter().filter(|s| !quiet || !s.is_current()) {
+                println!("{}: {:<8} {}", dir.display(), status.state, status.path.display());
+            }
+        }
+
+        if quiet && drifted > 0 {
+            error!("{} hook(s) are not current, run `nfty project hooks install` to repair.", drifted);
+            exit(1);
+        }
+    }
+}
@@ -1,16 +1,16 @@
 use anyhow::{anyhow, Result};
-use askama::Template;
-use chrono::{Datelike, NaiveDate, Utc};
-use nfty_derive::WritableTemplate;
+use chrono::{Datelike, Utc};
 use std::env;
 use std::fmt::{Display, Formatter};
-use std::fs;
-use std::io::Write;
 use std::path::PathBuf;
+use std::process::exit;
 use std::str::FromStr;
 use structopt::StructOpt;
 
-use crate::project::templates::WritableTemplate;
+use crate::project::render::RenderPlan;
+use crate::project::templates::license::{ApacheLicense, MitLicense, PrivateLicense};
+
+use super::conform::apply_or_report;
 
 #[derive(Debug, StructOpt)]
 pub struct License {
@@ -28,6 +28,12 @@ pub struct License {
     #[structopt(short = "a", long = "author", default_value = "Naftuli Kay")]
     /// The author/copyright holder for the license(s).
     pub license_holder: String,
+    /// Report what would be created, changed or removed without writing anything.
+    #[structopt(short = "n", long = "dry-run")]
+    pub dry_run: bool,
+    /// Show a unified diff of each file which is created, changed or removed.
+    #[structopt(long = "diff")]
+    pub diff: bool,
 }
 
 #[derive(Debug)]
@@ -75,8 +81,16 @@ impl FromStr for LicenseType {
 
 impl License {
     pub fn execute(&self) {
+        let cwd = env::current_dir();
+        let dir = self.project_dir.as_ref().unwrap_or(
+            cwd.as_ref()
+                .expect("could not get current working directory"),
+        );
+
+        let mut plan = RenderPlan::new(dir);
+
         if !self.no_clean {
-            if let Err(e) = self.clean() {
+            if let Err(e) = self.clean(&mut plan) {
                 log::error!("Unable to clean up existing license files: {}", e);
             }
         }
@@ -93,75 +107,62 @@ impl License {
 
         let multi_license = licenses.len() > 1;
 
-        let cwd = env::current_dir();
-        let dir = self.project_dir.as_ref().unwrap_or(
-            cwd.as_ref()
-                .expect("could not get current working directory"),
-        );
-
         for license_type in licenses {
-            match license_type {
+            let result = match license_type {
                 LicenseType::Apache2 => {
                     let template = ApacheLicense {
-                        author: self.license_holder.as_str(),
-                        date: Utc::today().naive_local(),
+                        author: self.license_holder.clone(),
+                        year: Utc::now().year(),
                     };
 
-                    let filename = if multi_license {
-                        "LICENSE-APACHE"
-                    } else {
-                        "LICENSE"
-                    };
+                    log::debug!("Rendering Apache Software License, Version 2.0...");
 
-                    log::info!(
-                        "Rendering Apache Software License, Version 2.0 to {}...",
-                        filename
-                    );
-                    template
-                        .write(&dir.join(filename))
-                        .expect("unable to render license");
+                    // a single license is the project's LICENSE
+                    if multi_license {
+                        plan.add_default(&template)
+                    } else {
+                        plan.add(&dir.join("LICENSE"), &template)
+                    }
                 }
                 LicenseType::Mit => {
                     let template = MitLicense {
-                        author: self.license_holder.as_str(),
-                        date: Utc::today().naive_local(),
+                        author: self.license_holder.clone(),
+                        year: Utc::now().year(),
                     };
 
-                    let filename = if multi_license {
-                        "LICENSE-MIT"
-                    } else {
-                        "LICENSE"
-                    };
+                    log::debug!("Rendering MIT License...");
 
-                    log::info!("Rendering MIT License to {}...", filename);
-                    template
-                        .write(&dir.join(filename))
-                        .expect("unable to render license");
+                    if multi_license {
+                        plan.add_default(&template)
+                    } else {
+                        plan.add(&dir.join("LICENSE"), &template)
+                    }
                 }
                 LicenseType::Private => {
                     let template = PrivateLicense {
-                        author: self.license_holder.as_str(),
-                        date: Utc::today().naive_local(),
+                        author: self.license_holder.clone(),
+                        year: Utc::now().year(),
                     };
 
-                    log::info!("Rendering private license...");
-                    template
-                        .write(&dir.join("LICENSE"))
-                        .expect("unable to render license");
+                    log::debug!("Rendering private license...");
+                    plan.add_default(&template)
                 }
                 LicenseType::Oss => unreachable!(),
-            }
+            };
+
+            result.map_err(|e| {
+                log::error!("Unable to render license: {}", e);
+                exit(1)
+            }).unwrap();
         }
+
+        apply_or_report(&plan, self.dry_run, self.diff);
     }
 
-    fn clean(&self) -> Result<()> {
-        log::info!("Removing all existing licenses before generating new ones.");
+    fn clean(&self, plan: &mut RenderPlan) -> Result<()> {
+        log::debug!("Removing all existing licenses before generating new ones.");
 
-        let cwd = env::current_dir();
-        let dir = self.project_dir.as_ref().unwrap_or(
-            cwd.as_ref()
-                .map_err(|e| anyhow!("unable to get current directory: {}", e))?,
-        );
+        let dir = plan.root().to_path_buf();
 
         // find all files in the given directory which start with `LICENSE` and remove them.
         for license_file in dir
@@ -178,10 +179,10 @@ impl License {
             })
             .map(|d| d.path())
         {
-            log::info!("Removing old license file {}", license_file.display());
-            fs::remove_file(&license_file).map_err(|e| {
+            log::debug!("Removing old license file {}", license_file.display());
+            plan.remove(&license_file).map_err(|e| {
                 anyhow!(
-                    "unable to remove license file {}: {}",
+                    "unable to read license file {}: {}",
                     license_file.display(),
                     e
                 )
@@ -191,24 +192,3 @@ impl License {
         Ok(())
     }
 }
-
-#[derive(Template, WritableTemplate)]
-#[template(path = "licenses/APACHE.j2")]
-pub struct ApacheLicense<'a> {
-    pub author: &'a str,
-    pub date: NaiveDate,
-}
-
-#[derive(Template, WritableTemplate)]
-#[template(path = "licenses/MIT.j2")]
-pub struct MitLicense<'a> {
-    pub author: &'a str,
-    pub date: NaiveDate,
-}
-
-#[derive(Template, WritableTemplate)]
-#[template(path = "licenses/PRIVATE.j2")]
-pub struct PrivateLicense<'a> {
-    pub author: &'a str,
-    pub date: NaiveDate,
-}
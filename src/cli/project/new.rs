@@ -0,0 +1,155 @@
+#[cfg(test)]
+mod tests;
+
+use super::conform::{self, Conform, ProjectTemplate};
+
+use crate::config::Config;
+use crate::project::Project;
+use crate::project::commit;
+use crate::project::templates::answers::Prompt;
+
+use log::{error, info, warn};
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::exit;
+
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub struct New {
+    /// The project to create, as owner/repository or any of the forms accepted by bring. It is
+    /// created in its directory under the project root.
+    pub project: String,
+    /// Project should be assumed to be private rather than the default, which is public.
+    #[structopt(short = "P", long = "private")]
+    pub private: bool,
+    #[structopt(subcommand)]
+    pub template: ProjectTemplate,
+}
+
+impl New {
+    pub fn execute(&self) {
+        let project = Project::from(&self.project).map_err(|_| {
+            error!("Unable to parse repository URL: {}", self.project);
+            exit(1)
+        }).unwrap();
+
+        if let ProjectTemplate::Upgrade | ProjectTemplate::Undo = self.template {
+            error!("A new project can only be created from the ansible template or a template pack.");
+            exit(1);
+        }
+
+        let dir = project.dir();
+
+        // an empty directory is fine, anything else is somebody's work
+        if fs::read_dir(&dir).map(|mut d| d.next().is_some()).unwrap_or(false) {
+            error!("Unable to create {}, {} exists already.", project.name(), dir.display());
+            exit(1);
+        }
+
+        let config = Config::load(&dir, Some(&project)).map_err(|e| {
+            error!("Unable to load configuration: {}", e);
+            exit(1)
+        }).unwrap();
+
+        let branch = config.branches.default_branch();
+
+        // anything created from here on is removed if creating the project fails
+        let created = Created::before(&dir);
+
+        let repo = project.init(&branch).map_err(|e| {
+            error!("Unable to initialize a Git repository in {}: {}", dir.display(), e);
+            created.abandon()
+        }).unwrap();
+
+        info!("Initialized {} on {} with origin {}", dir.display(), branch, project.url());
+
+        let conform = Conform {
+            private: self.private,
+            dry_run: false,
+            diff: false,
+            check: false,
+            force: false,
+            commit: false,
+            branch: false,
+            all: false,
+            filter: None,
+            template: self.template.clone(),
+        };
+
+        let mut prompt = Prompt::terminal();
+
+        conform.conform(&repo, &dir, prompt.as_mut()).map_err(|failure| {
+            failure.log();
+            created.abandon()
+        }).unwrap();
+
+        // hooks are installed once the project is rendered, so that they are chosen by what is in it
+        project.configure().map_err(|e| {
+            error!("Failed to install Git hooks: {}", e);
+            created.abandon()
+        }).unwrap();
+
+        let message = commit::message(&format!("Create {} from the {} template", project.repository(),
+            self.template.name()), &conform::options(&self.template, self.private));
+
+        let committed = commit::commit_all(&repo, &message).map_err(|e| {
+            error!("Unable to make the initial commit: {}", e);
+            created.abandon()
+        }).unwrap();
+
+        if let Some(id) = committed {
+            info!("Committed {} {}", &id.to_string()[..7], message.lines().next().unwrap_or_default());
+        }
+
+        info!("Created {} in {}", project.name(), dir.display());
+    }
+}
+
+/// What creating a project makes on disk, which is removed again if creating it fails part way,
+/// so that a half-initialized project doesn't stop it being created again.
+struct Created {
+    /// The outermost directory of the project's which didn't exist yet, if one didn't.
+    outermost: Option<PathBuf>,
+    /// The project's directory, which is emptied rather than removed if it existed already.
+    dir: PathBuf,
+}
+
+impl Created {
+    /// Take note of what exists before creating the project in the given directory.
+    fn before(dir: &Path) -> Self {
+        Created {
+            outermost: dir.ancestors().take_while(|d| !d.exists()).last().map(Path::to_path_buf),
+            dir: dir.to_path_buf(),
+        }
+    }
+
+    /// Remove everything created since.
+    fn remove(&self) -> io::Result<()> {
+        match self.outermost {
+            Some(ref outermost) if outermost.exists() => fs::remove_dir_all(outermost),
+            Some(_) => Ok(()),
+            None => fs::read_dir(&self.dir)?.try_for_each(|entry| {
+                let path = entry?.path();
+
+                if path.is_dir() && !path.symlink_metadata()?.file_type().is_symlink() {
+                    fs::remove_dir_all(path)
+                } else {
+                    fs::remove_file(path)
+                }
+            }),
+        }
+    }
+
+    /// Remove everything created since and exit, as creating the project failed.
+    fn abandon(&self) -> ! {
+        match self.remove() {
+            Ok(()) => info!("Removed the partially created project at {}", self.dir.display()),
+            Err(e) => warn!("Unable to remove the partially created project at {}: {}", self.dir.display(), e),
+        }
+
+        exit(1)
+    }
+}
@@ -0,0 +1,38 @@
+use super::*;
+
+use crate::testing::Scratch;
+
+#[test]
+fn test_created_removes_new_directories() {
+    let scratch = Scratch::new("new-created");
+    let dir = scratch.join("github.com").join("naftulikay").join("nfty");
+
+    fs::create_dir_all(scratch.join("github.com").join("someone-else")).unwrap();
+
+    let created = Created::before(&dir);
+    fs::create_dir_all(dir.join(".git")).unwrap();
+
+    created.remove().unwrap();
+
+    // only what didn't exist beforehand is removed
+    assert!(!scratch.join("github.com").join("naftulikay").exists());
+    assert!(scratch.join("github.com").join("someone-else").is_dir());
+}
+
+#[test]
+fn test_created_empties_existing_directory() {
+    let scratch = Scratch::new("new-existing");
+    let dir = scratch.join("nfty");
+
+    fs::create_dir_all(&dir).unwrap();
+
+    let created = Created::before(&dir);
+    fs::create_dir_all(dir.join(".git").join("hooks")).unwrap();
+    fs::write(dir.join("README.md"), "# nfty\n").unwrap();
+
+    created.remove().unwrap();
+
+    // an empty directory which was there already is left as it was
+    assert!(dir.is_dir());
+    assert_eq!(0, fs::read_dir(&dir).unwrap().count());
+}
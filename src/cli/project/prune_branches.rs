@@ -0,0 +1,55 @@
+use crate::config::Config;
+use crate::project::branches::{self, PruneOptions};
+
+use git2::Repository;
+
+use log::{error, info};
+
+use std::process::exit;
+
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub struct PruneBranches {
+    /// Show which branches would be deleted without deleting them.
+    #[structopt(short = "n", long = "dry-run")]
+    pub dry_run: bool,
+    /// Also delete remote-tracking branches which no longer exist on their remote.
+    #[structopt(long = "prune-gone")]
+    pub prune_gone: bool,
+    /// A glob pattern of branches to never delete. May be given multiple times, and replaces the
+    /// configured protected branches.
+    #[structopt(short = "p", long = "protect")]
+    pub protected: Vec<String>,
+}
+
+impl PruneBranches {
+    pub fn execute(&self) {
+        let repo = Repository::open_from_env().map_err(|e| {
+            error!("Unable to open a Git repository from the current directory: {}", e);
+            exit(1)
+        }).unwrap();
+
+        let config = Config::for_repository(&repo).map_err(|e| {
+            error!("Unable to load configuration: {}", e);
+            exit(1)
+        }).unwrap();
+
+        let options = PruneOptions {
+            protected: if self.protected.is_empty() { config.branches.protected } else { self.protected.clone() },
+            dry_run: self.dry_run,
+            prune_gone: self.prune_gone || config.branches.prune_gone,
+        };
+
+        match branches::prune(&repo, &options) {
+            Ok(ref report) if report.local.is_empty() && report.remote.is_empty() => {
+                info!("No branches to prune.")
+            }
+            Ok(_) => (),
+            Err(e) => {
+                error!("Unable to prune branches: {}", e);
+                exit(1)
+            }
+        }
+    }
+}
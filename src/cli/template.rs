@@ -0,0 +1,117 @@
+use crate::project::templates::answers;
+use crate::project::templates::overrides::Overrides;
+use crate::project::templates::registry::{self, Registered};
+
+use git2::Repository;
+
+use log::error;
+
+use serde_yaml::Value;
+
+use std::path::PathBuf;
+use std::process::exit;
+
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub enum Template {
+    /// List every template along with the source it resolves to: a project or user override, or
+    /// the template compiled into nfty.
+    #[structopt(name = "list")]
+    List,
+    /// Render a template to stdout, using any override of it.
+    #[structopt(name = "render")]
+    Render {
+        /// The name of the template, as given by template list. The .j2 extension may be omitted.
+        name: String,
+        /// Set a variable of the template, as name=value. Lists of strings are comma separated, and
+        /// other values which aren't strings or bools are given as YAML.
+        #[structopt(short = "s", long = "set", number_of_values = 1)]
+        set: Vec<String>,
+    },
+    /// Show the variables a template accepts, along with their types and defaults.
+    #[structopt(name = "vars")]
+    Vars {
+        /// The name of the template, as given by template list. The .j2 extension may be omitted.
+        name: String,
+    },
+}
+
+impl Template {
+    pub fn execute(&self) {
+        match self {
+            Template::List => list(),
+            Template::Render { name, set } => render(name, set),
+            Template::Vars { name } => vars(name),
+        }
+    }
+}
+
+/// The overrides of the project in the current directory, if there is one, and the current user.
+fn overrides() -> Overrides {
+    // project overrides are only found within a git repository
+    let root: Option<PathBuf> = Repository::open_from_env().ok().and_then(|r| r.workdir().map(|w| w.to_path_buf()));
+    Overrides::new(root.as_deref())
+}
+
+fn find(name: &str) -> Registered {
+    Registered::find(name).ok_or_else(|| {
+        error!("No template named {}, see nfty template list.", name);
+        exit(1)
+    }).unwrap()
+}
+
+fn list() {
+    let overrides = overrides();
+    let templates = registry::all();
+
+    let sources: Vec<String> = templates.iter().map(|t| overrides.resolve(t.template).to_string()).collect();
+
+    let width = templates.iter().map(|t| t.name.len()).max().unwrap_or(0);
+    let source_width = sources.iter().map(|s| s.len()).max().unwrap_or(0);
+
+    for (template, source) in templates.iter().zip(sources) {
+        println!("{:<width$}  {:<source_width$}  {}", template.name, source, template.description,
+            width = width, source_width = source_width);
+    }
+}
+
+fn render(name: &str, set: &[String]) {
+    let template = find(name);
+
+    let rendered = template.parse_assignments(set)
+        .and_then(|values| template.render(values, &overrides()))
+        .map_err(|e| {
+            error!("{:#}", e);
+            exit(1)
+        }).unwrap();
+
+    println!("{}", rendered);
+}
+
+fn vars(name: &str) {
+    let template = find(name);
+
+    let defaults = template.defaults().map_err(|e| {
+        error!("Unable to get the defaults of {}: {:#}", template.name, e);
+        exit(1)
+    }).unwrap();
+
+    let defaults: Vec<String> = template.fields.iter()
+        .map(|f| match defaults.get(&Value::String(f.name.to_string())) {
+            None | Some(Value::Null) => String::new(),
+            Some(value) => answers::display(value),
+        })
+        .collect();
+
+    let width = template.fields.iter().map(|f| f.name.len()).max().unwrap_or(0);
+    let kind_width = template.fields.iter().map(|f| f.ty.len()).max().unwrap_or(0);
+    let default_width = defaults.iter().map(|d| d.len()).max().unwrap_or(0);
+
+    for (field, default) in template.fields.iter().zip(defaults) {
+        let line = format!("{:<width$}  {:<kind_width$}  {:<default_width$}  {}", field.name, field.ty, default,
+            field.description, width = width, kind_width = kind_width, default_width = default_width);
+
+        println!("{}", line.trim_end());
+    }
+}
@@ -0,0 +1,303 @@
+#[cfg(test)]
+mod tests;
+
+use crate::project::hooks::packs;
+use crate::project::Project;
+
+use dirs::home_dir;
+
+use git2::Repository;
+
+use lazy_static::lazy_static;
+
+use log::warn;
+
+use serde_derive::Deserialize;
+
+use serde_yaml::{Mapping, Value};
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+lazy_static! {
+    /// The directory holding user-level nfty configuration, i.e. `~/.config/nfty`.
+    pub static ref CONFIG_ROOT: PathBuf = home_dir().expect("unable to get home dir").join(".config").join("nfty");
+}
+
+/// The directory within a project holding project-level nfty configuration, committed alongside it.
+pub static PROJECT_CONFIG_DIR: &str = ".nfty";
+
+/// The branch new projects start on when neither nfty nor Git is configured otherwise.
+static DEFAULT_BRANCH: &str = "main";
+
+/// The name of the configuration file in both `~/.config/nfty` and a project's `.nfty`.
+static CONFIG_FILE: &str = "config.yml";
+
+/// Settings for nfty, layered from most general to most specific:
+///
+///  1. the top level of `~/.config/nfty/config.yml`
+///  2. its `owners.<owner>` section
+///  3. its `projects.<host>/<owner>/<repository>` section
+///  4. the project's own `.nfty/config.yml`
+///
+/// Mappings are merged key by key, while any other value, including lists, replaces the value from
+/// the layer before it.
+///
+/// ```yaml
+/// branches:
+///   protected: [main, master, "release/*"]
+/// owners:
+///   naftulikay:
+///     branches:
+///       prune_gone: true
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub branches: BranchConfig,
+    pub commit_msg: CommitMsgConfig,
+    pub pre_commit: PreCommitConfig,
+    pub pre_push: PrePushConfig,
+    pub dependencies: DependencyConfig,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct BranchConfig {
+    /// Glob patterns matching branches which are never deleted by branch pruning.
+    pub protected: Vec<String>,
+    /// Whether branch pruning should also remove remote-tracking branches whose upstream branch no
+    /// longer exists on the remote.
+    pub prune_gone: bool,
+    /// The branch new projects start on. Git's own `init.defaultBranch` is used when it isn't set,
+    /// and `main` when neither is.
+    pub default: Option<String>,
+}
+
+impl Default for BranchConfig {
+    fn default() -> Self {
+        BranchConfig {
+            protected: ["main", "master", "develop", "production"].iter().map(|s| s.to_string()).collect(),
+            prune_gone: false,
+            default: None,
+        }
+    }
+}
+
+impl BranchConfig {
+    /// The branch new projects start on.
+    pub fn default_branch(&self) -> String {
+        self.default.clone()
+            .or_else(|| git2::Config::open_default().and_then(|c| c.get_string("init.defaultBranch")).ok())
+            .unwrap_or_else(|| DEFAULT_BRANCH.to_string())
+    }
+}
+
+/// The commit message policy enforced by the `commit-msg` hook. Every check is disabled by default.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct CommitMsgConfig {
+    /// Require subjects in Conventional Commits format, e.g. `feat(hooks): add a thing`.
+    pub conventional: bool,
+    /// The commit types allowed when `conventional` is enabled.
+    pub types: Vec<String>,
+    /// The maximum length of the subject line, in characters.
+    pub max_subject_length: Option<usize>,
+    /// A regular expression matching issue keys, e.g. `ABC-123`.
+    pub issue_pattern: String,
+    /// Require every commit message to reference an issue key.
+    pub require_issue: bool,
+    /// Fill in the issue key from the branch name when preparing a commit message.
+    pub prefill_issue: bool,
+}
+
+impl Default for CommitMsgConfig {
+    fn default() -> Self {
+        CommitMsgConfig {
+            conventional: false,
+            types: ["build", "chore", "ci", "docs", "feat", "fix", "perf", "refactor", "revert", "style", "test"]
+                .iter().map(|s| s.to_string()).collect(),
+            max_subject_length: None,
+            issue_pattern: "[A-Z][A-Z0-9]+-[0-9]+".to_string(),
+            require_issue: false,
+            prefill_issue: false,
+        }
+    }
+}
+
+/// The checks run against staged files by the `pre-commit` hook.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct PreCommitConfig {
+    /// Reject trailing whitespace and text files which don't end in a newline.
+    pub whitespace: bool,
+    /// Strip trailing whitespace from staged files instead of rejecting them. A missing newline at
+    /// the end of a file is still rejected.
+    pub autofix: bool,
+    /// The largest file in bytes which may be committed outside of Git LFS.
+    pub max_file_size: Option<u64>,
+    /// Reject merge conflict markers.
+    pub conflict_markers: bool,
+    /// Reject private keys, cloud credentials, and high-entropy values assigned to secret-looking
+    /// names.
+    pub secrets: bool,
+    /// Glob patterns of paths which are never checked.
+    pub exclude: Vec<String>,
+}
+
+impl Default for PreCommitConfig {
+    fn default() -> Self {
+        PreCommitConfig {
+            whitespace: false,
+            autofix: false,
+            max_file_size: Some(5 * 1024 * 1024),
+            conflict_markers: true,
+            secrets: true,
+            exclude: Vec::new(),
+        }
+    }
+}
+
+/// The checks run against pushed refs by the `pre-push` hook. Setting `NFTY_SKIP_PRE_PUSH=1` in the
+/// environment skips them.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct PrePushConfig {
+    /// Glob patterns matching branches on the remote which are guarded, defaulting to the branches
+    /// protected from pruning.
+    pub protected: Option<Vec<String>>,
+    /// Reject pushes which update or delete a guarded branch, so that changes reach it through pull
+    /// requests. On by default. Creating one, as the first push of a new project does, is always
+    /// allowed.
+    pub block_direct: bool,
+    /// Reject pushes which rewrite the history of a guarded branch, even when direct pushes are
+    /// allowed.
+    pub block_force: bool,
+    /// Warn about work-in-progress, fixup and squash commits in the pushed commits.
+    pub warn_wip: bool,
+}
+
+impl Default for PrePushConfig {
+    fn default() -> Self {
+        PrePushConfig {
+            protected: None,
+            block_direct: true,
+            block_force: true,
+            warn_wip: true,
+        }
+    }
+}
+
+/// The notifications given by the `post-merge` and `post-checkout` hooks when dependency manifests
+/// change.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct DependencyConfig {
+    /// Run the install command for each changed manifest rather than only printing it. Only honoured
+    /// in a project's own `.nfty/config.yml` when the user's hook pack trusts the project.
+    pub install: bool,
+}
+
+impl Config {
+    /// Load the configuration for the repository at the given path. Settings which run commands are
+    /// dropped from the project's own configuration unless the project is trusted.
+    pub fn load(path: &Path, project: Option<&Project>) -> io::Result<Self> {
+        let user = read(&CONFIG_ROOT.join(CONFIG_FILE))?;
+        let mut local = read(&path.join(PROJECT_CONFIG_DIR).join(CONFIG_FILE))?;
+
+        if !packs::is_trusted(project)? && distrust(&mut local) {
+            warn!("Ignoring dependencies.install in {}, the project isn't trusted.",
+                path.join(PROJECT_CONFIG_DIR).join(CONFIG_FILE).display());
+        }
+
+        Config::layer(user, local, project)
+    }
+
+    /// Load the configuration for an open repository, identifying its project from its `origin`
+    /// remote. Bare repositories only get user-level configuration.
+    pub fn for_repository(repo: &Repository) -> io::Result<Self> {
+        let project = Project::from_repository(repo);
+
+        match repo.workdir() {
+            Some(dir) => Config::load(dir, project.as_ref()),
+            None => Config::layer(read(&CONFIG_ROOT.join(CONFIG_FILE))?, Value::Null, project.as_ref()),
+        }
+    }
+
+    /// Resolve the configuration from user and project-level documents.
+    pub fn layer(user: Value, local: Value, project: Option<&Project>) -> io::Result<Self> {
+        let mut merged = Value::Mapping(Mapping::new());
+
+        merge(&mut merged, strip_sections(&user));
+
+        if let Some(project) = project {
+            let owner = section(&user, "owners", project.owner());
+            let repository = section(&user, "projects",
+                &format!("{}/{}/{}", project.host(), project.owner(), project.repository()));
+
+            merge(&mut merged, owner);
+            merge(&mut merged, repository);
+        }
+
+        merge(&mut merged, local);
+
+        serde_yaml::from_value(merged).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))
+    }
+}
+
+/// Read a YAML document, treating a missing file as an empty one.
+fn read(path: &Path) -> io::Result<Value> {
+    if !path.is_file() {
+        return Ok(Value::Null);
+    }
+
+    serde_yaml::from_str(&fs::read_to_string(path)?)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path.display(), e)))
+}
+
+/// Remove the settings from a project-level document which would run commands from an untrusted
+/// project, returning whether any were present.
+fn distrust(document: &mut Value) -> bool {
+    document.as_mapping_mut()
+        .and_then(|d| d.get_mut(&Value::String("dependencies".into())))
+        .and_then(|d| d.as_mapping_mut())
+        .and_then(|d| d.remove(&Value::String("install".into())))
+        .is_some()
+}
+
+/// Get a named entry from one of the override sections of the user configuration.
+fn section(document: &Value, kind: &str, name: &str) -> Value {
+    document.get(kind).and_then(|s| s.get(name)).cloned().unwrap_or(Value::Null)
+}
+
+/// Remove the override sections from the user configuration.
+fn strip_sections(document: &Value) -> Value {
+    match document {
+        Value::Mapping(m) => {
+            let mut result = m.clone();
+            result.remove(&Value::String("owners".into()));
+            result.remove(&Value::String("projects".into()));
+            Value::Mapping(result)
+        }
+        other => other.clone(),
+    }
+}
+
+/// Deeply merge one document over another.
+fn merge(base: &mut Value, layer: Value) {
+    match (base, layer) {
+        (_, Value::Null) => (),
+        (Value::Mapping(ref mut base), Value::Mapping(layer)) => {
+            for (key, value) in layer {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, layer) => *base = layer,
+    }
+}
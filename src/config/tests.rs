@@ -0,0 +1,78 @@
+use super::*;
+
+static USER_CONFIG: &str = r###"
+---
+branches:
+  protected: [main]
+owners:
+  naftulikay:
+    branches:
+      prune_gone: true
+projects:
+  github.com/naftulikay/nfty:
+    branches:
+      protected: [main, "release/*"]
+"###;
+
+#[test]
+fn test_default_config() {
+    let config = Config::layer(Value::Null, Value::Null, None).unwrap();
+
+    assert!(config.branches.protected.contains(&"master".to_string()));
+    assert!(config.branches.protected.contains(&"main".to_string()));
+    assert!(!config.branches.prune_gone);
+
+    // protected branches are only changed through pull requests unless configured otherwise
+    assert!(config.pre_push.block_direct);
+    assert!(config.pre_push.block_force);
+}
+
+#[test]
+fn test_layered_config() {
+    let user: Value = serde_yaml::from_str(USER_CONFIG).unwrap();
+
+    // top level only
+    let config = Config::layer(user.clone(), Value::Null, None).unwrap();
+    assert_eq!(vec!["main".to_string()], config.branches.protected);
+    assert!(!config.branches.prune_gone);
+
+    // owner and project sections
+    let project = Project::from("naftulikay/nfty").unwrap();
+    let config = Config::layer(user.clone(), Value::Null, Some(&project)).unwrap();
+    assert_eq!(vec!["main".to_string(), "release/*".to_string()], config.branches.protected);
+    assert!(config.branches.prune_gone);
+
+    // project-level configuration wins
+    let local: Value = serde_yaml::from_str("branches:\n  prune_gone: false").unwrap();
+    let config = Config::layer(user, local, Some(&project)).unwrap();
+    assert_eq!(vec!["main".to_string(), "release/*".to_string()], config.branches.protected);
+    assert!(!config.branches.prune_gone);
+}
+
+#[test]
+fn test_default_branch() {
+    let user: Value = serde_yaml::from_str("branches:\n  default: trunk").unwrap();
+    let config = Config::layer(user, Value::Null, None).unwrap();
+
+    assert_eq!("trunk", config.branches.default_branch());
+}
+
+#[test]
+fn test_distrust() {
+    let mut local: Value = serde_yaml::from_str("dependencies:\n  install: true\nbranches:\n  prune_gone: true").unwrap();
+
+    assert!(distrust(&mut local));
+    assert!(!distrust(&mut local));
+
+    let config = Config::layer(Value::Null, local, None).unwrap();
+    assert!(!config.dependencies.install);
+    assert!(config.branches.prune_gone);
+
+    // user-level configuration may still ask for installs
+    let user: Value = serde_yaml::from_str("dependencies:\n  install: true").unwrap();
+    let mut local: Value = serde_yaml::from_str("dependencies:\n  install: true").unwrap();
+    distrust(&mut local);
+    assert!(Config::layer(user, local, None).unwrap().dependencies.install);
+
+    assert!(!distrust(&mut Value::Null));
+}
@@ -3,10 +3,14 @@
 extern crate nfty_derive;
 
 pub mod cli;
+pub mod config;
 pub mod logging;
 pub mod project;
 pub mod util;
 
+#[cfg(test)]
+mod testing;
+
 use structopt::StructOpt;
 
 fn main() {
@@ -1,20 +1,27 @@
+pub mod backup;
+pub mod branches;
+pub mod commit;
+pub mod hooks;
 pub mod parse;
+pub mod render;
+pub mod state;
 pub mod templates;
 
-mod hooks;
-
 #[cfg(test)]
 mod test;
 
 use dirs::home_dir;
 
+use glob::Pattern;
+
 use lazy_static::lazy_static;
 
 use regex::Regex;
 
 use std::fmt;
+use std::fs;
 use std::io;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use git2::build::RepoBuilder;
 use git2::Cred;
@@ -22,6 +29,7 @@ use git2::FetchOptions;
 use git2::Progress;
 use git2::RemoteCallbacks;
 use git2::Repository;
+use git2::RepositoryInitOptions;
 
 lazy_static! {
     // match https protocol git clone thingamajigs
@@ -140,6 +148,13 @@ impl Project {
         }
     }
 
+    /// Create a project object from the `origin` remote of an existing repository.
+    pub fn from_repository(repo: &Repository) -> Option<Self> {
+        let remote = repo.find_remote("origin").ok()?;
+
+        Project::from(remote.url()?).ok()
+    }
+
     /// Get the protocol of the repository.
     pub fn protocol(&self) -> &Protocol {
         &self.protocol
@@ -181,6 +196,16 @@ impl Project {
         }
     }
 
+    /// The name of the project as it is laid out under the project root, i.e. `host/owner/repository`.
+    pub fn name(&self) -> String {
+        format!("{}/{}/{}", self.host, self.owner, self.repository)
+    }
+
+    /// Determine whether the name of the project matches a glob pattern.
+    pub fn matches(&self, pattern: &Pattern) -> bool {
+        pattern.matches(&self.name())
+    }
+
     /// Get the directory of a given project.
     pub fn dir(&self) -> PathBuf {
         PROJECT_ROOT
@@ -189,6 +214,37 @@ impl Project {
             .join(&self.repository)
     }
 
+    /// Find every project which has been cloned locally under the project root.
+    pub fn all_local() -> io::Result<Vec<Self>> {
+        let mut result = Vec::new();
+
+        if !PROJECT_ROOT.is_dir() {
+            return Ok(result);
+        }
+
+        // projects live at $PROJECT_ROOT/$HOST/$OWNER/$REPOSITORY
+        for host in subdirectories(&PROJECT_ROOT)? {
+            for owner in subdirectories(&host)? {
+                for repository in subdirectories(&owner)? {
+                    if !repository.join(".git").exists() {
+                        continue;
+                    }
+
+                    let name = |p: &Path| p.file_name().unwrap().to_string_lossy().to_string();
+
+                    if let Ok(project) = Project::from(&format!("{}:{}/{}", name(&host), name(&owner),
+                            name(&repository))) {
+                        result.push(project);
+                    }
+                }
+            }
+        }
+
+        result.sort_by_key(|p| p.dir());
+
+        Ok(result)
+    }
+
     /// Determine whether a project locally exists.
     pub fn is_local(&self) -> bool {
         let project_dir = self.dir();
@@ -228,8 +284,34 @@ impl Project {
         }
     }
 
+    /// Initialize a repository in the project's directory, creating it, which starts on the given
+    /// branch and has its `origin` remote set to the project's URL.
+    pub fn init(&self, branch: &str) -> io::Result<Repository> {
+        self.init_at(&self.dir(), branch)
+    }
+
+    /// Initialize a repository for the project in the given directory, as `init` does.
+    pub fn init_at(&self, dir: &Path, branch: &str) -> io::Result<Repository> {
+        let mut options = RepositoryInitOptions::new();
+        options.initial_head(branch).origin_url(&self.url());
+
+        match Repository::init_opts(dir, &options) {
+            Ok(r) => Ok(r),
+            Err(e) => Err(io::Error::other(e.to_string().trim())),
+        }
+    }
+
     /// Configure the repository, install hooks, etc.
     pub fn configure(&self) -> io::Result<()> {
-        hooks::install(&self.dir())
+        hooks::install(&self.dir(), Some(self))
     }
 }
+
+/// List the subdirectories of a given directory.
+fn subdirectories(path: &Path) -> io::Result<Vec<PathBuf>> {
+    Ok(fs::read_dir(path)?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_dir())
+        .collect())
+}
@@ -0,0 +1,204 @@
+#[cfg(test)]
+mod tests;
+
+use crate::config::PROJECT_CONFIG_DIR;
+use crate::project::state::State;
+
+use chrono::Utc;
+
+use log::debug;
+
+use serde_derive::{Deserialize, Serialize};
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The directory within a project's `.nfty` holding snapshots, one directory per snapshot named
+/// by when it was taken.
+static BACKUPS_DIR: &str = "backups";
+
+/// The file within a snapshot listing what it holds.
+static SNAPSHOT_FILE: &str = "snapshot.yml";
+
+/// The directory within a snapshot holding the files which were saved, at their paths relative to
+/// the project root.
+static FILES_DIR: &str = "files";
+
+/// The files and directories conform was about to touch, as they were before it touched them, so
+/// that they can be restored.
+///
+/// ```text
+/// .nfty/backups/2019-03-01T120000.000000Z/snapshot.yml
+/// .nfty/backups/2019-03-01T120000.000000Z/files/Makefile
+/// .nfty/backups/2019-03-01T120000.000000Z/files/LICENSE
+/// ```
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Snapshot {
+    /// Files which existed, kept in the snapshot.
+    pub saved: Vec<String>,
+    /// Files which didn't exist, removed when the snapshot is restored.
+    pub created: Vec<String>,
+    /// Directories which didn't exist, removed when the snapshot is restored if they are empty.
+    pub directories: Vec<String>,
+}
+
+impl Snapshot {
+    /// The directory holding every snapshot of the project at the given root.
+    pub fn backups(root: &Path) -> PathBuf {
+        root.join(PROJECT_CONFIG_DIR).join(BACKUPS_DIR)
+    }
+
+    /// Snapshot the given files and directories, given relative to the project root, returning the
+    /// directory of the snapshot. Directories which would have to be created for files which
+    /// don't exist yet are recorded as created too.
+    pub fn take(root: &Path, files: &[PathBuf], directories: &[PathBuf]) -> io::Result<PathBuf> {
+        let backups = Snapshot::backups(root);
+        fs::create_dir_all(&backups)?;
+
+        // snapshots are kept alongside the project's .nfty but never committed with it
+        let ignore = backups.join(".gitignore");
+
+        if !ignore.is_file() {
+            fs::write(&ignore, "*\n")?;
+        }
+
+        let dir = backups.join(Utc::now().format("%Y-%m-%dT%H%M%S%.6fZ").to_string());
+        let mut snapshot = Snapshot::default();
+
+        for relative in files {
+            let path = root.join(relative);
+
+            if path.is_file() {
+                let dest = dir.join(FILES_DIR).join(relative);
+
+                if let Some(parent) = dest.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+
+                fs::copy(&path, &dest)?;
+                snapshot.saved.push(key(relative));
+            } else {
+                snapshot.created.push(key(relative));
+
+                for ancestor in relative.ancestors().skip(1).filter(|a| !a.as_os_str().is_empty()) {
+                    snapshot.created_directory(root, ancestor);
+                }
+            }
+        }
+
+        for relative in directories {
+            snapshot.created_directory(root, relative);
+        }
+
+        fs::create_dir_all(&dir)?;
+        fs::write(dir.join(SNAPSHOT_FILE), serde_yaml::to_string(&snapshot).map_err(io::Error::other)?)?;
+
+        debug!("Saved {} files to {}", snapshot.saved.len(), dir.display());
+
+        Ok(dir)
+    }
+
+    /// Record a directory as created, if it doesn't exist and isn't recorded already.
+    fn created_directory(&mut self, root: &Path, relative: &Path) {
+        let relative = key(relative);
+
+        if !root.join(&relative).is_dir() && !self.directories.contains(&relative) {
+            self.directories.push(relative);
+        }
+    }
+
+    /// The directory of the most recent snapshot of the project at the given root, if it has any.
+    pub fn latest(root: &Path) -> io::Result<Option<PathBuf>> {
+        let backups = Snapshot::backups(root);
+
+        if !backups.is_dir() {
+            return Ok(None);
+        }
+
+        let mut snapshots = Vec::new();
+
+        for entry in fs::read_dir(&backups)? {
+            let path = entry?.path();
+
+            if path.join(SNAPSHOT_FILE).is_file() {
+                snapshots.push(path);
+            }
+        }
+
+        // snapshots are named by when they were taken, so the last by name is the most recent
+        Ok(snapshots.into_iter().max())
+    }
+
+    /// Load the snapshot in the given directory.
+    pub fn load(dir: &Path) -> io::Result<Snapshot> {
+        let path = dir.join(SNAPSHOT_FILE);
+
+        serde_yaml::from_str(&fs::read_to_string(&path)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path.display(), e)))
+    }
+
+    /// The files which restoring this snapshot would overwrite or remove, but which were edited
+    /// since conform rendered them, according to the state of the project at the given root.
+    pub fn edited(&self, root: &Path, state: &State) -> io::Result<Vec<String>> {
+        let mut edited = Vec::new();
+
+        for relative in self.saved.iter().chain(&self.created) {
+            if state.is_edited_on_disk(root, Path::new(relative))? {
+                edited.push(relative.clone());
+            }
+        }
+
+        Ok(edited)
+    }
+
+    /// Restore the project at the given root from the snapshot in the given directory: saved files
+    /// are put back, created files are removed, and created directories are removed if they are
+    /// empty. The snapshot is removed once it is restored.
+    pub fn restore(root: &Path, dir: &Path) -> io::Result<Snapshot> {
+        let snapshot = Snapshot::load(dir)?;
+
+        for relative in &snapshot.created {
+            let path = root.join(relative);
+
+            if path.is_file() {
+                debug!("Removing {}", relative);
+                fs::remove_file(&path)?;
+            }
+        }
+
+        for relative in &snapshot.saved {
+            let path = root.join(relative);
+
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+
+            debug!("Restoring {}", relative);
+            fs::copy(dir.join(FILES_DIR).join(relative), &path)?;
+        }
+
+        // deepest first, so that directories are empty by the time their parents are removed
+        let mut directories = snapshot.directories.clone();
+        directories.sort_by_key(|d| std::cmp::Reverse(Path::new(d).components().count()));
+
+        for relative in directories {
+            let path = root.join(&relative);
+
+            if path.is_dir() && fs::read_dir(&path)?.next().is_none() {
+                debug!("Removing {}/", relative);
+                fs::remove_dir(&path)?;
+            }
+        }
+
+        fs::remove_dir_all(dir)?;
+
+        Ok(snapshot)
+    }
+}
+
+/// The key of a path in a snapshot, its path relative to the project root.
+fn key(relative: &Path) -> String {
+    relative.to_string_lossy().to_string()
+}
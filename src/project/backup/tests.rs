@@ -0,0 +1,101 @@
+use super::*;
+
+use crate::project::state::{hash, FileState};
+use crate::testing::Scratch;
+use crate::util::Checksum;
+
+#[test]
+fn test_take_and_restore() {
+    let root = Scratch::new("backup-restore");
+
+    fs::write(root.join("LICENSE"), "All Rights Reserved").unwrap();
+    fs::write(root.join("Makefile"), "all:\n\techo hi\n").unwrap();
+
+    let files = vec![PathBuf::from("LICENSE"), PathBuf::from("Makefile"), PathBuf::from("tests/inventory/hosts.yml")];
+    let dir = Snapshot::take(&root, &files, &[PathBuf::from("meta")]).unwrap();
+
+    let snapshot = Snapshot::load(&dir).unwrap();
+    assert_eq!(vec!["LICENSE", "Makefile"], snapshot.saved);
+    assert_eq!(vec!["tests/inventory/hosts.yml"], snapshot.created);
+    assert_eq!(vec!["tests/inventory", "tests", "meta"], snapshot.directories);
+
+    // what conform would do
+    fs::remove_file(root.join("LICENSE")).unwrap();
+    fs::write(root.join("Makefile"), "all:\n\techo hello\n").unwrap();
+    fs::create_dir_all(root.join("tests/inventory")).unwrap();
+    fs::create_dir_all(root.join("meta")).unwrap();
+    fs::write(root.join("tests/inventory/hosts.yml"), "all: {}").unwrap();
+    fs::write(root.join("meta/main.yml"), "galaxy_info: {}").unwrap();
+
+    assert_eq!(Some(dir.clone()), Snapshot::latest(&root).unwrap());
+    Snapshot::restore(&root, &dir).unwrap();
+
+    assert_eq!("All Rights Reserved", fs::read_to_string(root.join("LICENSE")).unwrap());
+    assert_eq!("all:\n\techo hi\n", fs::read_to_string(root.join("Makefile")).unwrap());
+    assert!(!root.join("tests").exists());
+
+    // directories which aren't empty have files conform didn't create, so they are kept
+    assert!(root.join("meta/main.yml").is_file());
+
+    // the snapshot is used up, and snapshots are never committed
+    assert!(!dir.exists());
+    assert_eq!(None, Snapshot::latest(&root).unwrap());
+    assert_eq!("*\n", fs::read_to_string(Snapshot::backups(&root).join(".gitignore")).unwrap());
+
+}
+
+#[test]
+fn test_latest() {
+    let root = Scratch::new("backup-latest");
+    assert_eq!(None, Snapshot::latest(&root).unwrap());
+
+    let first = Snapshot::take(&root, &[PathBuf::from("Makefile")], &[]).unwrap();
+    let second = Snapshot::take(&root, &[PathBuf::from("LICENSE")], &[]).unwrap();
+
+    assert!(first < second);
+    assert_eq!(Some(second.clone()), Snapshot::latest(&root).unwrap());
+
+    Snapshot::restore(&root, &second).unwrap();
+    assert_eq!(Some(first), Snapshot::latest(&root).unwrap());
+
+}
+
+#[test]
+fn test_edited() {
+    let root = Scratch::new("backup-edited");
+    fs::write(root.join("Makefile"), "all:\n\techo hi\n").unwrap();
+
+    let files = vec![PathBuf::from("Makefile"), PathBuf::from("LICENSE")];
+    let dir = Snapshot::take(&root, &files, &[]).unwrap();
+    let snapshot = Snapshot::load(&dir).unwrap();
+
+    // what conform would do, and record
+    let mut state = State::default();
+
+    for (name, contents) in [("Makefile", "all:\n\techo hello\n"), ("LICENSE", "MIT")] {
+        fs::write(root.join(name), contents).unwrap();
+        state.files.insert(name.to_string(), FileState {
+            template: None,
+            version: "0.4.0".to_string(),
+            hash: hash(contents, None),
+            written: Some(Checksum::sha256(contents.as_bytes())),
+        });
+    }
+
+    assert!(snapshot.edited(&root, &state).unwrap().is_empty());
+
+    // edits made since would be lost by restoring the snapshot
+    fs::write(root.join("Makefile"), "all:\n\techo hello world\n").unwrap();
+    fs::write(root.join("LICENSE"), "MIT, with edits").unwrap();
+    assert_eq!(vec!["Makefile", "LICENSE"], snapshot.edited(&root, &state).unwrap());
+
+    // edits anywhere in a file are lost, not only within its managed block
+    let block = "# BEGIN nfty managed\nMIT\n# END nfty managed\n";
+    fs::write(root.join("LICENSE"), block).unwrap();
+    state.files.get_mut("LICENSE").unwrap().hash = hash(block, Some("#"));
+    state.files.get_mut("LICENSE").unwrap().written = Some(Checksum::sha256(block.as_bytes()));
+    assert_eq!(vec!["Makefile"], snapshot.edited(&root, &state).unwrap());
+
+    fs::write(root.join("LICENSE"), format!("{}Copyright me\n", block)).unwrap();
+    assert_eq!(vec!["Makefile", "LICENSE"], snapshot.edited(&root, &state).unwrap());
+}
@@ -0,0 +1,133 @@
+#[cfg(test)]
+mod tests;
+
+use crate::util::Git;
+
+use anyhow::{anyhow, Result};
+
+use git2::{BranchType, Direction, Repository};
+
+use glob::Pattern;
+
+use log::{debug, info};
+
+use std::collections::BTreeSet;
+
+/// Options controlling which branches are pruned.
+pub struct PruneOptions {
+    /// Glob patterns matching branches which are never deleted.
+    pub protected: Vec<String>,
+    /// Report what would be deleted without deleting anything.
+    pub dry_run: bool,
+    /// Also delete remote-tracking branches whose branch no longer exists on the remote. This
+    /// requires connecting to each remote.
+    pub prune_gone: bool,
+}
+
+/// The branches deleted, or which would have been deleted in a dry run.
+#[derive(Default)]
+pub struct PruneReport {
+    pub local: Vec<String>,
+    pub remote: Vec<String>,
+}
+
+/// Determine whether a branch name matches any of the given protected patterns.
+pub fn is_protected(name: &str, patterns: &[Pattern]) -> bool {
+    patterns.iter().any(|p| p.matches(name))
+}
+
+/// Delete local branches which have been fully merged into `HEAD`, the equivalent of
+/// `git branch --merged | xargs git branch -d` excluding the current and protected branches.
+pub fn prune(repo: &Repository, options: &PruneOptions) -> Result<PruneReport> {
+    let patterns = options.protected.iter()
+        .map(|p| Pattern::new(p).map_err(|e| anyhow!("invalid protected branch pattern {}: {}", p, e)))
+        .collect::<Result<Vec<_>>>()?;
+
+    let head = repo.head()?.peel_to_commit()?.id();
+    let mut report = PruneReport::default();
+
+    for branch in repo.branches(Some(BranchType::Local))? {
+        let (mut branch, _) = branch?;
+
+        let name = match branch.name()? {
+            Some(name) => name.to_string(),
+            None => continue,
+        };
+
+        if branch.is_head() || is_protected(&name, &patterns) {
+            debug!("Skipping current or protected branch {}", name);
+            continue;
+        }
+
+        let target = match branch.get().target() {
+            Some(target) => target,
+            None => continue,
+        };
+
+        if target != head && !repo.graph_descendant_of(head, target)? {
+            continue;
+        }
+
+        if options.dry_run {
+            info!("Would delete branch {} (was {})", name, &target.to_string()[..7]);
+        } else {
+            branch.delete()?;
+            info!("Deleted branch {} (was {})", name, &target.to_string()[..7]);
+        }
+
+        report.local.push(name);
+    }
+
+    if options.prune_gone {
+        report.remote = prune_gone(repo, &patterns, options.dry_run)?;
+    }
+
+    Ok(report)
+}
+
+/// Delete remote-tracking branches whose branch no longer exists on the remote, the equivalent of
+/// `git remote prune`.
+fn prune_gone(repo: &Repository, patterns: &[Pattern], dry_run: bool) -> Result<Vec<String>> {
+    let mut result = Vec::new();
+
+    for remote_name in repo.remotes()?.iter().flatten() {
+        let mut remote = repo.find_remote(remote_name)?;
+
+        debug!("Listing branches on remote {}", remote_name);
+
+        // the connection disconnects when dropped
+        let live = remote.connect_auth(Direction::Fetch, Some(Git::remote_callbacks()), None)?
+            .list()?
+            .iter()
+            .filter_map(|h| h.name().strip_prefix("refs/heads/").map(|n| format!("{}/{}", remote_name, n)))
+            .collect::<BTreeSet<_>>();
+
+        let prefix = format!("{}/", remote_name);
+
+        for branch in repo.branches(Some(BranchType::Remote))? {
+            let (mut branch, _) = branch?;
+
+            let name = match branch.name()? {
+                Some(name) if name.starts_with(&prefix) => name.to_string(),
+                _ => continue,
+            };
+
+            // symbolic refs like origin/HEAD aren't branches on the remote
+            if branch.get().symbolic_target().is_some() || live.contains(&name)
+                    || is_protected(&name[prefix.len()..], patterns) {
+                continue;
+            }
+
+            if dry_run {
+                info!("Would delete remote-tracking branch {}", name);
+            } else {
+                branch.delete()?;
+                info!("Deleted remote-tracking branch {}", name);
+            }
+
+            result.push(name);
+        }
+    }
+
+    Ok(result)
+}
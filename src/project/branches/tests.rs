@@ -0,0 +1,129 @@
+use super::*;
+
+use crate::testing::{self, Scratch};
+
+use git2::{Oid, Signature};
+
+#[test]
+fn test_is_protected() {
+    let patterns = ["main", "master", "release/*"].iter().map(|p| Pattern::new(p).unwrap()).collect::<Vec<_>>();
+
+    assert!(is_protected("main", &patterns));
+    assert!(is_protected("master", &patterns));
+    assert!(is_protected("release/1.0", &patterns));
+
+    assert!(!is_protected("feature/main", &patterns));
+    assert!(!is_protected("mainline", &patterns));
+    assert!(!is_protected("release", &patterns));
+}
+
+fn options(protected: &[&str], dry_run: bool, prune_gone: bool) -> PruneOptions {
+    PruneOptions { protected: protected.iter().map(|p| p.to_string()).collect(), dry_run, prune_gone }
+}
+
+fn local_branches(repo: &Repository) -> Vec<String> {
+    repo.branches(Some(BranchType::Local)).unwrap()
+        .map(|b| b.unwrap().0.name().unwrap().unwrap().to_string())
+        .collect()
+}
+
+/// A repository whose current branch is at `second`, which follows `first`, with `merged` and
+/// `release/1.0` at `first`, and `unmerged` at a commit which also follows `first` but isn't on
+/// the current branch.
+fn history(dir: &Scratch) -> (Repository, String) {
+    let repo = testing::repository(dir);
+
+    let first = testing::commit_file(&repo, "README.md", "# first\n", "Add a README");
+    testing::commit_file(&repo, "README.md", "# second\n", "Reword the README");
+
+    let diverged: Oid = {
+        let parent = repo.find_commit(first).unwrap();
+        let signature = Signature::now("nfty", "nfty@example.com").unwrap();
+        repo.commit(None, &signature, &signature, "Reword the README differently", &parent.tree().unwrap(),
+            &[&parent]).unwrap()
+    };
+
+    for (name, target) in [("merged", first), ("release/1.0", first), ("unmerged", diverged)] {
+        repo.branch(name, &repo.find_commit(target).unwrap(), false).unwrap();
+    }
+
+    let current = repo.head().unwrap().shorthand().unwrap().to_string();
+
+    (repo, current)
+}
+
+#[test]
+fn test_prune() {
+    let dir = Scratch::new("branches-prune");
+    let (repo, current) = history(&dir);
+
+    let report = prune(&repo, &options(&["release/*"], false, false)).unwrap();
+
+    // only merged branches are deleted, never the current branch even though it isn't protected
+    assert_eq!(vec!["merged".to_string()], report.local);
+    assert!(report.remote.is_empty());
+
+    let mut expected = vec![current, "release/1.0".to_string(), "unmerged".to_string()];
+    expected.sort();
+    assert_eq!(expected, local_branches(&repo));
+}
+
+#[test]
+fn test_prune_dry_run() {
+    let dir = Scratch::new("branches-prune-dry-run");
+    let (repo, _) = history(&dir);
+    let before = local_branches(&repo);
+
+    let report = prune(&repo, &options(&[], true, false)).unwrap();
+
+    assert_eq!(vec!["merged".to_string(), "release/1.0".to_string()], report.local);
+    assert_eq!(before, local_branches(&repo));
+}
+
+#[test]
+fn test_prune_gone() {
+    let dir = Scratch::new("branches-prune-gone");
+
+    let upstream = testing::repository(&dir.join("upstream"));
+    let first = testing::commit_file(&upstream, "README.md", "# first\n", "Add a README");
+
+    for name in ["gone", "live", "release/1.0", "release/2.0"] {
+        upstream.branch(name, &upstream.find_commit(first).unwrap(), false).unwrap();
+    }
+
+    let repo = Repository::clone(dir.join("upstream").to_str().unwrap(), dir.join("clone")).unwrap();
+
+    for name in ["gone", "release/1.0"] {
+        upstream.find_branch(name, BranchType::Local).unwrap().delete().unwrap();
+    }
+
+    let remote_branches = || -> Vec<String> {
+        repo.branches(Some(BranchType::Remote)).unwrap()
+            .map(|b| b.unwrap().0.name().unwrap().unwrap().to_string())
+            .filter(|name| name != "origin/HEAD")
+            .collect()
+    };
+
+    let before = remote_branches();
+    assert!(before.contains(&"origin/gone".to_string()));
+
+    // a dry run only reports what it would delete
+    let report = prune(&repo, &options(&["release/*"], true, true)).unwrap();
+    assert_eq!(vec!["origin/gone".to_string()], report.remote);
+    assert_eq!(before, remote_branches());
+
+    // branches still on the remote, and protected branches, are kept
+    let report = prune(&repo, &options(&["release/*"], false, true)).unwrap();
+    assert_eq!(vec!["origin/gone".to_string()], report.remote);
+
+    let after = remote_branches();
+    assert!(!after.contains(&"origin/gone".to_string()));
+    assert!(after.contains(&"origin/live".to_string()));
+    assert!(after.contains(&"origin/release/1.0".to_string()));
+    assert!(after.contains(&"origin/release/2.0".to_string()));
+
+    // without prune_gone the remote isn't consulted at all
+    upstream.find_branch("live", BranchType::Local).unwrap().delete().unwrap();
+    assert!(prune(&repo, &options(&[], false, false)).unwrap().remote.is_empty());
+    assert!(remote_branches().contains(&"origin/live".to_string()));
+}
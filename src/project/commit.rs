@@ -0,0 +1,132 @@
+#[cfg(test)]
+mod tests;
+
+use crate::config::PROJECT_CONFIG_DIR;
+use crate::project::templates::answers;
+
+use anyhow::{anyhow, Result};
+
+use chrono::NaiveDate;
+
+use git2::{BranchType, Index, IndexAddOption, Oid, Repository, StatusOptions};
+
+use log::debug;
+
+use serde_yaml::Value;
+
+use std::path::Path;
+
+/// The paths in the working tree which differ from `HEAD`, including untracked files but not
+/// ignored ones.
+pub fn dirty(repo: &Repository) -> Result<Vec<String>> {
+    let mut options = StatusOptions::new();
+    options.include_untracked(true).include_ignored(false);
+
+    let statuses = repo.statuses(Some(&mut options))?;
+
+    Ok(statuses.iter()
+        .filter(|s| !s.status().is_ignored())
+        .filter_map(|s| s.path().map(|p| p.to_string()))
+        .collect())
+}
+
+/// A name for a fresh branch to conform on, `nfty/conform-<date>`, followed by a number if a
+/// branch by that name exists already.
+pub fn branch_name(repo: &Repository, date: NaiveDate) -> String {
+    let name = format!("nfty/conform-{}", date.format("%Y-%m-%d"));
+
+    (1..)
+        .map(|n| match n {
+            1 => name.clone(),
+            n => format!("{}-{}", name, n),
+        })
+        .find(|n| repo.find_branch(n, BranchType::Local).is_err())
+        .unwrap()
+}
+
+/// Switch to a new branch at `HEAD`, leaving the working tree and index as they are. On an unborn
+/// `HEAD`, the branch is created by the first commit on it.
+pub fn switch(repo: &Repository, name: &str) -> Result<()> {
+    if let Ok(head) = repo.head().and_then(|h| h.peel_to_commit()) {
+        repo.branch(name, &head, false)?;
+    }
+
+    repo.set_head(&format!("refs/heads/{}", name))?;
+
+    Ok(())
+}
+
+/// Stage the given paths relative to the root of the working tree, removing any which no longer
+/// exist, along with everything in `.nfty` which isn't ignored, and commit them on `HEAD`. Nothing
+/// is committed if nothing changed.
+pub fn commit(repo: &Repository, paths: &[&Path], message: &str) -> Result<Option<Oid>> {
+    let root = repo.workdir().ok_or_else(|| anyhow!("unable to commit in a bare repository"))?;
+    let mut index = repo.index()?;
+
+    for path in paths {
+        if root.join(path).is_file() {
+            debug!("Staging {}", path.display());
+            index.add_path(path)?;
+        } else if index.get_path(path, 0).is_some() {
+            debug!("Staging the removal of {}", path.display());
+            index.remove_path(path)?;
+        }
+    }
+
+    if root.join(PROJECT_CONFIG_DIR).is_dir() {
+        index.add_all([PROJECT_CONFIG_DIR].iter(), IndexAddOption::DEFAULT, None)?;
+    }
+
+    index.update_all([PROJECT_CONFIG_DIR].iter(), None)?;
+
+    write(repo, &mut index, message)
+}
+
+/// Stage everything in the working tree which isn't ignored and commit it on `HEAD`. Nothing is
+/// committed if nothing changed.
+pub fn commit_all(repo: &Repository, message: &str) -> Result<Option<Oid>> {
+    let mut index = repo.index()?;
+
+    index.add_all(["*"].iter(), IndexAddOption::DEFAULT, None)?;
+    index.update_all(["*"].iter(), None)?;
+
+    write(repo, &mut index, message)
+}
+
+/// Write the index and commit its tree on `HEAD`, unless it is the tree `HEAD` has already.
+fn write(repo: &Repository, index: &mut Index, message: &str) -> Result<Option<Oid>> {
+    index.write()?;
+
+    let tree = repo.find_tree(index.write_tree()?)?;
+    let parent = repo.head().and_then(|h| h.peel_to_commit()).ok();
+
+    if parent.as_ref().is_some_and(|p| p.tree_id() == tree.id()) {
+        return Ok(None);
+    }
+
+    let signature = repo.signature()?;
+    let parents: Vec<_> = parent.iter().collect();
+
+    Ok(Some(repo.commit(Some("HEAD"), &signature, &signature, message, &tree, &parents)?))
+}
+
+/// The message of a commit rendering a template with the given options, under the given subject,
+/// leaving out the options which weren't given.
+pub fn message(subject: &str, options: &Value) -> String {
+    let mut message = format!("{}\n\nRendered by nfty {}", subject, env!("CARGO_PKG_VERSION"));
+
+    match options {
+        Value::Mapping(options) if !options.is_empty() => {
+            message.push_str(" with:\n");
+
+            for (name, value) in options.iter().filter(|(_, v)| !v.is_null()) {
+                let name = name.as_str().map(|n| n.to_string()).unwrap_or_else(|| answers::display(name));
+                message.push_str(&format!("\n  {}: {}", name, answers::display(value)));
+            }
+        }
+        _ => message.push('.'),
+    }
+
+    message.push('\n');
+    message
+}
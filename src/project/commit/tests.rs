@@ -0,0 +1,128 @@
+use super::*;
+
+use crate::testing::{self, Scratch};
+
+use serde_yaml::Mapping;
+
+use std::fs;
+
+/// A fresh repository for the given test, with an initial commit of a README.
+fn scratch(test: &str) -> (Scratch, Repository) {
+    let dir = Scratch::new(test);
+    let repo = testing::repository(&dir);
+
+    testing::commit_file(&repo, "README.md", "# test\n", "Initial commit");
+
+    (dir, repo)
+}
+
+#[test]
+fn test_dirty() {
+    let (dir, repo) = scratch("dirty");
+
+    assert!(dirty(&repo).unwrap().is_empty());
+
+    fs::write(dir.join(".gitignore"), "target/\n").unwrap();
+    fs::create_dir_all(dir.join("target")).unwrap();
+    fs::write(dir.join("target/build"), "").unwrap();
+    fs::write(dir.join("README.md"), "# edited\n").unwrap();
+
+    // ignored files don't count
+    assert_eq!(vec![".gitignore".to_string(), "README.md".to_string()], dirty(&repo).unwrap());
+
+}
+
+#[test]
+fn test_branch_name() {
+    let (_dir, repo) = scratch("commit-branch-name");
+    let date = NaiveDate::from_ymd_opt(2019, 3, 1).unwrap();
+
+    assert_eq!("nfty/conform-2019-03-01", branch_name(&repo, date));
+
+    let head = repo.head().unwrap().peel_to_commit().unwrap();
+    repo.branch("nfty/conform-2019-03-01", &head, false).unwrap();
+    assert_eq!("nfty/conform-2019-03-01-2", branch_name(&repo, date));
+
+    repo.branch("nfty/conform-2019-03-01-2", &head, false).unwrap();
+    assert_eq!("nfty/conform-2019-03-01-3", branch_name(&repo, date));
+
+}
+
+#[test]
+fn test_commit() {
+    let (dir, repo) = scratch("commit-commit");
+
+    fs::write(dir.join("LICENSE"), "MIT\n").unwrap();
+    fs::write(dir.join("unrelated"), "left alone\n").unwrap();
+    fs::create_dir_all(dir.join(PROJECT_CONFIG_DIR)).unwrap();
+    fs::write(dir.join(PROJECT_CONFIG_DIR).join("state.yml"), "---\n").unwrap();
+    fs::remove_file(dir.join("README.md")).unwrap();
+
+    let initial = repo.head().unwrap().name().unwrap().to_string();
+
+    switch(&repo, "nfty/conform-2019-03-01").unwrap();
+
+    let id = commit(&repo, &[Path::new("LICENSE"), Path::new("README.md")], "Conform").unwrap().unwrap();
+    let head = repo.head().unwrap();
+
+    assert_eq!(Some("refs/heads/nfty/conform-2019-03-01"), head.name());
+    assert_eq!(id, head.peel_to_commit().unwrap().id());
+
+    let tree = head.peel_to_tree().unwrap();
+
+    assert!(tree.get_name("LICENSE").is_some());
+    assert!(tree.get_name(PROJECT_CONFIG_DIR).is_some());
+    assert!(tree.get_name("README.md").is_none());
+    assert!(tree.get_name("unrelated").is_none());
+
+    // the branch conform was on is left at the initial commit
+    let initial = repo.find_reference(&initial).unwrap().peel_to_commit().unwrap();
+    assert_eq!(Some("Initial commit"), initial.message());
+
+    // nothing changed since, so there's nothing to commit
+    assert_eq!(None, commit(&repo, &[Path::new("LICENSE")], "Conform").unwrap());
+
+}
+
+#[test]
+fn test_message() {
+    let mut options = Mapping::new();
+    options.insert(Value::String("role_name".into()), Value::String("nginx".into()));
+    options.insert(Value::String("private".into()), Value::Bool(false));
+    options.insert(Value::String("python_version".into()), Value::Null);
+
+    let message = message("Conform project to the ansible template", &Value::Mapping(options));
+    let mut lines = message.lines();
+
+    assert_eq!(Some("Conform project to the ansible template"), lines.next());
+    assert_eq!(Some(""), lines.next());
+    assert!(message.contains("\n  role_name: nginx\n"));
+    assert!(message.contains("\n  private: no\n"));
+    assert!(!message.contains("python_version"));
+
+    assert!(super::message("Conform project to the upgrade template", &Value::Null).ends_with(".\n"));
+}
+
+#[test]
+fn test_commit_all() {
+    let (dir, repo) = scratch("commit-all");
+
+    fs::write(dir.join(".gitignore"), "target/\n").unwrap();
+    fs::create_dir_all(dir.join("target")).unwrap();
+    fs::write(dir.join("target/build"), "").unwrap();
+    fs::create_dir_all(dir.join("src")).unwrap();
+    fs::write(dir.join("src/main.rs"), "fn main() {}\n").unwrap();
+    fs::remove_file(dir.join("README.md")).unwrap();
+
+    commit_all(&repo, "Create project").unwrap().unwrap();
+
+    let tree = repo.head().unwrap().peel_to_tree().unwrap();
+
+    assert!(tree.get_name(".gitignore").is_some());
+    assert!(tree.get_name("src").is_some());
+    assert!(tree.get_name("target").is_none());
+    assert!(tree.get_name("README.md").is_none());
+
+    assert_eq!(None, commit_all(&repo, "Create project").unwrap());
+
+}
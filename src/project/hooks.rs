@@ -1,17 +1,42 @@
-use log::debug;
+#[cfg(test)]
+mod tests;
 
+pub mod commit_msg;
+pub mod dependencies;
+pub mod dispatch;
+pub mod lfs;
+pub mod packs;
+pub mod pre_commit;
+pub mod pre_push;
+
+use crate::project::Project;
+use crate::util::Checksum;
+
+use git2::{ErrorCode, Repository};
+
+use log::{debug, warn};
+
+use std::fmt;
+use std::fs;
 use std::fs::File;
 use std::fs::create_dir;
 use std::io;
 use std::io::prelude::*;
 use std::os::unix::fs::PermissionsExt;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
-static GENERIC_HOOK_PAYLOAD: &'static str = include_str!("lib/generic-hook.sh");
+/// The stub installed as each Git hook, which hands off to `nfty hook run`.
+static HOOK_STUB_PAYLOAD: &str = include_str!("lib/hook-stub.sh");
+
+/// Present in every hook stub installed by nfty, used to tell our hooks apart from foreign ones.
+static HOOK_STUB_MARKER: &str = "managed by nfty";
+
+/// The directory within `.git/hooks` where pre-existing foreign hooks are preserved on install.
+static BACKUP_DIR: &str = "nfty-backup";
 
 /// A list of types of Git hooks. The only supported hooks for now are client-side hooks, as
 /// server-side hooks aren't super useful in this case.
-static HOOK_TYPES: &'static [&'static str] = &[
+pub static HOOK_TYPES: &[&str] = &[
     "applypatch-msg",
     "commit-msg",
     "post-applypatch",
@@ -35,15 +60,123 @@ mod git_lfs {
 }
 
 mod custom {
-    pub static BRANCH_CLEAN: &'static str = include_str!("lib/branch-clean.sh");
+    pub static BRANCH_CLEAN: &str = include_str!("lib/branch-clean.sh");
+    pub static COMMIT_MSG: &str = include_str!("lib/commit-msg.sh");
+    pub static DEPENDENCIES_POST_CHECKOUT: &str = include_str!("lib/dependencies/post-checkout.sh");
+    pub static DEPENDENCIES_POST_MERGE: &str = include_str!("lib/dependencies/post-merge.sh");
+    pub static PRE_COMMIT: &str = include_str!("lib/pre-commit.sh");
+    pub static PRE_PUSH: &str = include_str!("lib/pre-push.sh");
+    pub static PREPARE_COMMIT_MSG: &str = include_str!("lib/prepare-commit-msg.sh");
+}
+
+/// The state of a single file or directory managed by nfty within `.git/hooks`.
+#[derive(Debug, Eq, PartialEq)]
+pub enum HookState {
+    /// Installed and identical to the payload embedded in this version of nfty.
+    Current,
+    /// Installed, but the contents or permissions differ from the embedded payload.
+    Drifted,
+    /// Not installed.
+    Missing,
+    /// A hook which wasn't installed by nfty occupies this path.
+    Foreign,
+    /// Installed by nfty, but no longer wanted in this repository.
+    Stale,
+}
+
+impl fmt::Display for HookState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", match self {
+            HookState::Current => "current",
+            HookState::Drifted => "drifted",
+            HookState::Missing => "missing",
+            HookState::Foreign => "foreign",
+            HookState::Stale => "stale",
+        })
+    }
+}
+
+/// The status of a path managed by nfty, relative to `.git/hooks`.
+pub struct HookStatus {
+    pub path: PathBuf,
+    pub state: HookState,
+}
+
+impl HookStatus {
+    pub fn is_current(&self) -> bool {
+        self.state == HookState::Current
+    }
+}
+
+/// Properties of a repository which determine the scripts nfty manages in it.
+pub struct HookContext {
+    /// Whether the repository stores files in Git LFS.
+    pub lfs: bool,
+}
+
+impl HookContext {
+    /// Inspect the repository at the given path.
+    pub fn detect(path: &Path) -> Self {
+        HookContext {
+            lfs: lfs::in_use(path),
+        }
+    }
+
+    /// A context in which every managed script is wanted, used to recognize all managed scripts
+    /// regardless of the repository.
+    pub fn everything() -> Self {
+        HookContext {
+            lfs: true,
+        }
+    }
+}
+
+/// The directory Git runs the hooks of a repository from: `core.hooksPath` if it is set, relative
+/// to the working tree, otherwise `hooks` within the Git directory shared by every worktree.
+pub fn hooks_path(repo: &Repository) -> io::Result<PathBuf> {
+    let base = repo.workdir().unwrap_or_else(|| repo.path());
+
+    match repo.config().and_then(|c| c.get_path("core.hooksPath")) {
+        Ok(path) => return Ok(base.join(path)),
+        Err(ref e) if e.code() == ErrorCode::NotFound => (),
+        Err(e) => return Err(io::Error::other(e)),
+    }
+
+    Ok(common_dir(repo)?.join("hooks"))
+}
+
+/// The Git directory shared by every worktree of a repository. The Git directory of a linked
+/// worktree names it in its `commondir` file, relative to itself.
+fn common_dir(repo: &Repository) -> io::Result<PathBuf> {
+    match fs::read_to_string(repo.path().join("commondir")) {
+        Ok(common) => fs::canonicalize(repo.path().join(common.trim())),
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(repo.path().to_path_buf()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Get the hooks directory of the repository at the given working directory.
+fn hooks_dir(path: &Path) -> io::Result<PathBuf> {
+    hooks_path(&Repository::open(path).map_err(io::Error::other)?)
 }
 
-pub fn install(path: &Path) -> io::Result<()> {
-    let hooks_dir = path.join(".git").join("hooks");
+/// Install the hook stubs, managed `<hook>.d` scripts, and enabled hook pack scripts into the
+/// repository at the given path. The project, if known, determines which pack scripts are enabled.
+///
+/// Hooks which weren't installed by nfty are moved into `.git/hooks/nfty-backup` so that
+/// `uninstall` can restore them. Git LFS hooks and filters are only installed in repositories which
+/// use LFS, and LFS hooks are removed from repositories which don't.
+pub fn install(path: &Path, project: Option<&Project>) -> io::Result<()> {
+    let hooks_dir = hooks_dir(path)?;
+    let context = HookContext::detect(path);
+
+    if context.lfs {
+        lfs::install_filters(path)?;
+    }
 
     // create the hooks directory if it doesnt't exist
     if !hooks_dir.is_dir() {
-        create_dir(&path)?;
+        fs::create_dir_all(&hooks_dir)?;
     }
 
     for hook in HOOK_TYPES {
@@ -55,104 +188,230 @@ pub fn install(path: &Path) -> io::Result<()> {
             create_dir(&hooks_d)?;
         }
 
-        // install the generic hook
-        write_hook(&hooks_dir.join(&hook), GENERIC_HOOK_PAYLOAD)?;
+        // preserve any hook we didn't install, then install the hook stub
+        let stub = hooks_dir.join(hook);
+
+        if stub_state(&stub) == HookState::Foreign && !backup(&hooks_dir, hook)? {
+            warn!("Not replacing foreign hook {}, a backup already exists.", stub.display());
+        } else {
+            write_hook(&stub, HOOK_STUB_PAYLOAD)?;
+        }
 
         // install the custom hooks
-        install_custom_hooks(&hook, &hooks_d)?;
+        install_custom_hooks(hook, &hooks_d, &context)?;
     }
 
-    Ok(())
+    // link user and project hook packs
+    packs::install(path, project)
 }
 
-#[cfg(unix)]
-fn write_hook<T>(path: &Path, payload: T) -> io::Result<()>
-        where T: Into<Vec<u8>> {
-    let mut output = File::create(&path)?;
-    output.write_all(&payload.into())?;
-    output.set_permissions(PermissionsExt::from_mode(0o0700))?;
-    output.sync_all()?;
+/// Remove everything installed by nfty from the repository at the given path, restoring any hooks
+/// which were present before nfty was installed.
+pub fn uninstall(path: &Path) -> io::Result<()> {
+    let hooks_dir = hooks_dir(path)?;
 
-    Ok(())
-}
+    packs::uninstall(path)?;
 
-fn install_custom_hooks(hook_name: &str, hooks_d: &Path) -> io::Result<()> {
-    debug!("Installing custom hooks for {}...", hook_name);
+    for hook in HOOK_TYPES {
+        let hooks_d = hooks_dir.join(format!("{}.d", &hook));
 
-    match hook_name {
-        "applypatch-msg"     => install_applypatch_msg(&hooks_d)?,
-        "commit-msg"         => install_commit_msg(&hooks_d)?,
-        "post-applypatch"    => install_post_applypatch(&hooks_d)?,
-        "post-checkout"      => install_post_checkout(&hooks_d)?,
-        "post-commit"        => install_post_commit(&hooks_d)?,
-        "post-merge"         => install_post_merge(&hooks_d)?,
-        "post-rewrite"       => install_post_rewrite(&hooks_d)?,
-        "post-update"        => install_post_update(&hooks_d)?,
-        "pre-applypatch"     => install_pre_applypatch(&hooks_d)?,
-        "pre-auto-gc"        => install_pre_auto_gc(&hooks_d)?,
-        "pre-commit"         => install_pre_commit(&hooks_d)?,
-        "pre-push"           => install_pre_push(&hooks_d)?,
-        "pre-rebase"         => install_pre_rebase(&hooks_d)?,
-        "prepare-commit-msg" => install_prepare_commit_msg(&hooks_d)?,
-        _ => (),
+        for (name, _) in custom_hooks(hook, &HookContext::everything()) {
+            let script = hooks_d.join(name);
+
+            if script.is_file() {
+                debug!("Removing {}", script.display());
+                fs::remove_file(&script)?;
+            }
+        }
+
+        // only remove the directory if nothing else lives there
+        if hooks_d.is_dir() && fs::read_dir(&hooks_d)?.next().is_none() {
+            fs::remove_dir(&hooks_d)?;
+        }
+
+        let stub = hooks_dir.join(hook);
+
+        match stub_state(&stub) {
+            HookState::Current | HookState::Drifted => fs::remove_file(&stub)?,
+            _ => (),
+        }
+
+        let preserved = hooks_dir.join(BACKUP_DIR).join(hook);
+
+        if preserved.is_file() && !stub.exists() {
+            debug!("Restoring {} from backup", stub.display());
+            fs::rename(&preserved, &stub)?;
+        }
     }
 
-    Ok(())
-}
+    let backup_dir = hooks_dir.join(BACKUP_DIR);
 
-fn install_applypatch_msg(_hooks_d: &Path) -> io::Result<()> {
-    Ok(())
-}
+    if backup_dir.is_dir() && fs::read_dir(&backup_dir)?.next().is_none() {
+        fs::remove_dir(&backup_dir)?;
+    }
 
-fn install_commit_msg(_hooks_d: &Path) -> io::Result<()> {
     Ok(())
 }
 
-fn install_post_applypatch(_hooks_d: &Path) -> io::Result<()> {
-    Ok(())
-}
+/// Compare everything nfty would install against what is currently installed in the repository at
+/// the given path.
+pub fn status(path: &Path, project: Option<&Project>) -> io::Result<Vec<HookStatus>> {
+    let hooks_dir = hooks_dir(path)?;
+    let context = HookContext::detect(path);
+    let mut result = Vec::new();
 
-fn install_post_checkout(hooks_d: &Path) -> io::Result<()> {
-    write_hook(&hooks_d.join("10-git-lfs.sh"), git_lfs::POST_CHECKOUT)
-}
+    for hook in HOOK_TYPES {
+        result.push(HookStatus {
+            path: PathBuf::from(hook),
+            state: stub_state(&hooks_dir.join(hook)),
+        });
 
-fn install_post_commit(hooks_d: &Path) -> io::Result<()> {
-    write_hook(&hooks_d.join("10-git-lfs.sh"), git_lfs::POST_COMMIT)
-}
+        let hooks_d = PathBuf::from(format!("{}.d", &hook));
+
+        result.push(HookStatus {
+            state: if hooks_dir.join(&hooks_d).is_dir() { HookState::Current } else { HookState::Missing },
+            path: hooks_d.clone(),
+        });
 
-fn install_post_merge(hooks_d: &Path) -> io::Result<()> {
-    write_hook(&hooks_d.join("10-git-lfs.sh"), git_lfs::POST_MERGE)?;
-    write_hook(&hooks_d.join("90-branch-clean.sh"), custom::BRANCH_CLEAN)
+        let wanted = custom_hooks(hook, &context);
+
+        for (name, payload) in &wanted {
+            result.push(HookStatus {
+                path: hooks_d.join(name),
+                state: payload_state(&hooks_dir.join(&hooks_d).join(name), payload),
+            });
+        }
+
+        for (name, _) in unwanted_hooks(hook, &wanted) {
+            if hooks_dir.join(&hooks_d).join(name).is_file() {
+                result.push(HookStatus {
+                    path: hooks_d.join(name),
+                    state: HookState::Stale,
+                });
+            }
+        }
+    }
+
+    result.extend(packs::status(path, project)?);
+
+    Ok(result)
 }
 
-fn install_post_rewrite(_hooks_d: &Path) -> io::Result<()> {
-    Ok(())
+/// Determine the state of a top-level hook stub.
+fn stub_state(path: &Path) -> HookState {
+    match payload_state(path, HOOK_STUB_PAYLOAD) {
+        HookState::Drifted => match fs::read_to_string(path) {
+            Ok(ref contents) if contents.contains(HOOK_STUB_MARKER) => HookState::Drifted,
+            _ => HookState::Foreign,
+        },
+        state => state,
+    }
 }
 
-fn install_post_update(_hooks_d: &Path) -> io::Result<()> {
-    Ok(())
+/// Determine the state of an installed file by comparing its checksum and permissions against the
+/// given payload.
+fn payload_state(path: &Path, payload: &str) -> HookState {
+    if !path.exists() {
+        return HookState::Missing;
+    }
+
+    let executable = fs::metadata(path).map(|m| m.permissions().mode() & 0o100 != 0).unwrap_or(false);
+
+    match fs::read(path) {
+        Ok(ref contents) if executable && Checksum::sha256(contents) == Checksum::sha256(payload.as_bytes()) => {
+            HookState::Current
+        }
+        _ => HookState::Drifted,
+    }
 }
 
-fn install_pre_applypatch(_hooks_d: &Path) -> io::Result<()> {
-    Ok(())
+/// Move a foreign hook into the backup directory. Returns `false` if a backup already exists.
+fn backup(hooks_dir: &Path, hook: &str) -> io::Result<bool> {
+    let backup_dir = hooks_dir.join(BACKUP_DIR);
+    let dest = backup_dir.join(hook);
+
+    if dest.exists() {
+        return Ok(false);
+    }
+
+    if !backup_dir.is_dir() {
+        create_dir(&backup_dir)?;
+    }
+
+    warn!("Preserving existing {} hook in {}", hook, dest.display());
+    fs::rename(hooks_dir.join(hook), &dest)?;
+
+    Ok(true)
 }
 
-fn install_pre_auto_gc(_hooks_d: &Path) -> io::Result<()> {
+#[cfg(unix)]
+fn write_hook<T>(path: &Path, payload: T) -> io::Result<()>
+        where T: Into<Vec<u8>> {
+    let mut output = File::create(path)?;
+    output.write_all(&payload.into())?;
+    output.set_permissions(PermissionsExt::from_mode(0o0700))?;
+    output.sync_all()?;
+
     Ok(())
 }
 
-fn install_pre_commit(_hooks_d: &Path) -> io::Result<()> {
+fn install_custom_hooks(hook_name: &str, hooks_d: &Path, context: &HookContext) -> io::Result<()> {
+    debug!("Installing custom hooks for {}...", hook_name);
+
+    let wanted = custom_hooks(hook_name, context);
+
+    for (name, payload) in &wanted {
+        write_hook(&hooks_d.join(name), *payload)?;
+    }
+
+    // clean up scripts which were installed before the repository stopped needing them
+    for (name, _) in unwanted_hooks(hook_name, &wanted) {
+        let script = hooks_d.join(name);
+
+        if script.is_file() {
+            debug!("Removing {}, it is no longer needed.", script.display());
+            fs::remove_file(&script)?;
+        }
+    }
+
     Ok(())
 }
 
-fn install_pre_push(hooks_d: &Path) -> io::Result<()> {
-    write_hook(&hooks_d.join("10-git-lfs.sh"), git_lfs::PRE_PUSH)
-}
+/// The scripts nfty installs into the `<hook>.d` directory for a given hook, as pairs of file name
+/// and payload.
+fn custom_hooks(hook_name: &str, context: &HookContext) -> Vec<(&'static str, &'static str)> {
+    let mut result = Vec::new();
 
-fn install_pre_rebase(_hooks_d: &Path) -> io::Result<()> {
-    Ok(())
+    if context.lfs {
+        match hook_name {
+            "post-checkout" => result.push(("10-git-lfs.sh", git_lfs::POST_CHECKOUT)),
+            "post-commit"   => result.push(("10-git-lfs.sh", git_lfs::POST_COMMIT)),
+            "post-merge"    => result.push(("10-git-lfs.sh", git_lfs::POST_MERGE)),
+            "pre-push"      => result.push(("10-git-lfs.sh", git_lfs::PRE_PUSH)),
+            _ => (),
+        }
+    }
+
+    match hook_name {
+        "commit-msg"         => result.push(("20-commit-msg.sh", custom::COMMIT_MSG)),
+        "post-checkout"      => result.push(("30-dependencies.sh", custom::DEPENDENCIES_POST_CHECKOUT)),
+        "post-merge"         => {
+            result.push(("30-dependencies.sh", custom::DEPENDENCIES_POST_MERGE));
+            result.push(("90-branch-clean.sh", custom::BRANCH_CLEAN));
+        }
+        "pre-commit"         => result.push(("20-pre-commit.sh", custom::PRE_COMMIT)),
+        "pre-push"           => result.push(("20-pre-push.sh", custom::PRE_PUSH)),
+        "prepare-commit-msg" => result.push(("20-prepare-commit-msg.sh", custom::PREPARE_COMMIT_MSG)),
+        _ => (),
+    }
+
+    result
 }
 
-fn install_prepare_commit_msg(_hooks_d: &Path) -> io::Result<()> {
-    Ok(())
+/// The managed scripts for a given hook which aren't in the wanted list.
+fn unwanted_hooks(hook_name: &str, wanted: &[(&'static str, &'static str)]) -> Vec<(&'static str, &'static str)> {
+    custom_hooks(hook_name, &HookContext::everything())
+        .into_iter()
+        .filter(|(name, _)| !wanted.iter().any(|(w, _)| w == name))
+        .collect()
 }
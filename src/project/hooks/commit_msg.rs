@@ -0,0 +1,158 @@
+#[cfg(test)]
+mod tests;
+
+use crate::config::CommitMsgConfig;
+
+use anyhow::{anyhow, Result};
+
+use lazy_static::lazy_static;
+
+use regex::Regex;
+
+lazy_static! {
+    // type(scope)!: description
+    static ref CONVENTIONAL_SUBJECT: Regex = Regex::new(r#"(?x)
+        ^(?P<type>[a-zA-Z]+)(?:\((?P<scope>[^()\r\n]+)\))?(?P<breaking>!)?:\x20(?P<description>\S.*)$
+    "#).unwrap();
+}
+
+/// The line below which Git discards the rest of the message when committing with `--verbose`.
+static SCISSORS: &str = "# ------------------------ >8 ------------------------";
+
+/// Subjects generated by Git itself, which are exempt from the Conventional Commits check.
+static GENERATED_PREFIXES: &[&str] = &["Merge ", "Revert \"", "fixup! ", "squash! ", "amend! "];
+
+/// Checks commit messages against the configured policy.
+pub struct Policy<'a> {
+    config: &'a CommitMsgConfig,
+    issue: Regex,
+}
+
+impl<'a> Policy<'a> {
+    pub fn new(config: &'a CommitMsgConfig) -> Result<Self> {
+        let issue = Regex::new(&config.issue_pattern)
+            .map_err(|e| anyhow!("invalid issue pattern {}: {}", config.issue_pattern, e))?;
+
+        Ok(Policy { config, issue })
+    }
+
+    /// Check a commit message, returning a description of each violation.
+    pub fn check(&self, message: &str) -> Vec<String> {
+        let message = strip_comments(message);
+        let mut lines = message.lines();
+        let mut violations = Vec::new();
+
+        let subject = match lines.next() {
+            Some(subject) if !subject.trim().is_empty() => subject,
+            _ => return vec!["the commit message is empty".to_string()],
+        };
+
+        if let Some(max) = self.config.max_subject_length {
+            let length = subject.chars().count();
+
+            if length > max {
+                violations.push(format!("the subject is {} characters long, the limit is {}", length, max));
+            }
+        }
+
+        if self.config.conventional && !GENERATED_PREFIXES.iter().any(|p| subject.starts_with(p)) {
+            match CONVENTIONAL_SUBJECT.captures(subject) {
+                Some(captures) => {
+                    let kind = &captures["type"];
+
+                    if !self.config.types.iter().any(|t| t == kind) {
+                        violations.push(format!("unknown commit type {}, expected one of: {}", kind,
+                            self.config.types.join(", ")));
+                    }
+                }
+                None => violations.push(
+                    "the subject must be formatted as `type(scope): description`".to_string()
+                ),
+            }
+
+            if lines.next().is_some_and(|l| !l.trim().is_empty()) {
+                violations.push("the subject must be followed by a blank line".to_string());
+            }
+        }
+
+        if self.config.require_issue && !self.issue.is_match(&message) {
+            violations.push(format!("the message must reference an issue matching {}", self.config.issue_pattern));
+        }
+
+        violations
+    }
+
+    /// Find the issue key in a branch name, e.g. `ABC-123` in `feature/ABC-123-foo`.
+    pub fn issue_from_branch<'b>(&self, branch: &'b str) -> Option<&'b str> {
+        self.issue.find(branch).map(|m| m.as_str())
+    }
+
+    /// Add the issue key from the branch name to a commit message which doesn't already reference
+    /// an issue. Conventional commits get a `Refs:` trailer, otherwise the key prefixes the subject.
+    ///
+    /// Returns `None` if the message should be left alone, including messages generated by Git.
+    pub fn prefill(&self, message: &str, branch: &str) -> Option<String> {
+        let stripped = strip_comments(message);
+
+        // prefixing fixup! and squash! subjects would stop them being autosquashed
+        if !self.config.prefill_issue || self.issue.is_match(&stripped)
+                || GENERATED_PREFIXES.iter().any(|p| stripped.starts_with(p)) {
+            return None;
+        }
+
+        let key = self.issue_from_branch(branch)?;
+
+        if self.config.conventional {
+            // an empty content leaves the first line free for the subject
+            let (content, trailing) = split_comments(message);
+            let separator = if trailing.is_empty() { "" } else { "\n" };
+
+            return Some(format!("{}\n\nRefs: {}\n{}{}", content.trim_end(), key, separator, trailing));
+        }
+
+        let mut lines = message.splitn(2, '\n');
+        let subject = lines.next().unwrap_or("");
+        let rest = lines.next().map_or(String::new(), |r| format!("\n{}", r));
+
+        if subject.starts_with('#') {
+            // an empty message which only has the commented instructions
+            Some(format!("{} \n{}", key, message))
+        } else {
+            Some(format!("{} {}{}", key, subject, rest))
+        }
+    }
+}
+
+/// Remove comment lines and everything below the scissors line, as Git does when committing.
+pub fn strip_comments(message: &str) -> String {
+    split_comments(message).0
+        .lines()
+        .filter(|l| !l.starts_with('#'))
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string()
+}
+
+/// Split a message into its content and the trailing comments Git appends to it, including
+/// anything below the scissors line.
+fn split_comments(message: &str) -> (String, String) {
+    let (head, scissors) = match message.find(SCISSORS) {
+        Some(index) => (&message[..index], &message[index..]),
+        None => (message, ""),
+    };
+
+    let lines = head.lines().collect::<Vec<_>>();
+    let content_lines = lines.iter().rposition(|l| !l.starts_with('#') && !l.trim().is_empty())
+        .map_or(0, |i| i + 1);
+
+    let mut trailing = lines[content_lines..].join("\n").trim_start_matches('\n').to_string();
+
+    if !trailing.is_empty() {
+        trailing.push('\n');
+    }
+
+    trailing.push_str(scissors);
+
+    (lines[..content_lines].join("\n"), trailing)
+}
@@ -0,0 +1,87 @@
+use super::*;
+
+static TEMPLATE: &str = "
+# Please enter the commit message for your changes. Lines starting
+# with '#' will be ignored, and an empty message aborts the commit.
+";
+
+fn config() -> CommitMsgConfig {
+    CommitMsgConfig {
+        conventional: true,
+        max_subject_length: Some(50),
+        require_issue: true,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_valid_messages() {
+    let config = config();
+    let policy = Policy::new(&config).unwrap();
+
+    assert!(policy.check("feat(hooks): add commit-msg policy\n\nRefs: ABC-123\n").is_empty());
+    assert!(policy.check("fix!: ABC-123 drop support for bash hooks").is_empty());
+    assert!(policy.check(&format!("docs: ABC-1 explain hooks\n{}", TEMPLATE)).is_empty());
+    assert!(policy.check("Merge branch 'feature/ABC-123-foo'").is_empty());
+}
+
+#[test]
+fn test_invalid_messages() {
+    let config = config();
+    let policy = Policy::new(&config).unwrap();
+
+    assert_eq!(1, policy.check(TEMPLATE).len());
+    assert_eq!(1, policy.check("add a thing ABC-123").len());
+    assert_eq!(1, policy.check("feature: add a thing ABC-123").len());
+    assert_eq!(1, policy.check("feat: add a thing").len());
+    assert_eq!(1, policy.check("feat: add a thing ABC-123\nwith no blank line").len());
+    assert_eq!(1, policy.check("feat: ABC-123 add a thing which has a really long subject line").len());
+}
+
+#[test]
+fn test_default_policy_is_permissive() {
+    let config = CommitMsgConfig::default();
+    let policy = Policy::new(&config).unwrap();
+
+    assert!(policy.check("whatever, this is fine and it can be as long as you'd like it to be").is_empty());
+}
+
+#[test]
+fn test_issue_from_branch() {
+    let config = CommitMsgConfig::default();
+    let policy = Policy::new(&config).unwrap();
+
+    assert_eq!(Some("ABC-123"), policy.issue_from_branch("feature/ABC-123-foo"));
+    assert_eq!(Some("OPS-7"), policy.issue_from_branch("OPS-7"));
+    assert_eq!(None, policy.issue_from_branch("feature/foo"));
+}
+
+#[test]
+fn test_prefill() {
+    // nothing is prefilled unless asked for, as plenty of branch names look like issue keys
+    let config = CommitMsgConfig::default();
+    let policy = Policy::new(&config).unwrap();
+
+    assert_eq!(None, policy.prefill("add a thing\n", "fix/UTF-8-paths"));
+    assert_eq!(None, policy.prefill("add a thing\n", "feature/ABC-123-foo"));
+
+    let config = CommitMsgConfig { prefill_issue: true, ..Default::default() };
+    let policy = Policy::new(&config).unwrap();
+
+    assert_eq!(Some(format!("ABC-123 \n{}", TEMPLATE.trim_start())),
+        policy.prefill(TEMPLATE.trim_start(), "feature/ABC-123-foo"));
+    assert_eq!(Some("ABC-123 add a thing\n".to_string()), policy.prefill("add a thing\n", "feature/ABC-123-foo"));
+    assert_eq!(None, policy.prefill("ABC-99 add a thing\n", "feature/ABC-123-foo"));
+    assert_eq!(None, policy.prefill("add a thing\n", "feature/foo"));
+    assert_eq!(None, policy.prefill("fixup! add a thing\n", "feature/ABC-123-foo"));
+    assert_eq!(None, policy.prefill("Merge branch 'main'\n", "feature/ABC-123-foo"));
+
+    let config = CommitMsgConfig { conventional: true, prefill_issue: true, ..Default::default() };
+    let policy = Policy::new(&config).unwrap();
+
+    assert_eq!(Some(format!("feat: add a thing\n\nRefs: ABC-123\n\n{}", TEMPLATE.trim_start())),
+        policy.prefill(&format!("feat: add a thing\n{}", TEMPLATE), "feature/ABC-123-foo"));
+
+    assert_eq!(Some(format!("\n\nRefs: ABC-123\n\n{}", TEMPLATE.trim_start())),
+        policy.prefill(TEMPLATE, "feature/ABC-123-foo"));
+}
@@ -0,0 +1,72 @@
+#[cfg(test)]
+mod tests;
+
+use anyhow::{anyhow, Result};
+
+use git2::{Delta, Oid, Repository};
+
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus};
+
+/// A kind of dependency manifest and the command which installs what it describes.
+pub struct Ecosystem {
+    pub name: &'static str,
+    pub manifest: &'static str,
+    pub command: &'static str,
+}
+
+/// The dependency manifests noticed when they change, matched by file name anywhere in the tree.
+static ECOSYSTEMS: &[Ecosystem] = &[
+    Ecosystem { name: "Rust", manifest: "Cargo.lock", command: "cargo fetch" },
+    Ecosystem { name: "Python", manifest: "requirements.txt", command: "pip install -r requirements.txt" },
+    Ecosystem { name: "npm", manifest: "package-lock.json", command: "npm ci" },
+    Ecosystem {
+        name: "Ansible Galaxy",
+        manifest: "requirements.yml",
+        command: "ansible-galaxy install --force -r requirements.yml -p .ansible/galaxy-roles",
+    },
+];
+
+/// A dependency manifest which changed between two commits.
+pub struct ManifestChange {
+    pub path: PathBuf,
+    pub ecosystem: &'static Ecosystem,
+}
+
+impl ManifestChange {
+    /// The directory to run the install command in, relative to the root of the repository.
+    pub fn dir(&self) -> &Path {
+        self.path.parent().unwrap_or_else(|| Path::new(""))
+    }
+
+    /// Run the install command in the manifest's directory within the given working tree.
+    pub fn install(&self, workdir: &Path) -> Result<ExitStatus> {
+        Command::new("sh")
+            .arg("-c")
+            .arg(self.ecosystem.command)
+            .current_dir(workdir.join(self.dir()))
+            .status()
+            .map_err(|e| anyhow!("unable to run {}: {}", self.ecosystem.command, e))
+    }
+}
+
+/// Find the ecosystem a file belongs to, if it is a dependency manifest.
+pub fn ecosystem(path: &Path) -> Option<&'static Ecosystem> {
+    let file_name = path.file_name()?.to_str()?;
+
+    ECOSYSTEMS.iter().find(|e| e.manifest == file_name)
+}
+
+/// List the dependency manifests added or modified between two commits.
+pub fn changed(repo: &Repository, old: Oid, new: Oid) -> Result<Vec<ManifestChange>> {
+    let old_tree = repo.find_commit(old)?.tree()?;
+    let new_tree = repo.find_commit(new)?.tree()?;
+
+    let diff = repo.diff_tree_to_tree(Some(&old_tree), Some(&new_tree), None)?;
+
+    Ok(diff.deltas()
+        .filter(|d| d.status() != Delta::Deleted)
+        .filter_map(|d| d.new_file().path().map(|p| p.to_path_buf()))
+        .filter_map(|path| ecosystem(&path).map(|ecosystem| ManifestChange { path, ecosystem }))
+        .collect())
+}
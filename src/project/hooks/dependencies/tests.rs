@@ -0,0 +1,46 @@
+use super::*;
+
+use crate::testing::{self, Scratch};
+
+#[test]
+fn test_ecosystem() {
+    assert_eq!(Some("Rust"), ecosystem(Path::new("Cargo.lock")).map(|e| e.name));
+    assert_eq!(Some("Python"), ecosystem(Path::new("app/requirements.txt")).map(|e| e.name));
+    assert_eq!(Some("Ansible Galaxy"), ecosystem(Path::new("requirements.yml")).map(|e| e.name));
+
+    assert!(ecosystem(Path::new("Cargo.toml")).is_none());
+    assert!(ecosystem(Path::new("requirements.txt.j2")).is_none());
+}
+
+#[test]
+fn test_dir() {
+    let change = ManifestChange { path: PathBuf::from("app/requirements.txt"), ecosystem: &ECOSYSTEMS[1] };
+    assert_eq!(Path::new("app"), change.dir());
+
+    let change = ManifestChange { path: PathBuf::from("Cargo.lock"), ecosystem: &ECOSYSTEMS[0] };
+    assert_eq!(Path::new(""), change.dir());
+}
+
+#[test]
+fn test_changed() {
+    let scratch = Scratch::new("dependencies-changed");
+    let repo = testing::repository(&scratch);
+
+    let first = testing::commit_file(&repo, "README.md", "# Project\n", "Initial commit.");
+    testing::commit_file(&repo, "Cargo.lock", "# lock\n", "Add a lockfile.");
+    testing::commit_file(&repo, "app/requirements.txt", "requests\n", "Add requirements.");
+    let last = testing::commit_file(&repo, "README.md", "# Project!\n", "Update the readme.");
+
+    let changes = changed(&repo, first, last).unwrap();
+    let paths = changes.iter().map(|c| c.path.clone()).collect::<Vec<_>>();
+
+    assert_eq!(vec![PathBuf::from("Cargo.lock"), PathBuf::from("app/requirements.txt")], paths);
+    assert_eq!("Python", changes[1].ecosystem.name);
+    assert_eq!(Path::new("app"), changes[1].dir());
+
+    // going back removes both manifests, which isn't anything to install
+    assert!(changed(&repo, last, first).unwrap().is_empty());
+
+    // nothing changed
+    assert!(changed(&repo, last, last).unwrap().is_empty());
+}
@@ -0,0 +1,149 @@
+#[cfg(test)]
+mod tests;
+
+use log::debug;
+
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::io::prelude::*;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Stdio};
+use std::time::{Duration, Instant};
+
+/// Hooks which receive input from Git on standard input. For these, standard input is read once
+/// and replayed to every script in the `<hook>.d` directory, rather than being consumed by the
+/// first script to read it.
+static STDIN_HOOK_TYPES: &[&str] = &[
+    "post-rewrite",
+    "pre-push",
+];
+
+/// The result of running a single script from a `<hook>.d` directory.
+pub struct ScriptResult {
+    pub script: PathBuf,
+    pub status: ExitStatus,
+    pub elapsed: Duration,
+}
+
+impl ScriptResult {
+    /// The name of the script, e.g. `10-git-lfs.sh`.
+    pub fn name(&self) -> String {
+        self.script.file_name().map_or(String::new(), |n| n.to_string_lossy().to_string())
+    }
+
+    /// The exit code to propagate back to Git. Scripts killed by a signal yield `1`.
+    pub fn code(&self) -> i32 {
+        self.status.code().unwrap_or(1)
+    }
+}
+
+/// Runs the scripts installed for a single Git hook.
+pub struct Dispatcher {
+    hook_type: String,
+    hooks_d: PathBuf,
+}
+
+impl Dispatcher {
+    /// Create a dispatcher for the given hook type within a Git hooks directory.
+    pub fn new(hooks_dir: &Path, hook_type: &str) -> Self {
+        Dispatcher {
+            hook_type: hook_type.to_string(),
+            hooks_d: hooks_dir.join(format!("{}.d", hook_type)),
+        }
+    }
+
+    /// The hook type being dispatched, e.g. `pre-push`.
+    pub fn hook_type(&self) -> &str {
+        &self.hook_type
+    }
+
+    /// Find all executable files in the `<hook>.d` directory in sorted order.
+    ///
+    /// A missing directory simply means that there is nothing to run.
+    pub fn discover(&self) -> io::Result<Vec<PathBuf>> {
+        if !self.hooks_d.is_dir() {
+            debug!("No {} directory, nothing to run.", self.hooks_d.display());
+            return Ok(Vec::new());
+        }
+
+        let mut scripts = fs::read_dir(&self.hooks_d)?
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| is_executable(p))
+            .collect::<Vec<_>>();
+
+        scripts.sort();
+
+        Ok(scripts)
+    }
+
+    /// Run each script in order, forwarding arguments and standard input, stopping at the first
+    /// script which fails. The returned results end with the failing script, if any.
+    pub fn run(&self, args: &[OsString]) -> io::Result<Vec<ScriptResult>> {
+        self.run_from(args, &mut io::stdin())
+    }
+
+    /// Run each script in order as `run` does, replaying the given input rather than standard input
+    /// to hooks which receive input from Git.
+    pub fn run_from<R>(&self, args: &[OsString], stdin: &mut R) -> io::Result<Vec<ScriptResult>> where R: Read {
+        let scripts = self.discover()?;
+
+        let input = if STDIN_HOOK_TYPES.contains(&self.hook_type.as_str()) && !scripts.is_empty() {
+            let mut buffer = Vec::new();
+            stdin.read_to_end(&mut buffer)?;
+            Some(buffer)
+        } else {
+            None
+        };
+
+        let mut results = Vec::with_capacity(scripts.len());
+
+        for script in scripts {
+            let result = self.run_script(script, args, input.as_deref())?;
+            let failed = !result.status.success();
+
+            debug!("git:{} - {} exited with {} in {:.3}s", self.hook_type, result.name(), result.status,
+                result.elapsed.as_secs_f64());
+
+            results.push(result);
+
+            if failed {
+                break;
+            }
+        }
+
+        Ok(results)
+    }
+
+    fn run_script(&self, script: PathBuf, args: &[OsString], input: Option<&[u8]>) -> io::Result<ScriptResult> {
+        let started = Instant::now();
+
+        let mut child = Command::new(&script)
+            .args(args)
+            .stdin(if input.is_some() { Stdio::piped() } else { Stdio::inherit() })
+            .spawn()?;
+
+        if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+            // scripts are free to ignore their input, so a closed pipe isn't an error
+            match stdin.write_all(input) {
+                Err(ref e) if e.kind() == io::ErrorKind::BrokenPipe => (),
+                other => other?,
+            }
+        }
+
+        let status = child.wait()?;
+
+        Ok(ScriptResult {
+            script,
+            status,
+            elapsed: started.elapsed(),
+        })
+    }
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    fs::metadata(path).map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0).unwrap_or(false)
+}
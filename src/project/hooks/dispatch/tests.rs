@@ -0,0 +1,74 @@
+use super::*;
+
+use crate::testing::Scratch;
+
+/// Write a script into a `<hook>.d` directory with the given permissions.
+fn script(hooks_d: &Path, name: &str, body: &str, mode: u32) {
+    fs::create_dir_all(hooks_d).unwrap();
+
+    let path = hooks_d.join(name);
+    fs::write(&path, format!("#!/bin/sh\n{}\n", body)).unwrap();
+    fs::set_permissions(&path, fs::Permissions::from_mode(mode)).unwrap();
+}
+
+#[test]
+fn test_discover() {
+    let hooks = Scratch::new("dispatch-discover");
+    let hooks_d = hooks.join("pre-commit.d");
+
+    // nothing to run without a directory
+    assert!(Dispatcher::new(&hooks, "pre-commit").discover().unwrap().is_empty());
+
+    script(&hooks_d, "20-second", "true", 0o755);
+    script(&hooks_d, "10-first", "true", 0o700);
+    script(&hooks_d, "15-disabled", "true", 0o644);
+    fs::create_dir_all(hooks_d.join("30-directory")).unwrap();
+    fs::set_permissions(hooks_d.join("30-directory"), fs::Permissions::from_mode(0o755)).unwrap();
+
+    // only executable files, in sorted order
+    assert_eq!(vec![hooks_d.join("10-first"), hooks_d.join("20-second")],
+        Dispatcher::new(&hooks, "pre-commit").discover().unwrap());
+}
+
+#[test]
+fn test_run_replays_stdin() {
+    let hooks = Scratch::new("dispatch-stdin");
+    let hooks_d = hooks.join("pre-push.d");
+
+    for name in ["10-first", "20-second"].iter() {
+        script(&hooks_d, name, &format!("echo \"$@\" > {0}.args\ncat > {0}.stdin", hooks.join(name).display()),
+            0o755);
+    }
+
+    let input = "refs/heads/main 1111 refs/heads/main 2222\n";
+    let args = vec![OsString::from("origin"), OsString::from("git@github.com:naftulikay/nfty")];
+
+    let results = Dispatcher::new(&hooks, "pre-push").run_from(&args, &mut input.as_bytes()).unwrap();
+
+    assert_eq!(vec!["10-first", "20-second"], results.iter().map(|r| r.name()).collect::<Vec<_>>());
+    assert!(results.iter().all(|r| r.code() == 0));
+
+    // every script gets the arguments and the whole of the input, not just the first to read it
+    for name in ["10-first", "20-second"].iter() {
+        assert_eq!("origin git@github.com:naftulikay/nfty\n",
+            fs::read_to_string(hooks.join(format!("{}.args", name))).unwrap());
+        assert_eq!(input, fs::read_to_string(hooks.join(format!("{}.stdin", name))).unwrap());
+    }
+}
+
+#[test]
+fn test_run_stops_at_first_failure() {
+    let hooks = Scratch::new("dispatch-failure");
+    let hooks_d = hooks.join("pre-commit.d");
+
+    script(&hooks_d, "10-passes", "true", 0o755);
+    script(&hooks_d, "20-fails", "exit 3", 0o755);
+    script(&hooks_d, "30-never-runs", &format!("touch {}", hooks.join("ran").display()), 0o755);
+
+    let results = Dispatcher::new(&hooks, "pre-commit").run_from(&[], &mut io::empty()).unwrap();
+
+    // the results end with the script which failed, whose exit code is passed on to Git
+    assert_eq!(vec!["10-passes", "20-fails"], results.iter().map(|r| r.name()).collect::<Vec<_>>());
+    assert_eq!(3, results.last().unwrap().code());
+    assert!(!hooks.join("ran").exists());
+}
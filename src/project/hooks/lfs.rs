@@ -0,0 +1,54 @@
+#[cfg(test)]
+mod tests;
+
+use git2::{ConfigLevel, Repository};
+
+use log::debug;
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// The attribute which routes a path through the Git LFS filter.
+static LFS_FILTER_ATTRIBUTE: &str = "filter=lfs";
+
+/// The local configuration written by `git lfs install --local`.
+static LFS_FILTER_CONFIG: &[(&str, &str)] = &[
+    ("filter.lfs.clean", "git-lfs clean -- %f"),
+    ("filter.lfs.smudge", "git-lfs smudge -- %f"),
+    ("filter.lfs.process", "git-lfs filter-process"),
+];
+
+/// Determine whether the repository at the given path stores any files in Git LFS, by looking for
+/// the LFS filter in its `.gitattributes` or `.git/info/attributes`.
+pub fn in_use(path: &Path) -> bool {
+    [path.join(".gitattributes"), path.join(".git").join("info").join("attributes")]
+        .iter()
+        .filter_map(|p| fs::read_to_string(p).ok())
+        .any(|contents| uses_lfs_filter(&contents))
+}
+
+/// Determine whether the contents of a Git attributes file route any paths through Git LFS.
+pub fn uses_lfs_filter(attributes: &str) -> bool {
+    attributes
+        .lines()
+        .map(|l| l.trim())
+        .filter(|l| !l.starts_with('#'))
+        .any(|l| l.split_whitespace().skip(1).any(|attr| attr == LFS_FILTER_ATTRIBUTE))
+}
+
+/// Configure the LFS filters in the repository's local configuration, the equivalent of
+/// `git lfs install --local` without its hooks, which nfty manages itself.
+pub fn install_filters(path: &Path) -> io::Result<()> {
+    let to_io = |e: git2::Error| io::Error::other(e.to_string());
+
+    let repo = Repository::open(path).map_err(to_io)?;
+    let mut config = repo.config().and_then(|c| c.open_level(ConfigLevel::Local)).map_err(to_io)?;
+
+    for (name, value) in LFS_FILTER_CONFIG {
+        debug!("Setting {} = {}", name, value);
+        config.set_str(name, value).map_err(to_io)?;
+    }
+
+    config.set_bool("filter.lfs.required", true).map_err(to_io)
+}
@@ -0,0 +1,12 @@
+use super::*;
+
+#[test]
+fn test_uses_lfs_filter() {
+    assert!(uses_lfs_filter("*.psd filter=lfs diff=lfs merge=lfs -text\n"));
+    assert!(uses_lfs_filter("*.sh text eol=lf\nassets/** filter=lfs -text\n"));
+
+    assert!(!uses_lfs_filter(""));
+    assert!(!uses_lfs_filter("*.sh text eol=lf\n"));
+    assert!(!uses_lfs_filter("# *.psd filter=lfs diff=lfs merge=lfs -text\n"));
+    assert!(!uses_lfs_filter("*.psd filter=lfs-custom\n"));
+}
@@ -0,0 +1,314 @@
+#[cfg(test)]
+mod tests;
+
+use crate::config::{CONFIG_ROOT, PROJECT_CONFIG_DIR};
+use crate::project::Project;
+
+use super::{custom_hooks, HookContext, HookState, HookStatus, HOOK_TYPES};
+
+use log::{debug, warn};
+
+use serde_derive::Deserialize;
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::os::unix::fs::symlink;
+use std::path::{Path, PathBuf};
+
+/// The file within a hook pack describing where its scripts are enabled.
+static PACK_MANIFEST: &str = "pack.yml";
+
+/// The ordering prefix given to scripts which don't declare their own, e.g. `lint.sh` is installed
+/// as `50-lint.sh`.
+static DEFAULT_PREFIX: &str = "50";
+
+/// The optional `pack.yml` at the root of a hook pack.
+///
+/// ```yaml
+/// scripts:
+///   pre-commit.d/20-rustfmt.sh:
+///     owners: [naftulikay]
+/// trusted:
+///   hosts: [github.com]
+///   owners: [naftulikay]
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct PackManifest {
+    /// Rules restricting where individual scripts are enabled, keyed by `<hook>.d/<script>`.
+    /// Scripts without a rule are enabled everywhere.
+    pub scripts: BTreeMap<String, EnableRule>,
+    /// Only read from the user pack: the projects whose committed `.nfty/hooks` are installed.
+    /// Hooks committed to a repository are never installed unless the project is trusted here.
+    pub trusted: Option<EnableRule>,
+}
+
+/// Restricts something to projects on the given hosts and/or belonging to the given owners. Empty
+/// lists match anything.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct EnableRule {
+    pub hosts: Vec<String>,
+    pub owners: Vec<String>,
+}
+
+impl EnableRule {
+    /// Determine whether this rule matches the given project. Restrictive rules never match a
+    /// repository which can't be identified as a project.
+    pub fn matches(&self, project: Option<&Project>) -> bool {
+        if self.hosts.is_empty() && self.owners.is_empty() {
+            return true;
+        }
+
+        project.is_some_and(|p| {
+            (self.hosts.is_empty() || self.hosts.iter().any(|h| h == p.host()))
+                && (self.owners.is_empty() || self.owners.iter().any(|o| o == p.owner()))
+        })
+    }
+}
+
+/// A script from a hook pack, and the name it is installed as in `.git/hooks/<hook>.d`.
+pub struct PackScript {
+    pub hook: String,
+    pub name: String,
+    pub source: PathBuf,
+}
+
+/// A directory of `<hook>.d` directories holding scripts to link into a repository's hooks.
+pub struct HookPack {
+    root: PathBuf,
+    manifest: PackManifest,
+}
+
+impl HookPack {
+    /// Load the hook pack at the given directory, if it exists.
+    pub fn load(root: &Path) -> io::Result<Option<Self>> {
+        if !root.is_dir() {
+            return Ok(None);
+        }
+
+        let manifest_path = root.join(PACK_MANIFEST);
+
+        let manifest = if manifest_path.is_file() {
+            serde_yaml::from_reader(fs::File::open(&manifest_path)?).map_err(|e| {
+                io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", manifest_path.display(), e))
+            })?
+        } else {
+            PackManifest::default()
+        };
+
+        Ok(Some(HookPack { root: root.to_path_buf(), manifest }))
+    }
+
+    /// The user's hook pack in `~/.config/nfty/hooks`.
+    pub fn user() -> io::Result<Option<Self>> {
+        HookPack::load(&user_root())
+    }
+
+    /// Determine whether this pack, as the user's hook pack, trusts what is committed to the given
+    /// project.
+    pub fn trusts(&self, project: Option<&Project>) -> bool {
+        self.manifest.trusted.as_ref().is_some_and(|rule| rule.matches(project))
+    }
+
+    /// The hook pack committed to the project at the given path in `.nfty/hooks`.
+    pub fn project(path: &Path) -> io::Result<Option<Self>> {
+        HookPack::load(&project_root(path))
+    }
+
+    /// List the scripts in this pack which are enabled for the given project, in installation
+    /// order.
+    pub fn scripts(&self, project: Option<&Project>) -> io::Result<Vec<PackScript>> {
+        let mut result = Vec::new();
+
+        for hook in HOOK_TYPES {
+            let hooks_d = self.root.join(format!("{}.d", hook));
+
+            if !hooks_d.is_dir() {
+                continue;
+            }
+
+            let mut sources = fs::read_dir(&hooks_d)?
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .filter(|p| p.is_file())
+                .collect::<Vec<_>>();
+
+            sources.sort();
+
+            for source in sources {
+                let file_name = source.file_name().unwrap().to_string_lossy().to_string();
+                let key = format!("{}.d/{}", hook, file_name);
+
+                if !self.manifest.scripts.get(&key).is_none_or(|r| r.matches(project)) {
+                    debug!("Hook pack script {} is not enabled for this project.", source.display());
+                    continue;
+                }
+
+                result.push(PackScript {
+                    hook: hook.to_string(),
+                    name: ordered_name(&file_name),
+                    source,
+                });
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+/// The root of the user's hook pack.
+fn user_root() -> PathBuf {
+    CONFIG_ROOT.join("hooks")
+}
+
+/// The root of the hook pack committed to the project at the given path.
+fn project_root(path: &Path) -> PathBuf {
+    path.join(PROJECT_CONFIG_DIR).join("hooks")
+}
+
+/// Give a script an ordering prefix if it doesn't already start with one.
+pub fn ordered_name(file_name: &str) -> String {
+    let digits = file_name.chars().take_while(|c| c.is_ascii_digit()).count();
+
+    if digits > 0 && file_name[digits..].starts_with('-') {
+        file_name.to_string()
+    } else {
+        format!("{}-{}", DEFAULT_PREFIX, file_name)
+    }
+}
+
+/// Determine whether the user trusts what is committed to the given project, by the `trusted` rule
+/// of their hook pack.
+pub fn is_trusted(project: Option<&Project>) -> io::Result<bool> {
+    Ok(HookPack::user()?.is_some_and(|u| u.trusts(project)))
+}
+
+/// Resolve every pack script to install into the repository at the given path. The user pack is
+/// resolved first so that scripts committed to a trusted project take precedence.
+pub fn resolve(path: &Path, project: Option<&Project>) -> io::Result<Vec<PackScript>> {
+    let mut scripts: BTreeMap<(String, String), PackScript> = BTreeMap::new();
+    let mut packs = Vec::new();
+
+    let user = HookPack::user()?;
+    let trusted = user.as_ref().is_some_and(|u| u.trusts(project));
+
+    if let Some(pack) = user {
+        packs.push(pack);
+    }
+
+    if let Some(pack) = HookPack::project(path)? {
+        if trusted {
+            packs.push(pack);
+        } else {
+            debug!("Not installing hooks from {}, the project isn't trusted.", pack.root.display());
+        }
+    }
+
+    for pack in packs {
+        for script in pack.scripts(project)? {
+            scripts.insert((script.hook.clone(), script.name.clone()), script);
+        }
+    }
+
+    Ok(scripts.into_values().collect())
+}
+
+/// Link the resolved pack scripts into the repository's `<hook>.d` directories and remove links to
+/// scripts which are no longer enabled.
+pub fn install(path: &Path, project: Option<&Project>) -> io::Result<()> {
+    let hooks_dir = super::hooks_dir(path)?;
+    let scripts = resolve(path, project)?;
+
+    // remove links from any pack which are no longer wanted
+    for link in links(path)? {
+        let wanted = scripts.iter().any(|s| hooks_dir.join(format!("{}.d", s.hook)).join(&s.name) == link);
+
+        if !wanted {
+            debug!("Removing stale hook pack script {}", link.display());
+            fs::remove_file(&link)?;
+        }
+    }
+
+    for script in scripts {
+        let hooks_d = hooks_dir.join(format!("{}.d", script.hook));
+        let dest = hooks_d.join(&script.name);
+
+        if custom_hooks(&script.hook, &HookContext::everything()).iter().any(|(name, _)| *name == script.name) {
+            warn!("Not installing {}, it would replace a hook managed by nfty.", script.source.display());
+            continue;
+        }
+
+        if let Ok(meta) = fs::symlink_metadata(&dest) {
+            if !meta.file_type().is_symlink() {
+                warn!("Not installing {}, {} already exists.", script.source.display(), dest.display());
+                continue;
+            }
+
+            fs::remove_file(&dest)?;
+        }
+
+        if !hooks_d.is_dir() {
+            fs::create_dir(&hooks_d)?;
+        }
+
+        debug!("Linking {} to {}", dest.display(), script.source.display());
+        symlink(&script.source, &dest)?;
+    }
+
+    Ok(())
+}
+
+/// Remove every link to a pack script from the repository at the given path.
+pub fn uninstall(path: &Path) -> io::Result<()> {
+    for link in links(path)? {
+        debug!("Removing hook pack script {}", link.display());
+        fs::remove_file(&link)?;
+    }
+
+    Ok(())
+}
+
+/// Compare the links in the repository at the given path with the resolved pack scripts.
+pub fn status(path: &Path, project: Option<&Project>) -> io::Result<Vec<HookStatus>> {
+    let hooks_dir = super::hooks_dir(path)?;
+
+    Ok(resolve(path, project)?.into_iter().map(|script| {
+        let relative = PathBuf::from(format!("{}.d", script.hook)).join(&script.name);
+
+        let state = match fs::read_link(hooks_dir.join(&relative)) {
+            Ok(ref target) if *target == script.source => HookState::Current,
+            Ok(_) => HookState::Drifted,
+            Err(_) if hooks_dir.join(&relative).exists() => HookState::Foreign,
+            Err(_) => HookState::Missing,
+        };
+
+        HookStatus { path: relative, state }
+    }).collect())
+}
+
+/// Find every symlink in the repository's `<hook>.d` directories which points into a hook pack.
+fn links(path: &Path) -> io::Result<Vec<PathBuf>> {
+    let hooks_dir = super::hooks_dir(path)?;
+    let roots = [user_root(), project_root(path)];
+    let mut result = Vec::new();
+
+    for hook in HOOK_TYPES {
+        let hooks_d = hooks_dir.join(format!("{}.d", hook));
+
+        if !hooks_d.is_dir() {
+            continue;
+        }
+
+        for entry in fs::read_dir(&hooks_d)?.filter_map(|e| e.ok()) {
+            if let Ok(target) = fs::read_link(entry.path()) {
+                if roots.iter().any(|r| target.starts_with(r)) {
+                    result.push(entry.path());
+                }
+            }
+        }
+    }
+
+    Ok(result)
+}
@@ -0,0 +1,23 @@
+use super::*;
+
+#[test]
+fn test_ordered_name() {
+    assert_eq!("10-lint.sh", ordered_name("10-lint.sh"));
+    assert_eq!("50-lint.sh", ordered_name("lint.sh"));
+    assert_eq!("50-2fa.sh", ordered_name("2fa.sh"));
+}
+
+#[test]
+fn test_enable_rule() {
+    let project = Project::from("github.com:naftulikay/nfty").unwrap();
+
+    assert!(EnableRule::default().matches(Some(&project)));
+    assert!(EnableRule::default().matches(None));
+
+    let rule: EnableRule = serde_yaml::from_str("owners: [naftulikay]").unwrap();
+    assert!(rule.matches(Some(&project)));
+    assert!(!rule.matches(None));
+
+    let rule: EnableRule = serde_yaml::from_str("hosts: [gitlab.com]\nowners: [naftulikay]").unwrap();
+    assert!(!rule.matches(Some(&project)));
+}
@@ -0,0 +1,260 @@
+#[cfg(test)]
+mod tests;
+
+use crate::config::PreCommitConfig;
+
+use anyhow::{anyhow, Result};
+
+use git2::{Delta, Repository};
+
+use glob::Pattern;
+
+use lazy_static::lazy_static;
+
+use log::info;
+
+use regex::Regex;
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+lazy_static! {
+    // well-known credential formats
+    static ref SECRET_PATTERNS: Vec<(&'static str, Regex)> = vec![
+        ("a private key", Regex::new(r"-----BEGIN ((RSA|DSA|EC|OPENSSH|PGP|ENCRYPTED) )?PRIVATE KEY( BLOCK)?-----").unwrap()),
+        ("an AWS access key ID", Regex::new(r"\b(AKIA|ASIA|AGPA|AIDA|AROA|AIPA|ANPA|ANVA)[A-Z0-9]{16}\b").unwrap()),
+        ("a GitHub token", Regex::new(r"\bgh[pousr]_[A-Za-z0-9]{36,}\b").unwrap()),
+        ("a Slack token", Regex::new(r"\bxox[abposr]-[A-Za-z0-9-]{10,}").unwrap()),
+    ];
+
+    // a value assigned to a name which looks like it holds a secret, e.g. `aws_secret_access_key = ...`
+    static ref SECRET_ASSIGNMENT: Regex = Regex::new(r#"(?ix)
+        (?:secret|token|passw(?:or)?d|credential|api_?key|private_?key)[a-z0-9_-]*["']?\s*[:=]\s*["']?
+        (?P<value>[A-Za-z0-9+/=_-]{20,})
+    "#).unwrap();
+}
+
+/// Values assigned to secret-looking names with at least this much Shannon entropy per character
+/// are considered secrets. Random base64 is close to 6, English prose and identifiers are closer
+/// to 3.
+static SECRET_ENTROPY_THRESHOLD: f64 = 4.0;
+
+/// A problem found in a staged file.
+#[derive(Debug, PartialEq)]
+pub struct Finding {
+    pub path: PathBuf,
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{}: {}", self.path.display(), line, self.message),
+            None => write!(f, "{}: {}", self.path.display(), self.message),
+        }
+    }
+}
+
+/// Check the files staged in the repository's index, reading their contents from the index rather
+/// than the working tree. If autofix is enabled, trailing whitespace is stripped in the index and,
+/// where the working tree file matches what was staged, in the working tree too.
+pub fn check(repo: &Repository, config: &PreCommitConfig) -> Result<Vec<Finding>> {
+    let exclude = config.exclude.iter()
+        .map(|p| Pattern::new(p).map_err(|e| anyhow!("invalid exclude pattern {}: {}", p, e)))
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut index = repo.index()?;
+    let head = match repo.head() {
+        Ok(head) => Some(head.peel_to_tree()?),
+        // there's nothing to compare against before the first commit
+        Err(_) => None,
+    };
+
+    let diff = repo.diff_tree_to_index(head.as_ref(), Some(&index), None)?;
+    let mut staged = BTreeMap::new();
+
+    for delta in diff.deltas() {
+        match delta.status() {
+            Delta::Added | Delta::Modified | Delta::Copied | Delta::Renamed | Delta::Typechange => (),
+            _ => continue,
+        }
+
+        if let Some(path) = delta.new_file().path() {
+            if !exclude.iter().any(|p| p.matches_path(path)) {
+                staged.insert(path.to_path_buf(), delta.new_file().id());
+            }
+        }
+    }
+
+    let mut findings = Vec::new();
+    let mut fixed = false;
+
+    for (path, id) in staged {
+        let blob = repo.find_blob(id)?;
+        let content = blob.content();
+
+        if let Some(max) = config.max_file_size {
+            if content.len() as u64 > max {
+                // a file stored in LFS is staged as a small pointer, so large blobs aren't in LFS
+                findings.push(Finding {
+                    path: path.clone(),
+                    line: None,
+                    message: format!("file is {} bytes, larger than the {} byte limit; consider Git LFS",
+                        content.len(), max),
+                });
+            }
+        }
+
+        // the remaining checks only apply to text
+        let text = match std::str::from_utf8(content) {
+            Ok(text) if !content.contains(&0) => text,
+            _ => continue,
+        };
+
+        let mut problems = Vec::new();
+
+        if config.conflict_markers {
+            problems.extend(check_conflict_markers(text));
+        }
+
+        if config.secrets {
+            problems.extend(check_secrets(text));
+        }
+
+        if config.whitespace {
+            let whitespace = check_whitespace(text);
+
+            // only trailing whitespace is fixed, so a missing final newline is still reported
+            if config.autofix && whitespace.iter().any(|(line, _)| line.is_some()) {
+                let text = fix_staged(repo, &mut index, &path, text)?;
+                info!("Fixed whitespace in {}", path.display());
+                fixed = true;
+
+                problems.extend(check_whitespace(&text));
+            } else {
+                problems.extend(whitespace);
+            }
+        }
+
+        findings.extend(problems.into_iter().map(|(line, message)| Finding {
+            path: path.clone(),
+            line,
+            message,
+        }));
+    }
+
+    if fixed {
+        index.write()?;
+    }
+
+    Ok(findings)
+}
+
+/// Replace a staged file with its whitespace fixed, fixing the working tree copy too if it has no
+/// unstaged changes. Returns the fixed text.
+fn fix_staged(repo: &Repository, index: &mut git2::Index, path: &Path, text: &str) -> Result<String> {
+    let fixed = fix_whitespace(text);
+    let mut entry = index.get_path(path, 0).ok_or_else(|| anyhow!("{} is not staged", path.display()))?;
+
+    entry.id = repo.blob(fixed.as_bytes())?;
+    entry.file_size = fixed.len() as u32;
+    index.add(&entry)?;
+
+    if let Some(workdir) = repo.workdir() {
+        let worktree_path = workdir.join(path);
+
+        if fs::read(&worktree_path).map(|c| c == text.as_bytes()).unwrap_or(false) {
+            fs::write(&worktree_path, &fixed)?;
+        }
+    }
+
+    Ok(fixed)
+}
+
+/// Find lines with trailing whitespace and a missing final newline.
+pub fn check_whitespace(text: &str) -> Vec<(Option<usize>, String)> {
+    let mut result = text.lines()
+        .enumerate()
+        .filter(|(_, l)| l.ends_with(' ') || l.ends_with('\t'))
+        .map(|(n, _)| (Some(n + 1), "trailing whitespace".to_string()))
+        .collect::<Vec<_>>();
+
+    if !text.is_empty() && !text.ends_with('\n') {
+        result.push((None, "no newline at end of file".to_string()));
+    }
+
+    result
+}
+
+/// Strip trailing spaces and tabs from every line, keeping each line terminator, `\n` or `\r\n`,
+/// as it was, including the lack of one at the end of the text.
+pub fn fix_whitespace(text: &str) -> String {
+    text.split_inclusive('\n')
+        .map(|line| {
+            let terminator = match line.ends_with("\r\n") {
+                true => "\r\n",
+                false if line.ends_with('\n') => "\n",
+                false => "",
+            };
+
+            format!("{}{}", line[..line.len() - terminator.len()].trim_end_matches([' ', '\t']), terminator)
+        })
+        .collect()
+}
+
+/// Find merge conflict markers. A bare `=======` line is only reported alongside the other
+/// markers, as it is also used to underline headings.
+pub fn check_conflict_markers(text: &str) -> Vec<(Option<usize>, String)> {
+    let markers = text.lines()
+        .enumerate()
+        .filter(|(_, l)| l.starts_with("<<<<<<< ") || l.starts_with(">>>>>>> ") || *l == "=======")
+        .map(|(n, l)| (n + 1, l))
+        .collect::<Vec<_>>();
+
+    if !markers.iter().any(|(_, l)| *l != "=======") {
+        return Vec::new();
+    }
+
+    markers.into_iter()
+        .map(|(n, _)| (Some(n), "merge conflict marker".to_string()))
+        .collect()
+}
+
+/// Find well-known credentials and high-entropy values assigned to secret-looking names.
+pub fn check_secrets(text: &str) -> Vec<(Option<usize>, String)> {
+    let mut result = Vec::new();
+
+    for (n, line) in text.lines().enumerate() {
+        if let Some((kind, _)) = SECRET_PATTERNS.iter().find(|(_, re)| re.is_match(line)) {
+            result.push((Some(n + 1), format!("possible secret: {}", kind)));
+            continue;
+        }
+
+        if let Some(captures) = SECRET_ASSIGNMENT.captures(line) {
+            if entropy(&captures["value"]) >= SECRET_ENTROPY_THRESHOLD {
+                result.push((Some(n + 1), "possible secret: a high-entropy value".to_string()));
+            }
+        }
+    }
+
+    result
+}
+
+/// Calculate the Shannon entropy of a string in bits per character.
+pub fn entropy(value: &str) -> f64 {
+    let mut counts = BTreeMap::new();
+
+    for c in value.chars() {
+        *counts.entry(c).or_insert(0usize) += 1;
+    }
+
+    let length = value.chars().count() as f64;
+
+    counts.values()
+        .map(|&count| count as f64 / length)
+        .map(|p| -p * p.log2())
+        .sum()
+}
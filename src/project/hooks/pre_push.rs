@@ -0,0 +1,172 @@
+#[cfg(test)]
+mod tests;
+
+use crate::config::{Config, PrePushConfig};
+use crate::project::branches::is_protected;
+
+use anyhow::{anyhow, Result};
+
+use git2::{Oid, Repository};
+
+use glob::Pattern;
+
+use lazy_static::lazy_static;
+
+use regex::Regex;
+
+lazy_static! {
+    // WIP, wip:, [WIP] and friends, or an uppercase WIP anywhere, e.g. after an issue key
+    static ref WIP_SUBJECT: Regex = Regex::new(r"(?i:^(\[wip\]|wip\b))|\bWIP\b").unwrap();
+}
+
+/// The environment variable which, when set to `1`, skips the pre-push checks.
+pub static SKIP_VARIABLE: &str = "NFTY_SKIP_PRE_PUSH";
+
+/// Subjects generated by `git commit --fixup` and `--squash`, meant to be squashed before pushing.
+static AUTOSQUASH_PREFIXES: &[&str] = &["fixup! ", "squash! ", "amend! "];
+
+/// A ref update Git is about to push, parsed from a line of the pre-push hook's standard input.
+#[derive(Debug, PartialEq)]
+pub struct RefUpdate {
+    pub local_ref: String,
+    pub local_oid: Oid,
+    pub remote_ref: String,
+    pub remote_oid: Oid,
+}
+
+impl RefUpdate {
+    /// Parse a `<local ref> <local sha> <remote ref> <remote sha>` line.
+    pub fn parse(line: &str) -> Result<Self> {
+        let fields = line.split_whitespace().collect::<Vec<_>>();
+
+        if fields.len() != 4 {
+            return Err(anyhow!("unexpected pre-push input: {}", line));
+        }
+
+        Ok(RefUpdate {
+            local_ref: fields[0].to_string(),
+            local_oid: Oid::from_str(fields[1])?,
+            remote_ref: fields[2].to_string(),
+            remote_oid: Oid::from_str(fields[3])?,
+        })
+    }
+
+    /// Parse every line of the pre-push hook's standard input.
+    pub fn parse_all(input: &str) -> Result<Vec<Self>> {
+        input.lines().filter(|l| !l.trim().is_empty()).map(RefUpdate::parse).collect()
+    }
+
+    /// The name of the branch being updated on the remote, if a branch is being updated.
+    pub fn branch(&self) -> Option<&str> {
+        self.remote_ref.strip_prefix("refs/heads/")
+    }
+
+    /// Whether this update deletes the ref on the remote.
+    pub fn is_delete(&self) -> bool {
+        self.local_oid.is_zero()
+    }
+
+    /// Whether this update creates the ref on the remote.
+    pub fn is_create(&self) -> bool {
+        self.remote_oid.is_zero()
+    }
+}
+
+/// The problems found with a push. Errors reject the push, warnings are only reported.
+#[derive(Debug, Default)]
+pub struct PushReport {
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+/// Checks pushed refs against the configured protected branches.
+pub struct Guard<'a> {
+    config: &'a PrePushConfig,
+    protected: Vec<Pattern>,
+}
+
+impl<'a> Guard<'a> {
+    pub fn new(config: &'a Config) -> Result<Self> {
+        let protected = config.pre_push.protected.as_ref().unwrap_or(&config.branches.protected).iter()
+            .map(|p| Pattern::new(p).map_err(|e| anyhow!("invalid protected branch pattern {}: {}", p, e)))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Guard { config: &config.pre_push, protected })
+    }
+
+    /// Check the ref updates being pushed to the named remote.
+    pub fn check(&self, repo: &Repository, remote: &str, updates: &[RefUpdate]) -> Result<PushReport> {
+        let mut report = PushReport::default();
+
+        for update in updates {
+            if let Some(branch) = update.branch().filter(|b| is_protected(b, &self.protected)) {
+                if update.is_delete() {
+                    if self.config.block_direct || self.config.block_force {
+                        report.errors.push(format!("deleting protected branch {} is not allowed", branch));
+                    }
+                } else if update.is_create() {
+                    // a branch which doesn't exist on the remote yet has no history to protect
+                } else if self.config.block_direct {
+                    report.errors.push(format!("pushing directly to protected branch {} is not allowed", branch));
+                } else if self.config.block_force && !is_fast_forward(repo, update)? {
+                    report.errors.push(format!("pushing to protected branch {} would rewrite its history", branch));
+                }
+            }
+
+            if self.config.warn_wip && !update.is_delete() {
+                for (id, subject) in pushed_commits(repo, remote, update)? {
+                    if is_wip(&subject) {
+                        report.warnings.push(format!("pushing unfinished commit {} {} to {}",
+                            &id.to_string()[..7], subject, update.remote_ref));
+                    }
+                }
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+/// Determine whether a commit subject marks a work-in-progress commit or one meant to be squashed.
+pub fn is_wip(subject: &str) -> bool {
+    WIP_SUBJECT.is_match(subject) || AUTOSQUASH_PREFIXES.iter().any(|p| subject.starts_with(p))
+}
+
+/// Determine whether an update only adds commits to the remote ref. An unknown remote commit means
+/// the remote has history we don't, so the update can't be a fast-forward.
+fn is_fast_forward(repo: &Repository, update: &RefUpdate) -> Result<bool> {
+    if update.is_create() || update.local_oid == update.remote_oid {
+        return Ok(true);
+    }
+
+    if repo.find_commit(update.remote_oid).is_err() {
+        return Ok(false);
+    }
+
+    Ok(repo.graph_descendant_of(update.local_oid, update.remote_oid)?)
+}
+
+/// List the id and subject of each commit which the update adds to the remote, skipping anything
+/// already on one of its remote-tracking branches.
+fn pushed_commits(repo: &Repository, remote: &str, update: &RefUpdate) -> Result<Vec<(Oid, String)>> {
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push(update.local_oid)?;
+
+    if !update.is_create() && repo.find_commit(update.remote_oid).is_ok() {
+        revwalk.hide(update.remote_oid)?;
+    }
+
+    // pushing to a URL rather than a named remote has no remote-tracking branches
+    if repo.find_remote(remote).is_ok() {
+        revwalk.hide_glob(&format!("refs/remotes/{}/*", remote))?;
+    }
+
+    let mut result = Vec::new();
+
+    for id in revwalk {
+        let commit = repo.find_commit(id?)?;
+        result.push((commit.id(), commit.summary().unwrap_or("").to_string()));
+    }
+
+    Ok(result)
+}
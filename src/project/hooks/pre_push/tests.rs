@@ -0,0 +1,169 @@
+use super::*;
+
+use crate::testing::{self, Scratch};
+
+use git2::Signature;
+
+static LOCAL: &str = "1d5f2f9a0a5bd7c5e1f3d9c7c3e4a6b8f0d2e4c6";
+static REMOTE: &str = "9b2e6c4a8d0f1e3b5a7c9d1f3e5a7b9c1d3f5e7a";
+static ZERO: &str = "0000000000000000000000000000000000000000";
+
+#[test]
+fn test_parse() {
+    let update = RefUpdate::parse(&format!("refs/heads/feature {} refs/heads/main {}", LOCAL, REMOTE)).unwrap();
+
+    assert_eq!("refs/heads/feature", update.local_ref);
+    assert_eq!(Oid::from_str(LOCAL).unwrap(), update.local_oid);
+    assert_eq!(Some("main"), update.branch());
+    assert!(!update.is_create());
+    assert!(!update.is_delete());
+
+    assert!(RefUpdate::parse("refs/heads/feature").is_err());
+    assert!(RefUpdate::parse(&format!("refs/heads/main nope refs/heads/main {}", REMOTE)).is_err());
+}
+
+#[test]
+fn test_parse_all() {
+    let input = format!("refs/heads/main {} refs/heads/main {}\n(delete) {} refs/tags/v1 {}\n\n",
+        LOCAL, ZERO, ZERO, REMOTE);
+    let updates = RefUpdate::parse_all(&input).unwrap();
+
+    assert_eq!(2, updates.len());
+    assert!(updates[0].is_create());
+    assert!(updates[1].is_delete());
+    assert_eq!(None, updates[1].branch());
+
+    assert!(RefUpdate::parse_all("").unwrap().is_empty());
+}
+
+#[test]
+fn test_is_wip() {
+    assert!(is_wip("WIP"));
+    assert!(is_wip("wip: half of the thing"));
+    assert!(is_wip("[WIP] half of the thing"));
+    assert!(is_wip("ABC-123 WIP: half of the thing"));
+    assert!(is_wip("fixup! feat: add a thing"));
+    assert!(is_wip("squash! feat: add a thing"));
+
+    assert!(!is_wip("feat: add a thing"));
+    assert!(!is_wip("Wipe the cache before building"));
+    assert!(!is_wip("fix the fixup! handling"));
+}
+
+/// A repository with `main` at `second`, which follows `first`, and `diverged`, which also follows
+/// `first` but isn't on `main`.
+struct History {
+    _dir: Scratch,
+    repo: Repository,
+    first: Oid,
+    second: Oid,
+    diverged: Oid,
+}
+
+impl History {
+    fn new(test: &str) -> Self {
+        let dir = Scratch::new(test);
+        let repo = testing::repository(&dir);
+
+        let first = testing::commit_file(&repo, "README.md", "# first\n", "Add a README");
+        let second = testing::commit_file(&repo, "README.md", "# second\n", "WIP: reword the README");
+
+        let diverged = {
+            let parent = repo.find_commit(first).unwrap();
+            let signature = Signature::now("nfty", "nfty@example.com").unwrap();
+            repo.commit(None, &signature, &signature, "Reword the README differently", &parent.tree().unwrap(),
+                &[&parent]).unwrap()
+        };
+
+        History { _dir: dir, repo, first, second, diverged }
+    }
+
+    /// Check a single update of a remote branch from one commit to another.
+    fn check(&self, config: &Config, branch: &str, from: Oid, to: Oid) -> PushReport {
+        let update = RefUpdate {
+            local_ref: format!("refs/heads/{}", branch),
+            local_oid: to,
+            remote_ref: format!("refs/heads/{}", branch),
+            remote_oid: from,
+        };
+
+        Guard::new(config).unwrap().check(&self.repo, "origin", &[update]).unwrap()
+    }
+}
+
+#[test]
+fn test_guard_defaults() {
+    let history = History::new("pre-push-defaults");
+    let config = Config::default();
+
+    assert!(config.pre_push.block_direct);
+
+    // creating a protected branch, as the first push of a new project does
+    assert!(history.check(&config, "main", Oid::zero(), history.second).errors.is_empty());
+
+    // updating one, even by fast-forwarding it, or deleting it, is rejected
+    assert_eq!(vec!["pushing directly to protected branch main is not allowed".to_string()],
+        history.check(&config, "main", history.first, history.second).errors);
+    assert_eq!(vec!["deleting protected branch main is not allowed".to_string()],
+        history.check(&config, "main", history.second, Oid::zero()).errors);
+
+    // branches which aren't protected can be rewritten
+    assert!(history.check(&config, "feature", history.second, history.diverged).errors.is_empty());
+
+    // unfinished commits are only warned about
+    let report = history.check(&config, "feature", history.first, history.second);
+    assert!(report.errors.is_empty());
+    assert_eq!(1, report.warnings.len());
+    assert!(report.warnings[0].contains("WIP: reword the README"));
+}
+
+#[test]
+fn test_guard_block_force() {
+    let history = History::new("pre-push-block-force");
+
+    let mut config = Config::default();
+    config.pre_push.block_direct = false;
+
+    // fast-forwarding a protected branch is allowed once direct pushes are
+    assert!(history.check(&config, "main", history.first, history.second).errors.is_empty());
+
+    // rewriting its history, or deleting it, still isn't
+    assert_eq!(vec!["pushing to protected branch main would rewrite its history".to_string()],
+        history.check(&config, "main", history.second, history.diverged).errors);
+    assert_eq!(vec!["deleting protected branch main is not allowed".to_string()],
+        history.check(&config, "main", history.second, Oid::zero()).errors);
+}
+
+#[test]
+fn test_guard_block_direct() {
+    let history = History::new("pre-push-block-direct");
+
+    let mut config = Config::default();
+    config.pre_push.protected = Some(vec!["release/*".to_string()]);
+
+    assert!(history.check(&config, "release/1.0", Oid::zero(), history.second).errors.is_empty());
+
+    assert_eq!(vec!["pushing directly to protected branch release/1.0 is not allowed".to_string()],
+        history.check(&config, "release/1.0", history.first, history.second).errors);
+    assert_eq!(vec!["deleting protected branch release/1.0 is not allowed".to_string()],
+        history.check(&config, "release/1.0", history.second, Oid::zero()).errors);
+
+    // the configured patterns replace the branches protected from pruning
+    assert!(history.check(&config, "main", history.first, history.second).errors.is_empty());
+}
+
+#[test]
+fn test_guard_allows_everything_when_disabled() {
+    let history = History::new("pre-push-disabled");
+
+    let mut config = Config::default();
+    config.pre_push.block_direct = false;
+    config.pre_push.block_force = false;
+    config.pre_push.warn_wip = false;
+
+    let report = history.check(&config, "main", history.second, history.diverged);
+    assert!(report.errors.is_empty());
+    assert!(report.warnings.is_empty());
+
+    assert!(history.check(&config, "main", history.second, Oid::zero()).errors.is_empty());
+}
@@ -0,0 +1,163 @@
+use super::*;
+
+use crate::testing::{self, Scratch};
+
+/// The state of a single path within `.git/hooks`, as reported by `status`.
+fn state_of(path: &Path, hook_path: &str) -> HookState {
+    status(path, None).unwrap().into_iter()
+        .find(|s| s.path == Path::new(hook_path))
+        .map(|s| s.state)
+        .unwrap()
+}
+
+/// Write an executable file into `.git/hooks`.
+fn write_executable(path: &Path, contents: &str) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, contents).unwrap();
+    fs::set_permissions(path, fs::Permissions::from_mode(0o755)).unwrap();
+}
+
+#[test]
+fn test_status() {
+    let dir = Scratch::new("hooks-status");
+    testing::repository(&dir);
+
+    assert_eq!(HookState::Missing, state_of(&dir, "pre-commit"));
+    assert_eq!(HookState::Missing, state_of(&dir, "pre-commit.d"));
+    assert_eq!(HookState::Missing, state_of(&dir, "pre-commit.d/20-pre-commit.sh"));
+
+    install(&dir, None).unwrap();
+
+    assert!(status(&dir, None).unwrap().iter().all(|s| s.is_current()));
+
+    // edited contents, and scripts which can't run, have drifted
+    let hooks = hooks_dir(&dir).unwrap();
+    fs::write(hooks.join("pre-commit"), format!("{}\necho edited\n", HOOK_STUB_PAYLOAD)).unwrap();
+    fs::set_permissions(hooks.join("pre-push.d/20-pre-push.sh"), fs::Permissions::from_mode(0o644)).unwrap();
+
+    assert_eq!(HookState::Drifted, state_of(&dir, "pre-commit"));
+    assert_eq!(HookState::Drifted, state_of(&dir, "pre-push.d/20-pre-push.sh"));
+
+    // a hook without the marker isn't one of ours
+    write_executable(&hooks.join("commit-msg"), "#!/bin/sh\nexit 0\n");
+    assert_eq!(HookState::Foreign, state_of(&dir, "commit-msg"));
+
+    // LFS scripts left behind in a repository which doesn't use LFS are stale
+    write_executable(&hooks.join("post-commit.d/10-git-lfs.sh"), git_lfs::POST_COMMIT);
+    assert_eq!(HookState::Stale, state_of(&dir, "post-commit.d/10-git-lfs.sh"));
+
+    // installing again repairs everything nfty owns
+    install(&dir, None).unwrap();
+
+    assert_eq!(HookState::Current, state_of(&dir, "pre-commit"));
+    assert_eq!(HookState::Current, state_of(&dir, "pre-push.d/20-pre-push.sh"));
+    assert!(!hooks.join("post-commit.d/10-git-lfs.sh").exists());
+}
+
+#[test]
+fn test_install_and_uninstall() {
+    let dir = Scratch::new("hooks-uninstall");
+    testing::repository(&dir);
+
+    let hooks = hooks_dir(&dir).unwrap();
+    let foreign = "#!/bin/sh\necho my own hook\n";
+
+    write_executable(&hooks.join("pre-commit"), foreign);
+    write_executable(&hooks.join("pre-commit.d/50-mine.sh"), "#!/bin/sh\nexit 0\n");
+
+    install(&dir, None).unwrap();
+
+    // the foreign hook is kept aside and replaced by the stub
+    assert_eq!(foreign, fs::read_to_string(hooks.join(BACKUP_DIR).join("pre-commit")).unwrap());
+    assert_eq!(HookState::Current, state_of(&dir, "pre-commit"));
+    assert!(hooks.join("pre-commit.d/50-mine.sh").is_file());
+
+    uninstall(&dir).unwrap();
+
+    // the foreign hook is back where it was, along with the scripts nfty didn't install
+    assert_eq!(foreign, fs::read_to_string(hooks.join("pre-commit")).unwrap());
+    assert!(hooks.join("pre-commit.d/50-mine.sh").is_file());
+    assert!(!hooks.join("pre-commit.d/20-pre-commit.sh").exists());
+    assert!(!hooks.join(BACKUP_DIR).exists());
+
+    // and nothing else nfty installed is left
+    assert!(!hooks.join("commit-msg").exists());
+    assert!(!hooks.join("commit-msg.d").exists());
+}
+
+#[test]
+fn test_install_keeps_first_backup() {
+    let dir = Scratch::new("hooks-backup");
+    testing::repository(&dir);
+
+    let hooks = hooks_dir(&dir).unwrap();
+
+    write_executable(&hooks.join("pre-push"), "#!/bin/sh\necho first\n");
+    install(&dir, None).unwrap();
+
+    // a second foreign hook isn't allowed to replace the one already kept aside
+    write_executable(&hooks.join("pre-push"), "#!/bin/sh\necho second\n");
+    install(&dir, None).unwrap();
+
+    assert_eq!("#!/bin/sh\necho first\n", fs::read_to_string(hooks.join(BACKUP_DIR).join("pre-push")).unwrap());
+    assert_eq!("#!/bin/sh\necho second\n", fs::read_to_string(hooks.join("pre-push")).unwrap());
+    assert_eq!(HookState::Foreign, state_of(&dir, "pre-push"));
+}
+
+/// Add a linked worktree of a repository on a new branch, laid out as `git worktree add` does.
+fn add_worktree(repo: &Repository, name: &str, path: &Path) -> Repository {
+    let head = repo.head().unwrap().peel_to_commit().unwrap();
+    repo.branch(name, &head, false).unwrap();
+
+    let admin = repo.path().join("worktrees").join(name);
+    fs::create_dir_all(&admin).unwrap();
+    fs::create_dir_all(path).unwrap();
+
+    fs::write(admin.join("HEAD"), format!("ref: refs/heads/{}\n", name)).unwrap();
+    fs::write(admin.join("commondir"), "../..\n").unwrap();
+    fs::write(admin.join("gitdir"), format!("{}\n", path.join(".git").display())).unwrap();
+    fs::write(path.join(".git"), format!("gitdir: {}\n", admin.display())).unwrap();
+
+    Repository::open(path).unwrap()
+}
+
+#[test]
+fn test_hooks_path_in_worktree() {
+    let dir = Scratch::new("hooks-worktree");
+    let repo = testing::repository(&dir.join("main"));
+    testing::commit_file(&repo, "README.md", "# README\n", "Add a README");
+
+    let worktree = add_worktree(&repo, "linked", &dir.join("linked"));
+    let hooks = fs::canonicalize(repo.path()).unwrap().join("hooks");
+
+    // every worktree runs the hooks of the repository it belongs to
+    assert_eq!(hooks, fs::canonicalize(hooks_path(&repo).unwrap()).unwrap());
+    assert_ne!(repo.path(), worktree.path());
+    assert_eq!(hooks, hooks_path(&worktree).unwrap());
+
+    // so installing from a worktree installs them there, for both to see
+    install(&dir.join("linked"), None).unwrap();
+
+    assert_eq!(HookState::Current, stub_state(&hooks.join("pre-commit")));
+    assert!(status(&dir.join("main"), None).unwrap().iter().all(|s| s.is_current()));
+    assert!(!dir.join("linked/hooks").exists());
+}
+
+#[test]
+fn test_hooks_path_configured() {
+    let dir = Scratch::new("hooks-configured");
+    let repo = testing::repository(&dir);
+
+    // a relative path is relative to the working tree, as the hooks are run from there
+    repo.config().unwrap().set_str("core.hooksPath", ".githooks").unwrap();
+    assert_eq!(dir.join(".githooks"), hooks_path(&repo).unwrap());
+
+    install(&dir, None).unwrap();
+
+    assert_eq!(HookState::Current, stub_state(&dir.join(".githooks/pre-commit")));
+    assert!(!repo.path().join("hooks/pre-commit").exists());
+
+    let shared = dir.join("shared/hooks");
+    repo.config().unwrap().set_str("core.hooksPath", shared.to_str().unwrap()).unwrap();
+    assert_eq!(shared, hooks_path(&repo).unwrap());
+}
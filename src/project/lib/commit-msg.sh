@@ -0,0 +1,3 @@
+#!/bin/sh
+# managed by nfty: check the commit message against the configured commit message policy.
+exec nfty hook commit-msg "$@"
@@ -0,0 +1,3 @@
+#!/bin/sh
+# managed by nfty: point out dependency manifests changed by the merge.
+exec nfty hook dependencies
@@ -0,0 +1,3 @@
+#!/bin/sh
+# managed by nfty: dispatches this hook to the scripts in the matching <hook>.d directory.
+exec nfty hook run "$(basename "$0")" -- "$@"
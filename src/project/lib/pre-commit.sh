@@ -0,0 +1,3 @@
+#!/bin/sh
+# managed by nfty: check staged files for whitespace errors, large files, conflict markers and secrets.
+exec nfty hook pre-commit
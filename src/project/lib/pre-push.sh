@@ -0,0 +1,3 @@
+#!/bin/sh
+# managed by nfty: guard protected branches and warn about unfinished commits before pushing.
+exec nfty hook pre-push "$@"
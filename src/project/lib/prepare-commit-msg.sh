@@ -0,0 +1,3 @@
+#!/bin/sh
+# managed by nfty: fill in the issue key from the branch name.
+exec nfty hook prepare-commit-msg "$@"
@@ -0,0 +1,430 @@
+#[cfg(test)]
+mod tests;
+
+use crate::project::state::State;
+use crate::project::templates::{self, Overwrite, Placement, WritableTemplate};
+use crate::project::templates::overrides::Overrides;
+use crate::project::templates::managed;
+use crate::project::templates::validate;
+
+use diffy::{ConflictStyle, DiffOptions, MergeOptions};
+
+use log::{debug, info, warn};
+
+use std::collections::BTreeSet;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+/// What applying a render plan does to a file.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Change {
+    Create,
+    Update,
+    Unchanged,
+    Remove,
+    /// The file was edited since it was last rendered, so it is left alone.
+    Skip,
+}
+
+impl Change {
+    /// Classify the change from a file's current contents to its planned contents, where `None`
+    /// means that the file doesn't or won't exist.
+    pub fn between(current: Option<&str>, planned: Option<&str>) -> Self {
+        match (current, planned) {
+            (None, Some(_)) => Change::Create,
+            (Some(_), None) => Change::Remove,
+            (Some(current), Some(planned)) if current != planned => Change::Update,
+            _ => Change::Unchanged,
+        }
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", match self {
+            Change::Create => "create",
+            Change::Update => "change",
+            Change::Unchanged => "unchanged",
+            Change::Remove => "remove",
+            Change::Skip => "skip",
+        })
+    }
+}
+
+/// A file which a render plan creates, changes, removes or leaves alone.
+pub struct PlannedFile {
+    pub path: PathBuf,
+    pub change: Change,
+    /// The name of the template the file is rendered from, if it is rendered from one.
+    pub template: Option<String>,
+    /// The comment syntax of the file's managed block, if it has one.
+    pub comment: Option<String>,
+    /// The contents of the file before the plan is applied.
+    pub current: Option<String>,
+    /// The contents of the file after the plan is applied.
+    pub planned: Option<String>,
+    /// What was rendered for the file, before any merge with its current contents.
+    pub rendered: Option<String>,
+    /// The permissions the file is written with, if its template declares them.
+    pub mode: Option<u32>,
+    /// When the file may be overwritten if it exists.
+    pub overwrite: Overwrite,
+}
+
+impl PlannedFile {
+    /// A unified diff from the current to the planned contents, labelled with the given path.
+    pub fn diff(&self, name: &Path) -> String {
+        if self.change == Change::Unchanged || self.change == Change::Skip {
+            return String::new();
+        }
+
+        let original = self.current.as_deref().unwrap_or("");
+        let modified = self.planned.as_deref().unwrap_or("");
+
+        DiffOptions::new()
+            .set_original_filename(match self.current {
+                Some(_) => format!("a/{}", name.display()),
+                None => "/dev/null".to_string(),
+            })
+            .set_modified_filename(match self.planned {
+                Some(_) => format!("b/{}", name.display()),
+                None => "/dev/null".to_string(),
+            })
+            .create_patch(original, modified)
+            .to_string()
+    }
+}
+
+/// The files rendering would write to a project, computed without touching anything so that the
+/// changes can be reviewed before they are applied.
+pub struct RenderPlan {
+    root: PathBuf,
+    files: Vec<PlannedFile>,
+    directories: Vec<PathBuf>,
+    overrides: Overrides,
+    /// The files conform rendered before, which are nfty's even if they have no managed block.
+    rendered: BTreeSet<PathBuf>,
+}
+
+impl RenderPlan {
+    pub fn new(root: &Path) -> Self {
+        RenderPlan {
+            root: root.to_path_buf(),
+            files: Vec::new(),
+            directories: Vec::new(),
+            overrides: Overrides::new(Some(root)),
+            rendered: BTreeSet::new(),
+        }
+    }
+
+    /// Take the files conform rendered before from its state. Those without a managed block were
+    /// rendered before nfty had them, so they are replaced rather than adopted as though they were
+    /// the project's own.
+    pub fn with_state(mut self, state: &State) -> Self {
+        self.rendered = state.files.keys().map(|relative| self.root.join(relative)).collect();
+        self
+    }
+
+    /// The root directory of the project being rendered.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// The files in the plan, in the order they were added.
+    pub fn files(&self) -> &[PlannedFile] {
+        &self.files
+    }
+
+    /// The directories the plan creates because they don't exist yet, in the order they were added.
+    pub fn directories(&self) -> &[PathBuf] {
+        &self.directories
+    }
+
+    /// The path of a file relative to the project root, for display.
+    pub fn relative<'a>(&self, path: &'a Path) -> &'a Path {
+        path.strip_prefix(&self.root).unwrap_or(path)
+    }
+
+    /// Plan to render a template to the given path, over the file's managed block if the template
+    /// has one. Templates overridden for the project or user are rendered from their override.
+    pub fn add<T>(&mut self, dest: &Path, template: &T) -> io::Result<()> where T: WritableTemplate {
+        let current = self.current(dest)?;
+
+        let rendered = match self.overrides.render(template.name(), template)? {
+            Some(rendered) => {
+                debug!("Rendering {} from its override", template.name());
+                rendered
+            }
+            None => template.contents()?,
+        };
+
+        let contents = template.place(rendered, self.placed_over(dest, current.as_deref(), template.comment()));
+
+        // what is checked is what is written, including anything around the managed block
+        validate::check(template, &contents)?;
+
+        self.plan(dest, Some(contents), Some(template.name().to_string()), template.comment().map(|c| c.to_string()))?;
+        self.declare(dest, template.mode(), template.overwrite());
+
+        Ok(())
+    }
+
+    /// Declare the permissions of the file just planned and when it may be overwritten.
+    fn declare(&mut self, dest: &Path, mode: Option<u32>, overwrite: Overwrite) {
+        let file = self.files.last_mut().unwrap();
+        file.mode = mode;
+        file.overwrite = overwrite;
+
+        // files which are never overwritten are left as they are once they exist
+        if file.overwrite == Overwrite::Never && file.current.is_some() {
+            file.planned = file.current.clone();
+            file.change = Change::Unchanged;
+        }
+
+        // files with the wrong permissions are changed even if their contents are the same
+        if let Some(mode) = file.mode.filter(|_| file.change == Change::Unchanged && file.current.is_some()) {
+            if fs::metadata(dest).map(|m| m.permissions().mode() & 0o7777 != mode).unwrap_or(false) {
+                file.change = Change::Update;
+            }
+        }
+    }
+
+    /// Plan to render a template to its default path within the project root.
+    pub fn add_default<T>(&mut self, template: &T) -> io::Result<()> where T: WritableTemplate {
+        let dest = self.default_dest(template)?;
+        self.add(&dest, template)
+    }
+
+    /// The default path of a template within the project root.
+    pub fn default_dest<T>(&self, template: &T) -> io::Result<PathBuf> where T: WritableTemplate {
+        let path = T::default_path().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, format!("{} has no default path", template.name()))
+        })?;
+
+        Ok(self.root.join(path))
+    }
+
+    /// Place rendered contents over the file at the given path as declared, just as a template
+    /// declaring the same with `#[writable(...)]` would be, giving what would be written.
+    pub fn placed(&self, dest: &Path, rendered: String, placement: &Placement) -> io::Result<String> {
+        let current = self.current(dest)?;
+        Ok(placement.place(rendered, self.placed_over(dest, current.as_deref(), placement.comment.as_deref())))
+    }
+
+    /// Plan to write contents rendered from the named template and placed with `placed` to the
+    /// given path.
+    pub fn add_placed(&mut self, dest: &Path, contents: String, template: String, placement: &Placement)
+            -> io::Result<()> {
+        self.plan(dest, Some(contents), Some(template), placement.comment.clone())?;
+        self.declare(dest, placement.mode, placement.overwrite);
+
+        Ok(())
+    }
+
+    /// The overrides of templates for the project being rendered.
+    pub fn overrides(&self) -> &Overrides {
+        &self.overrides
+    }
+
+    /// The contents a rendering is placed over: nothing for a file conform rendered before it had
+    /// managed blocks, which is replaced as a whole, otherwise the file's current contents.
+    fn placed_over<'a>(&self, dest: &Path, current: Option<&'a str>, comment: Option<&str>) -> Option<&'a str> {
+        let unmanaged = |current: &str| comment.is_some_and(|c| !current.lines().any(|l| l.trim_end() == managed::begin(c)));
+
+        match current {
+            Some(current) if self.rendered.contains(dest) && unmanaged(current) => None,
+            current => current,
+        }
+    }
+
+    /// Plan to create a directory, if it doesn't exist yet.
+    pub fn add_directory(&mut self, path: &Path) {
+        if !path.is_dir() && !self.directories.iter().any(|d| d == path) {
+            self.directories.push(path.to_path_buf());
+        }
+    }
+
+    /// Plan to write the given contents to the given path. Planning the same path twice replaces
+    /// what was planned for it before.
+    pub fn add_contents(&mut self, dest: &Path, contents: String) -> io::Result<()> {
+        self.plan(dest, Some(contents), None, None)
+    }
+
+    /// Plan to remove the file at the given path, if it exists.
+    pub fn remove(&mut self, path: &Path) -> io::Result<()> {
+        self.plan(path, None, None, None)
+    }
+
+    /// The contents of a file before the plan is applied.
+    fn current(&self, path: &Path) -> io::Result<Option<String>> {
+        match self.files.iter().find(|f| f.path == path) {
+            Some(file) => Ok(file.current.clone()),
+            None => read(path),
+        }
+    }
+
+    fn plan(&mut self, path: &Path, planned: Option<String>, template: Option<String>,
+            comment: Option<String>) -> io::Result<()> {
+        let current = self.current(path)?;
+        self.files.retain(|f| f.path != path);
+
+        self.files.push(PlannedFile {
+            path: path.to_path_buf(),
+            change: Change::between(current.as_deref(), planned.as_deref()),
+            template,
+            comment,
+            current,
+            rendered: planned.clone(),
+            planned,
+            mode: None,
+            overwrite: Overwrite::default(),
+        });
+
+        Ok(())
+    }
+
+    /// Skip changing or removing files which were edited since they were last rendered, unless
+    /// forced to overwrite them or their templates always overwrite them.
+    pub fn protect(&mut self, state: &State, force: bool) {
+        for index in 0..self.files.len() {
+            let file = &self.files[index];
+
+            let changing = (file.change == Change::Update || file.change == Change::Remove)
+                && file.overwrite != Overwrite::Always;
+
+            if changing && state.is_edited(self.relative(&file.path), file) {
+                if force {
+                    warn!("Overwriting {}, it was edited since it was last rendered.",
+                        self.relative(&file.path).display());
+                } else {
+                    self.files[index].change = Change::Skip;
+                }
+            }
+        }
+    }
+
+    /// Three-way merge what was rendered into files edited since they were last rendered, using
+    /// what was last rendered as their common ancestor. Conflicting changes are planned with
+    /// conflict markers, and the conflicted files returned. Edited files without a recorded
+    /// ancestor are skipped, and files whose templates always overwrite them aren't merged.
+    pub fn merge(&mut self, state: &State) -> io::Result<Vec<PathBuf>> {
+        let mut conflicts = Vec::new();
+
+        for index in 0..self.files.len() {
+            let relative = self.relative(&self.files[index].path).to_path_buf();
+            let base = state.base(&self.root, &relative)?;
+            let edited = state.is_edited(&relative, &self.files[index]);
+            let file = &mut self.files[index];
+
+            let (current, planned) = match (&file.current, &file.planned) {
+                (Some(current), Some(planned)) if file.change == Change::Update && file.overwrite != Overwrite::Always => {
+                    (current, planned)
+                }
+                _ => continue,
+            };
+
+            let base = match base {
+                Some(ref base) if base != current => base,
+                // the file is as it was last rendered, so it takes the new rendering as it is
+                Some(_) => continue,
+                None if edited => {
+                    file.change = Change::Skip;
+                    continue;
+                }
+                None => continue,
+            };
+
+            let merged = match MergeOptions::new().set_conflict_style(ConflictStyle::Merge).merge(base, current, planned) {
+                Ok(merged) => merged,
+                Err(conflicted) => {
+                    conflicts.push(file.path.clone());
+                    conflicted
+                }
+            };
+
+            file.change = Change::between(Some(current), Some(&merged));
+            file.planned = Some(merged);
+        }
+
+        Ok(conflicts)
+    }
+
+    /// Whether applying the plan would leave every file as it is.
+    pub fn is_unchanged(&self) -> bool {
+        self.directories.is_empty() && self.files.iter().all(|f| f.change == Change::Unchanged)
+    }
+
+    /// Log what the plan does to each file, followed by its diff if requested.
+    pub fn report(&self, diff: bool) {
+        for dir in &self.directories {
+            info!("Would create {}/", self.relative(dir).display());
+        }
+
+        for file in &self.files {
+            let name = self.relative(&file.path);
+
+            match file.change {
+                Change::Unchanged => debug!("Would leave {} unchanged", name.display()),
+                Change::Skip => warn!("Would skip {}, it was edited since it was last rendered.", name.display()),
+                change => info!("Would {} {}", change, name.display()),
+            }
+
+            if diff {
+                print!("{}", file.diff(name));
+            }
+        }
+    }
+
+    /// A unified diff of every file the plan creates, changes or removes.
+    pub fn diff(&self) -> String {
+        self.files.iter().map(|f| f.diff(self.relative(&f.path))).collect()
+    }
+
+    /// Write every created or changed file, and remove every removed file.
+    pub fn apply(&self) -> io::Result<()> {
+        for dir in &self.directories {
+            info!("Creating {}/", self.relative(dir).display());
+            fs::create_dir_all(dir)?;
+        }
+
+        for file in &self.files {
+            let name = self.relative(&file.path);
+
+            match (file.change, &file.planned) {
+                (Change::Create, Some(contents)) | (Change::Update, Some(contents)) => {
+                    info!("Rendering {}", name.display());
+
+                    if let Some(parent) = file.path.parent().filter(|p| !p.is_dir()) {
+                        debug!("Creating {}", parent.display());
+                        fs::create_dir_all(parent)?;
+                    }
+
+                    templates::write(&file.path, contents, file.mode)?;
+                }
+                (Change::Remove, _) => {
+                    info!("Removing {}", name.display());
+                    fs::remove_file(&file.path)?;
+                }
+                (Change::Skip, _) => {
+                    warn!("Skipping {}, it was edited since it was last rendered. Use --force to overwrite it.",
+                        name.display());
+                }
+                _ => debug!("{} is unchanged", name.display()),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Read a file's current contents, treating a missing file as one which doesn't exist yet.
+fn read(path: &Path) -> io::Result<Option<String>> {
+    match fs::read(path) {
+        Ok(bytes) => Ok(Some(String::from_utf8_lossy(&bytes).into_owned())),
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
@@ -0,0 +1,229 @@
+use super::*;
+
+use crate::testing::Scratch;
+
+#[test]
+fn test_change_between() {
+    assert_eq!(Change::Create, Change::between(None, Some("a")));
+    assert_eq!(Change::Update, Change::between(Some("a"), Some("b")));
+    assert_eq!(Change::Unchanged, Change::between(Some("a"), Some("a")));
+    assert_eq!(Change::Remove, Change::between(Some("a"), None));
+    assert_eq!(Change::Unchanged, Change::between(None, None));
+}
+
+#[test]
+fn test_diff() {
+    let file = PlannedFile {
+        path: PathBuf::from("/project/Makefile"),
+        change: Change::Update,
+        template: None,
+        comment: None,
+        current: Some("all:\n\techo hi\n".to_string()),
+        planned: Some("all:\n\techo hello\n".to_string()),
+        rendered: None,
+        mode: None,
+        overwrite: Overwrite::default(),
+    };
+
+    assert_eq!("--- a/Makefile\n+++ b/Makefile\n@@ -1,2 +1,2 @@\n all:\n-\techo hi\n+\techo hello\n",
+        file.diff(Path::new("Makefile")));
+
+    let file = PlannedFile {
+        path: PathBuf::from("/project/LICENSE"),
+        change: Change::Create,
+        template: None,
+        comment: None,
+        current: None,
+        planned: Some("MIT\n".to_string()),
+        rendered: None,
+        mode: None,
+        overwrite: Overwrite::default(),
+    };
+
+    assert!(file.diff(Path::new("LICENSE")).starts_with("--- /dev/null\n+++ b/LICENSE\n"));
+}
+
+#[test]
+fn test_plan_replaces_path() {
+    let mut plan = RenderPlan::new(Path::new("/nonexistent/project"));
+    let path = plan.root().join("LICENSE");
+
+    plan.remove(&path).unwrap();
+    plan.add_contents(&path, "MIT".to_string()).unwrap();
+
+    assert_eq!(1, plan.files().len());
+    assert_eq!(Change::Create, plan.files()[0].change);
+    assert_eq!(Path::new("LICENSE"), plan.relative(&plan.files()[0].path));
+    assert!(!plan.is_unchanged());
+}
+
+/// A plan updating a single file from its current contents to what was rendered for it.
+fn updating(current: &str, rendered: &str) -> RenderPlan {
+    let root = PathBuf::from("/nonexistent/project");
+
+    RenderPlan {
+        directories: Vec::new(),
+        files: vec![PlannedFile {
+            path: root.join("Makefile"),
+            change: Change::between(Some(current), Some(rendered)),
+            template: Some("ansible/Makefile.j2".to_string()),
+            comment: None,
+            current: Some(current.to_string()),
+            planned: Some(rendered.to_string()),
+            rendered: Some(rendered.to_string()),
+            mode: None,
+            overwrite: Overwrite::default(),
+        }],
+        overrides: Overrides::new(None),
+        rendered: BTreeSet::new(),
+        root,
+    }
+}
+
+/// The state after the given contents were rendered into the project's Makefile.
+fn rendered(base: &str) -> State {
+    let mut state = State::default();
+    state.record(&updating("", base));
+    state
+}
+
+#[test]
+fn test_merge() {
+    let base = "all: test\n\ntest:\n\tmolecule test\n\nclean:\n\trm -fr .ansible\n";
+    let current = "all: test\n\ntest:\n\tmolecule test\n\nclean:\n\trm -fr .ansible build\n";
+    let upgraded = "all: test\n\ntest:\n\tmolecule test --all\n\nclean:\n\trm -fr .ansible\n";
+
+    let mut plan = updating(current, upgraded);
+    assert!(plan.merge(&rendered(base)).unwrap().is_empty());
+
+    let file = &plan.files()[0];
+    assert_eq!(Change::Update, file.change);
+    assert_eq!(Some("all: test\n\ntest:\n\tmolecule test --all\n\nclean:\n\trm -fr .ansible build\n"),
+        file.planned.as_deref());
+    assert_eq!(Some(upgraded), file.rendered.as_deref());
+}
+
+#[test]
+fn test_merge_unedited() {
+    let base = "all:\n\techo hi\n";
+    let upgraded = "all:\n\techo hello\n";
+
+    let mut plan = updating(base, upgraded);
+    assert!(plan.merge(&rendered(base)).unwrap().is_empty());
+    assert_eq!(Some(upgraded), plan.files()[0].planned.as_deref());
+}
+
+#[test]
+fn test_merge_conflict() {
+    let base = "all:\n\techo hi\n";
+    let current = "all:\n\techo hey\n";
+    let upgraded = "all:\n\techo hello\n";
+
+    let mut plan = updating(current, upgraded);
+    let conflicts = plan.merge(&rendered(base)).unwrap();
+
+    assert_eq!(vec![plan.root().join("Makefile")], conflicts);
+
+    let planned = plan.files()[0].planned.as_deref().unwrap();
+    assert!(planned.contains("<<<<<<< ours\n\techo hey\n=======\n\techo hello\n>>>>>>> theirs\n"));
+}
+
+#[test]
+fn test_merge_without_base() {
+    let mut state = State::default();
+
+    state.files.insert("Makefile".to_string(), crate::project::state::FileState {
+        template: Some("ansible/Makefile.j2".to_string()),
+        version: "0.4.0".to_string(),
+        hash: crate::project::state::hash("all:\n\techo hi\n", None),
+        written: None,
+    });
+
+    // edited files without anything recorded to merge against are left alone
+    let mut plan = updating("all:\n\techo hey\n", "all:\n\techo hello\n");
+    assert!(plan.merge(&state).unwrap().is_empty());
+    assert_eq!(Change::Skip, plan.files()[0].change);
+}
+
+#[test]
+fn test_protect_overwrite_always() {
+    let state = rendered("all:\n\techo hi\n");
+
+    let mut plan = updating("all:\n\techo hey\n", "all:\n\techo hello\n");
+    plan.protect(&state, false);
+    assert_eq!(Change::Skip, plan.files()[0].change);
+
+    // files whose templates always overwrite them are neither skipped nor merged
+    let mut plan = updating("all:\n\techo hey\n", "all:\n\techo hello\n");
+    plan.files[0].overwrite = Overwrite::Always;
+
+    plan.protect(&state, false);
+    assert!(plan.merge(&state).unwrap().is_empty());
+    assert_eq!(Change::Update, plan.files()[0].change);
+    assert_eq!(Some("all:\n\techo hello\n"), plan.files()[0].planned.as_deref());
+}
+
+#[test]
+fn test_add_default() {
+    let root = Scratch::new("render-default");
+
+    let mut plan = RenderPlan::new(&root);
+    plan.add_default(&templates::ansible::Makefile::new()).unwrap();
+
+    let file = &plan.files()[0];
+    assert_eq!(root.join("Makefile"), file.path);
+    assert_eq!(Some(0o755), file.mode);
+    assert!(file.planned.as_deref().unwrap().ends_with('\n'));
+
+    plan.apply().unwrap();
+    assert_eq!(0o755, fs::metadata(root.join("Makefile")).unwrap().permissions().mode() & 0o777);
+
+    // the permissions are restored if they change, even though the contents didn't
+    fs::set_permissions(root.join("Makefile"), fs::Permissions::from_mode(0o644)).unwrap();
+
+    let mut plan = RenderPlan::new(&root);
+    plan.add_default(&templates::ansible::Makefile::new()).unwrap();
+    assert_eq!(Change::Update, plan.files()[0].change);
+
+}
+
+#[test]
+fn test_add_replaces_files_rendered_before_managed_blocks() {
+    let root = Scratch::new("render-unmanaged");
+    let config = || templates::ansible::AnsibleConfig::new().role_path(".ansible/galaxy-roles".to_string());
+
+    // what an older nfty rendered, without markers and since changed by the template
+    fs::write(root.join("ansible.cfg"), "[defaults]\nretry_files_enabled = false\nroles_path = .ansible/roles").unwrap();
+
+    let mut state = State::default();
+    state.files.insert("ansible.cfg".to_string(), crate::project::state::FileState {
+        template: Some("ansible/ansible.cfg.j2".to_string()),
+        version: "0.4.0".to_string(),
+        hash: crate::project::state::hash("[defaults]\nretry_files_enabled = false\nroles_path = .ansible/roles", None),
+        written: None,
+    });
+
+    let mut plan = RenderPlan::new(&root).with_state(&state);
+    plan.add_default(&config()).unwrap();
+
+    assert_eq!("# BEGIN nfty managed\n[defaults]\nretry_files_enabled = false\nroles_path = .ansible/galaxy-roles\n\
+        # END nfty managed\n", plan.files()[0].planned.as_deref().unwrap());
+
+    // a file conform never rendered is the project's own, so it is kept
+    let mut plan = RenderPlan::new(&root);
+    plan.add_default(&config()).unwrap();
+    assert_eq!(2, plan.files()[0].planned.as_deref().unwrap().matches("[defaults]").count());
+}
+
+#[test]
+fn test_add_validates_what_is_written() {
+    let root = Scratch::new("render-validate-placed");
+
+    // the rendering is valid, but not once it is placed after what the file already has
+    fs::write(root.join(".travis.yml"), "env: [unclosed\n").unwrap();
+
+    let mut plan = RenderPlan::new(&root);
+    let error = plan.add_default(&templates::ansible::TravisConfig::new()).unwrap_err();
+
+    assert!(error.to_string().starts_with("invalid YAML"), "{}", error);
+}
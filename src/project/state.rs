@@ -0,0 +1,228 @@
+#[cfg(test)]
+mod tests;
+
+use crate::config::PROJECT_CONFIG_DIR;
+use crate::project::render::{Change, PlannedFile, RenderPlan};
+use crate::project::templates::managed;
+use crate::util::Checksum;
+
+use serde_derive::{Deserialize, Serialize};
+
+use serde_yaml::Value;
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The file within a project's `.nfty` recording what conform last rendered.
+static STATE_FILE: &str = "state.yml";
+
+/// The directory within a project's `.nfty` holding what conform last rendered for each file, the
+/// common ancestor when merging an upgrade.
+static RENDERED_DIR: &str = "rendered";
+
+/// What conform last rendered into a project, used to detect files edited since.
+///
+/// ```yaml
+/// template: ansible
+/// version: 0.4.0
+/// options:
+///   private: false
+///   python_version: 3.6.8
+/// source:
+///   repository: git@github.com:naftulikay/nfty-templates@v3
+///   commit: 0f3c8b6d1e0a4b4e8a9f2b7c5d6e7f8091a2b3c4
+/// files:
+///   Makefile:
+///     template: ansible/Makefile.j2
+///     version: 0.4.0
+///     hash: 2c26b46b68ffc68ff99b453c1d30413413422d706483bfa0f98a5e886266e7ae
+/// ```
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct State {
+    /// The project template last conformed to.
+    pub template: Option<String>,
+    /// The version of nfty which last conformed the project.
+    pub version: Option<String>,
+    /// The options the project was last conformed with.
+    pub options: Value,
+    /// The repository the templates were fetched from, if they were fetched.
+    pub source: Option<SourceState>,
+    /// Each rendered file, keyed by its path relative to the project root.
+    pub files: BTreeMap<String, FileState>,
+    /// What was rendered for each file since the state was loaded, written when it is saved. Files
+    /// which were removed have nothing rendered.
+    #[serde(skip)]
+    rendered: BTreeMap<String, Option<String>>,
+}
+
+/// The revision of a template repository which was rendered.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SourceState {
+    /// The repository, including the branch, tag or commit it was fetched at.
+    pub repository: String,
+    /// The commit the repository resolved to.
+    pub commit: String,
+}
+
+/// A file rendered by conform.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct FileState {
+    /// The template the file was rendered from, if it came from one.
+    pub template: Option<String>,
+    /// The version of nfty which rendered the file.
+    pub version: String,
+    /// The SHA-256 digest of the part of the file nfty owns: its managed block if it has one,
+    /// otherwise all of it.
+    pub hash: String,
+    /// The SHA-256 digest of the whole file as conform wrote it, so that undo can tell whether
+    /// anything in it was edited since. Projects conformed before it was recorded have none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub written: Option<String>,
+}
+
+impl State {
+    /// The path of the state file in the project at the given root.
+    pub fn path(root: &Path) -> PathBuf {
+        root.join(PROJECT_CONFIG_DIR).join(STATE_FILE)
+    }
+
+    /// Load the state of the project at the given root, which is empty if it was never conformed.
+    pub fn load(root: &Path) -> io::Result<Self> {
+        let path = State::path(root);
+
+        if !path.is_file() {
+            return Ok(State::default());
+        }
+
+        serde_yaml::from_str(&fs::read_to_string(&path)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path.display(), e)))
+    }
+
+    /// Save the state into the project at the given root.
+    pub fn save(&self, root: &Path) -> io::Result<()> {
+        let path = State::path(root);
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let contents = serde_yaml::to_string(self).map_err(io::Error::other)?;
+
+        fs::write(&path, contents)?;
+
+        for (relative, rendered) in &self.rendered {
+            let base = State::base_path(root, Path::new(relative));
+
+            match rendered {
+                Some(rendered) => {
+                    if let Some(parent) = base.parent() {
+                        fs::create_dir_all(parent)?;
+                    }
+
+                    fs::write(&base, rendered)?;
+                }
+                None if base.is_file() => fs::remove_file(&base)?,
+                None => (),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The path holding what was last rendered for a file, given its path relative to the root.
+    pub fn base_path(root: &Path, relative: &Path) -> PathBuf {
+        root.join(PROJECT_CONFIG_DIR).join(RENDERED_DIR).join(relative)
+    }
+
+    /// What was last rendered for a file, given its path relative to the project root. Projects
+    /// conformed before rendered files were kept have nothing recorded for them.
+    pub fn base(&self, root: &Path, relative: &Path) -> io::Result<Option<String>> {
+        if let Some(rendered) = self.rendered.get(&key(relative)) {
+            return Ok(rendered.clone());
+        }
+
+        match fs::read_to_string(State::base_path(root, relative)) {
+            Ok(contents) => Ok(Some(contents)),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Determine whether a planned file was edited since it was last rendered. Files which were
+    /// never rendered, or no longer exist, haven't been edited.
+    pub fn is_edited(&self, relative: &Path, file: &PlannedFile) -> bool {
+        let recorded = match self.files.get(&key(relative)) {
+            Some(recorded) => recorded,
+            None => return false,
+        };
+
+        file.current.as_deref().is_some_and(|current| hash(current, file.comment.as_deref()) != recorded.hash)
+    }
+
+    /// Determine whether any of a file was edited since conform last wrote it, given its path
+    /// relative to the project root, by reading it from the project. Files which were never
+    /// rendered, or no longer exist, haven't been edited.
+    pub fn is_edited_on_disk(&self, root: &Path, relative: &Path) -> io::Result<bool> {
+        let recorded = match self.files.get(&key(relative)) {
+            Some(recorded) => recorded,
+            None => return Ok(false),
+        };
+
+        match fs::read_to_string(root.join(relative)) {
+            Ok(current) => Ok(match &recorded.written {
+                Some(written) => Checksum::sha256(current.as_bytes()) != *written,
+                None => hash(&current, managed::comment(&current)) != recorded.hash,
+            }),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Record what an applied plan rendered. Skipped files keep what was recorded for them before,
+    /// and merged files are recorded as rendered, so that they count as edited since.
+    pub fn record(&mut self, plan: &RenderPlan) {
+        let version = env!("CARGO_PKG_VERSION").to_string();
+
+        for file in plan.files() {
+            let relative = key(plan.relative(&file.path));
+
+            match (file.change, &file.rendered) {
+                (Change::Skip, _) => (),
+                (_, Some(rendered)) => {
+                    self.files.insert(relative.clone(), FileState {
+                        template: file.template.clone(),
+                        version: version.clone(),
+                        hash: hash(rendered, file.comment.as_deref()),
+                        written: file.planned.as_ref().map(|p| Checksum::sha256(p.as_bytes())),
+                    });
+
+                    self.rendered.insert(relative, Some(rendered.clone()));
+                }
+                (_, None) => {
+                    self.files.remove(&relative);
+                    self.rendered.insert(relative, None);
+                }
+            }
+        }
+
+        self.version = Some(version);
+    }
+}
+
+/// The key of a file in the state, its path relative to the project root.
+fn key(relative: &Path) -> String {
+    relative.to_string_lossy().to_string()
+}
+
+/// Hash the part of a file's contents which nfty owns.
+pub fn hash(contents: &str, comment: Option<&str>) -> String {
+    let owned = match comment {
+        Some(comment) => managed::owned(contents, comment),
+        None => contents,
+    };
+
+    Checksum::sha256(owned.as_bytes())
+}
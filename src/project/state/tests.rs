@@ -0,0 +1,90 @@
+use super::*;
+
+use crate::project::templates::Overwrite;
+use crate::testing::Scratch;
+
+fn planned(current: &str, comment: Option<&'static str>) -> PlannedFile {
+    PlannedFile {
+        path: PathBuf::from("/project/.gitignore"),
+        change: Change::Update,
+        template: Some("git/gitignore.j2".to_string()),
+        comment: comment.map(|c| c.to_string()),
+        current: Some(current.to_string()),
+        planned: Some("# BEGIN nfty managed\n*.pyc\n# END nfty managed\n".to_string()),
+        rendered: None,
+        mode: None,
+        overwrite: Overwrite::default(),
+    }
+}
+
+fn state(rendered: &str, comment: Option<&str>) -> State {
+    let mut state = State::default();
+
+    state.files.insert(".gitignore".to_string(), FileState {
+        template: Some("git/gitignore.j2".to_string()),
+        version: "0.4.0".to_string(),
+        hash: hash(rendered, comment),
+        written: None,
+    });
+
+    state
+}
+
+#[test]
+fn test_is_edited() {
+    let rendered = "*.pyc\n";
+    let state = state(rendered, None);
+
+    assert!(!state.is_edited(Path::new(".gitignore"), &planned(rendered, None)));
+    assert!(state.is_edited(Path::new(".gitignore"), &planned("*.pyc\n/build\n", None)));
+
+    // files which were never rendered can't have been edited
+    assert!(!State::default().is_edited(Path::new(".gitignore"), &planned("/build\n", None)));
+}
+
+#[test]
+fn test_is_edited_managed_block() {
+    let rendered = "# BEGIN nfty managed\n*.pyo\n# END nfty managed\n";
+    let state = state(rendered, Some("#"));
+
+    // edits around the managed block don't count
+    let around = format!("/build\n{}/dist\n", rendered);
+    assert!(!state.is_edited(Path::new(".gitignore"), &planned(&around, Some("#"))));
+
+    let within = "# BEGIN nfty managed\n*.pyo\n/build\n# END nfty managed\n";
+    assert!(state.is_edited(Path::new(".gitignore"), &planned(within, Some("#"))));
+}
+
+#[test]
+fn test_record() {
+    let mut plan = RenderPlan::new(Path::new("/nonexistent/project"));
+    plan.add_contents(&plan.root().join("Makefile"), "all:".to_string()).unwrap();
+
+    let mut state = State::default();
+    state.record(&plan);
+
+    assert_eq!(Some(env!("CARGO_PKG_VERSION")), state.version.as_deref());
+    assert_eq!(hash("all:", None), state.files["Makefile"].hash);
+
+    let written = plan.files()[0].planned.as_ref().unwrap();
+    assert_eq!(Some(Checksum::sha256(written.as_bytes())), state.files["Makefile"].written);
+}
+
+#[test]
+fn test_is_edited_on_disk() {
+    let root = Scratch::new("state-edited-on-disk");
+    let rendered = "# BEGIN nfty managed\n*.pyo\n# END nfty managed\n";
+    let state = state(rendered, Some("#"));
+
+    // without what was written, as conformed by older versions, only the managed block counts
+    fs::write(root.join(".gitignore"), format!("/build\n{}", rendered)).unwrap();
+    assert!(!state.is_edited_on_disk(&root, Path::new(".gitignore")).unwrap());
+
+    fs::write(root.join(".gitignore"), "# BEGIN nfty managed\n*.pyo\n/build\n# END nfty managed\n").unwrap();
+    assert!(state.is_edited_on_disk(&root, Path::new(".gitignore")).unwrap());
+
+    // files which were never rendered, or were removed since, have no edits to lose
+    assert!(!State::default().is_edited_on_disk(&root, Path::new(".gitignore")).unwrap());
+    fs::remove_file(root.join(".gitignore")).unwrap();
+    assert!(!state.is_edited_on_disk(&root, Path::new(".gitignore")).unwrap());
+}
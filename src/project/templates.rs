@@ -1,14 +1,247 @@
+#[cfg(test)]
+mod tests;
+
 pub mod ansible;
+pub mod answers;
 pub mod git;
 pub mod license;
+pub mod managed;
+pub mod overrides;
+pub mod pack;
 pub mod python;
+pub mod registry;
 pub mod vagrant;
+pub mod validate;
+
+use crate::project::templates::pack::VariableType;
+
+use minijinja::{Environment, UndefinedBehavior};
+
+use serde::Serialize;
 
-use askama::Template;
+use serde_derive::Deserialize;
 
+use std::collections::BTreeSet;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
 use std::path::Path;
-use std::io::Result;
+use std::io::{ErrorKind, Result};
+use std::process;
+
+/// A field of a template, as declared on its type.
+#[derive(Debug)]
+pub struct Field {
+    pub name: &'static str,
+    /// The type of the field as written, e.g. `Option<String>`.
+    pub ty: &'static str,
+    /// The kind of variable the field is, or none if its value is given as YAML.
+    pub kind: Option<VariableType>,
+    /// The doc comment of the field.
+    pub description: &'static str,
+}
+
+/// The kind of variable a field of this type is, which decides how its value is given on the
+/// command line. Every type a template field has must implement it, and those without a kind
+/// are given as YAML.
+pub trait FieldKind {
+    const KIND: Option<VariableType> = None;
+}
+
+impl FieldKind for String {
+    const KIND: Option<VariableType> = Some(VariableType::String);
+}
+
+impl FieldKind for bool {
+    const KIND: Option<VariableType> = Some(VariableType::Bool);
+}
+
+impl FieldKind for i32 {
+    const KIND: Option<VariableType> = Some(VariableType::Integer);
+}
+
+impl FieldKind for Vec<String> {
+    const KIND: Option<VariableType> = Some(VariableType::List);
+}
+
+impl FieldKind for BTreeSet<String> {
+    const KIND: Option<VariableType> = Some(VariableType::List);
+}
+
+impl<T> FieldKind for Option<T> where T: FieldKind {
+    const KIND: Option<VariableType> = T::KIND;
+}
+
+/// How a rendered file ends, declared with `#[writable(newline = "...")]`.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Newline {
+    /// The file ends where the trimmed rendering does, which is the default.
+    #[default]
+    Trim,
+    /// The file always ends with a newline.
+    Ensure,
+}
+
+/// When an existing file may be overwritten, declared with `#[writable(overwrite = "...")]`.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Overwrite {
+    /// The file is always overwritten, e.g. when its rendering already accounts for its contents.
+    Always,
+    /// The file is only overwritten if it wasn't edited since it was last rendered, unless forced,
+    /// which is the default.
+    #[default]
+    IfUnmodified,
+    /// The file is only ever created, and is left alone once it exists.
+    Never,
+}
+
+/// A template which renders a file. Its fields are serialized as the context it is rendered with,
+/// whether from its built-in source or an override of it, so that both see the same values.
+pub trait WritableTemplate: Serialize {
+    /// The name of the template, i.e. its path within `templates/`.
+    fn name(&self) -> &'static str;
+
+    /// What the template renders, taken from the doc comment of its type.
+    fn description() -> &'static str where Self: Sized;
+
+    /// The fields of the template, which are the variables it is rendered with.
+    fn fields() -> &'static [Field] where Self: Sized;
+
+    /// The path the template is rendered to relative to the project root, declared with
+    /// `#[writable(default_path = "Makefile")]`.
+    fn default_path() -> Option<&'static str> where Self: Sized {
+        None
+    }
+
+    /// Render the template to the contents of the file it is written to.
+    fn contents(&self) -> Result<String>;
+
+    /// The line comment syntax delimiting the managed block in the rendered file, declared with
+    /// `#[writable(comment = "#")]`. Templates without one own the whole file.
+    fn comment(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// Render the template over the existing contents of a file. Only the managed block is
+    /// replaced in templates which have one, keeping anything added around it.
+    fn contents_over(&self, existing: Option<&str>) -> Result<String> {
+        Ok(self.place(self.contents()?, existing))
+    }
+
+    /// The permissions of the rendered file, declared with `#[writable(mode = 0o755)]`. Files
+    /// without one are created with the default permissions, and keep theirs when overwritten.
+    fn mode(&self) -> Option<u32> {
+        None
+    }
+
+    /// How the rendered file ends, declared with `#[writable(newline = "ensure")]`.
+    fn newline(&self) -> Newline {
+        Newline::default()
+    }
+
+    /// When an existing file may be overwritten, declared with
+    /// `#[writable(overwrite = "if-unmodified")]`.
+    fn overwrite(&self) -> Overwrite {
+        Overwrite::default()
+    }
+
+    /// Check that rendered contents are what the template declares they are with
+    /// `#[writable(format = "yaml", schema = "Inventory")]`, i.e. YAML or INI, and
+    /// that YAML deserializes into its schema, a type in `validate`, if it has one.
+    fn validate(&self, _contents: &str) -> std::result::Result<(), validate::Invalid> {
+        Ok(())
+    }
+
+    /// Place rendered contents over the existing contents of a file, as `contents_over` does.
+    fn place(&self, contents: String, existing: Option<&str>) -> String {
+        place(contents, existing, self.comment(), self.newline())
+    }
+
+    /// Render the template over the file at the given path, unless the file exists and the
+    /// template is never to overwrite it.
+    fn write(&self, path: &Path) -> Result<()> {
+        let existing = match fs::read_to_string(path) {
+            Ok(existing) => Some(existing),
+            Err(ref e) if e.kind() == ErrorKind::NotFound => None,
+            Err(e) => return Err(e),
+        };
+
+        if existing.is_some() && self.overwrite() == Overwrite::Never {
+            return Ok(());
+        }
+
+        write(path, &self.contents_over(existing.as_deref())?, self.mode())
+    }
+}
+
+/// How the files a template pack renders are written, which the pack declares for each file just as
+/// a template declares it with `#[writable(...)]`.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Placement {
+    /// The line comment syntax of the file's managed block. Files without one are owned entirely.
+    pub comment: Option<String>,
+    /// The permissions of the file, e.g. `0o755`. Files without one keep theirs.
+    pub mode: Option<u32>,
+    pub newline: Newline,
+    pub overwrite: Overwrite,
+}
+
+impl Placement {
+    /// Place rendered contents over the existing contents of a file.
+    pub fn place(&self, contents: String, existing: Option<&str>) -> String {
+        place(contents, existing, self.comment.as_deref(), self.newline)
+    }
+}
+
+/// Place rendered contents over the existing contents of a file, replacing only its managed block
+/// if it has a comment syntax, and ending it as declared.
+fn place(contents: String, existing: Option<&str>, comment: Option<&str>, newline: Newline) -> String {
+    let mut placed = match comment {
+        Some(comment) => managed::replace(existing, &contents, comment),
+        None => contents,
+    };
+
+    if newline == Newline::Ensure && !placed.ends_with('\n') {
+        placed.push('\n');
+    }
+
+    placed
+}
+
+/// Render a template's source with the given context, trimmed. Built-in templates and their
+/// overrides are both rendered this way, so that anything one accepts the other does too. Using a
+/// variable which isn't defined is an error, rather than rendering nothing in its place.
+pub fn render_str<T>(source: &str, context: &T) -> std::result::Result<String, minijinja::Error> where T: Serialize {
+    let mut env = Environment::new();
+    env.set_undefined_behavior(UndefinedBehavior::Strict);
+
+    env.render_str(source, context).map(|s| s.trim().to_string())
+}
+
+/// Write a file atomically, by writing a temporary file alongside it and renaming it over the
+/// file, so that the file is never left half written. The file is given the mode if there is one,
+/// otherwise it keeps the permissions it had.
+pub fn write(path: &Path, contents: &str, mode: Option<u32>) -> Result<()> {
+    let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    let temp = path.with_file_name(format!(".{}.{}.tmp", name, process::id()));
+
+    let permissions = match mode {
+        Some(mode) => Some(fs::Permissions::from_mode(mode)),
+        None => fs::metadata(path).ok().map(|m| m.permissions()),
+    };
+
+    let result = fs::write(&temp, contents)
+        .and_then(|_| match permissions {
+            Some(permissions) => fs::set_permissions(&temp, permissions),
+            None => Ok(()),
+        })
+        .and_then(|_| fs::rename(&temp, path));
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
 
-pub trait WritableTemplate: Template {
-    fn write(&self, path: &Path) -> Result<()>;
+    result
 }
@@ -1,11 +1,16 @@
-use askama::Template;
+use crate::project::templates::FieldKind;
+
+use serde_derive::{Deserialize, Serialize};
 
 use std::collections::BTreeSet;
-use std::io::prelude::*;
 
-#[derive(Default, Template, WritableTemplate)]
+/// The Ansible configuration, pointing Ansible at the roles the role being tested depends on.
+#[derive(Default, Deserialize, Serialize, WritableTemplate)]
+#[serde(default)]
 #[template(path = "ansible/ansible.cfg.j2")]
+#[writable(comment = "#", default_path = "ansible.cfg", newline = "ensure", format = "ini")]
 pub struct AnsibleConfig {
+    /// Directories Ansible looks for roles in.
     pub role_paths: Vec<String>,
 }
 
@@ -21,10 +26,15 @@ impl AnsibleConfig {
     }
 }
 
-#[derive(Default, Template, WritableTemplate)]
+/// The Docker Compose file running the machines a role is tested against.
+#[derive(Default, Deserialize, Serialize, WritableTemplate)]
+#[serde(default)]
 #[template(path = "ansible/docker-compose.yml.j2")]
+#[writable(default_path = "docker-compose.yml", newline = "ensure", format = "yaml", schema = "ComposeFile")]
 pub struct DockerComposeConfig {
+    /// Run a Docker in Docker service for machines to use.
     pub dind: bool,
+    /// The machines to test against, as a list of name, image and is_privileged.
     pub machines: Vec<DockerComposeMachine>,
 }
 
@@ -47,9 +57,12 @@ impl DockerComposeConfig {
         self
     }
 }
+
+#[derive(Deserialize, Serialize)]
 pub struct DockerComposeMachine {
     pub name: String,
     pub image: String,
+    #[serde(default)]
     pub is_privileged: bool,
 }
 
@@ -68,9 +81,16 @@ impl DockerComposeMachine {
     }
 }
 
-#[derive(Default, Template, WritableTemplate)]
+// machines are given as YAML
+impl FieldKind for Vec<DockerComposeMachine> {}
+
+/// The Ansible Galaxy roles a role is tested with.
+#[derive(Default, Deserialize, Serialize, WritableTemplate)]
+#[serde(default)]
 #[template(path = "ansible/galaxy-requirements.yml.j2")]
+#[writable(default_path = "requirements.yml", newline = "ensure", format = "yaml", schema = "GalaxyRequirements")]
 pub struct AnsibleRequirements {
+    /// The roles to install, as a list of src and optionally name and version.
     pub roles: Vec<GalaxyRole>,
 }
 
@@ -92,9 +112,12 @@ impl AnsibleRequirements {
     }
 }
 
+#[derive(Deserialize, Serialize)]
 pub struct GalaxyRole {
     pub src: String,
+    #[serde(default)]
     pub name: Option<String>,
+    #[serde(default)]
     pub version: Option<String>,
 }
 
@@ -116,8 +139,14 @@ impl GalaxyRole {
     }
 }
 
-#[derive(Default, Template, WritableTemplate)]
+// roles are given as YAML
+impl FieldKind for Vec<GalaxyRole> {}
+
+/// The Travis CI configuration running the tests of a role.
+#[derive(Default, Deserialize, Serialize, WritableTemplate)]
+#[serde(default)]
 #[template(path = "ansible/travis.yml.j2")]
+#[writable(comment = "#", default_path = ".travis.yml", newline = "ensure", format = "yaml")]
 pub struct TravisConfig {}
 
 impl TravisConfig {
@@ -126,8 +155,11 @@ impl TravisConfig {
     }
 }
 
-#[derive(Default, Template, WritableTemplate)]
+/// The Makefile of a role, which starts its machines and runs its tests.
+#[derive(Default, Deserialize, Serialize, WritableTemplate)]
+#[serde(default)]
 #[template(path = "ansible/Makefile.j2")]
+#[writable(comment = "#", mode = 0o755, default_path = "Makefile", newline = "ensure")]
 pub struct Makefile {}
 
 impl Makefile {
@@ -136,8 +168,11 @@ impl Makefile {
     }
 }
 
-#[derive(Default, Template, WritableTemplate)]
+/// The files ignored within the tests of a role.
+#[derive(Default, Deserialize, Serialize, WritableTemplate)]
+#[serde(default)]
 #[template(path = "ansible/tests/gitignore.j2")]
+#[writable(comment = "#", default_path = "tests/.gitignore", newline = "ensure")]
 pub struct TestsGitIgnore {}
 
 impl TestsGitIgnore {
@@ -146,8 +181,11 @@ impl TestsGitIgnore {
     }
 }
 
-#[derive(Default, Template, WritableTemplate)]
+/// The Makefile which runs the tests of a role against its machines.
+#[derive(Default, Deserialize, Serialize, WritableTemplate)]
+#[serde(default)]
 #[template(path = "ansible/tests/Makefile.j2")]
+#[writable(comment = "#", mode = 0o755, default_path = "tests/Makefile", newline = "ensure")]
 pub struct TestsMakefile {}
 
 impl TestsMakefile {
@@ -156,9 +194,57 @@ impl TestsMakefile {
     }
 }
 
-#[derive(Default, Template, WritableTemplate)]
+/// The Ansible configuration of the tests of a role, pointing Ansible at the role and the roles
+/// its tests depend on, rendered from ansible/ansible.cfg.j2.
+#[derive(Default, Deserialize, Serialize, WritableTemplate)]
+#[serde(default)]
+#[template(path = "ansible/ansible.cfg.j2")]
+#[writable(comment = "#", default_path = "tests/ansible.cfg", newline = "ensure", format = "ini")]
+pub struct TestsAnsibleConfig {
+    /// Directories Ansible looks for roles in.
+    pub role_paths: Vec<String>,
+}
+
+impl TestsAnsibleConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn role_path(mut self, path: String) -> Self {
+        self.role_paths.push(path);
+        self
+    }
+}
+
+/// The Ansible Galaxy roles the tests of a role depend on, rendered from
+/// ansible/galaxy-requirements.yml.j2.
+#[derive(Default, Deserialize, Serialize, WritableTemplate)]
+#[serde(default)]
+#[template(path = "ansible/galaxy-requirements.yml.j2")]
+#[writable(default_path = "tests/requirements.yml", newline = "ensure", format = "yaml", schema = "GalaxyRequirements")]
+pub struct TestsAnsibleRequirements {
+    /// The roles to install, as a list of src and optionally name and version.
+    pub roles: Vec<GalaxyRole>,
+}
+
+impl TestsAnsibleRequirements {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_roles(mut self, roles: Vec<GalaxyRole>) -> Self {
+        self.roles = roles;
+        self
+    }
+}
+
+/// The inventory of the machines a role is tested against.
+#[derive(Default, Deserialize, Serialize, WritableTemplate)]
+#[serde(default)]
 #[template(path = "ansible/tests/inventory/hosts.yml.j2")]
+#[writable(default_path = "tests/inventory/hosts.yml", newline = "ensure", format = "yaml", schema = "Inventory")]
 pub struct InventoryYaml {
+    /// The names of the machines.
     pub hosts: BTreeSet<String>,
 }
 
@@ -0,0 +1,175 @@
+#[cfg(test)]
+mod tests;
+
+use crate::config::PROJECT_CONFIG_DIR;
+use crate::project::templates::pack::{Variable, Variables};
+
+use log::warn;
+
+use serde_derive::{Deserialize, Serialize};
+
+use serde_yaml::Value;
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+
+/// The file within a project's `.nfty` holding the answers given to conform.
+static ANSWERS_FILE: &str = "answers.yml";
+
+/// The values given to the variables of project templates and packs, committed alongside the
+/// project so that later runs replay them rather than asking again.
+///
+/// ```yaml
+/// templates:
+///   ansible:
+///     enable_dind: true
+///     python_version: 3.7.2
+/// packs:
+///   rust:
+///     edition: "2018"
+/// ```
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Answers {
+    /// Answers for the project templates built into nfty, by template name.
+    pub templates: BTreeMap<String, BTreeMap<String, Value>>,
+    /// Answers for template packs, by pack name.
+    pub packs: BTreeMap<String, BTreeMap<String, Value>>,
+}
+
+impl Answers {
+    /// The path of the answers file in the project at the given root.
+    pub fn path(root: &Path) -> PathBuf {
+        root.join(PROJECT_CONFIG_DIR).join(ANSWERS_FILE)
+    }
+
+    /// Load the answers given in the project at the given root, which are empty if there are none.
+    pub fn load(root: &Path) -> io::Result<Self> {
+        let path = Answers::path(root);
+
+        if !path.is_file() {
+            return Ok(Answers::default());
+        }
+
+        serde_yaml::from_str(&fs::read_to_string(&path)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path.display(), e)))
+    }
+
+    /// Save the answers into the project at the given root.
+    pub fn save(&self, root: &Path) -> io::Result<()> {
+        let path = Answers::path(root);
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        fs::write(&path, serde_yaml::to_string(self).map_err(io::Error::other)?)
+    }
+}
+
+/// Asks for the values of variables, one line at a time.
+pub struct Prompt<R, W> {
+    input: R,
+    output: W,
+}
+
+/// A prompt on the terminal.
+pub type Terminal = Prompt<io::StdinLock<'static>, io::Stderr>;
+
+impl Terminal {
+    /// A prompt on the terminal, if stdin is one.
+    pub fn terminal() -> Option<Self> {
+        let stdin = io::stdin();
+
+        if !stdin.is_terminal() {
+            return None;
+        }
+
+        Some(Prompt::new(stdin.lock(), io::stderr()))
+    }
+}
+
+impl<R, W> Prompt<R, W> where R: BufRead, W: Write {
+    pub fn new(input: R, output: W) -> Self {
+        Prompt { input, output }
+    }
+
+    /// Ask for the value of a variable until a valid one is given. An empty answer takes the
+    /// variable's default, if it has one.
+    pub fn ask(&mut self, name: &str, variable: &Variable) -> io::Result<Value> {
+        loop {
+            let question = variable.description.as_deref().unwrap_or(name);
+
+            match variable.default {
+                Some(ref default) => write!(self.output, "{} [{}]: ", question, display(default))?,
+                None => write!(self.output, "{}: ", question)?,
+            }
+
+            self.output.flush()?;
+
+            let mut line = String::new();
+
+            if self.input.read_line(&mut line)? == 0 {
+                return Err(io::Error::new(io::ErrorKind::UnexpectedEof, format!("no answer given for {}", name)));
+            }
+
+            let line = line.trim();
+
+            if line.is_empty() {
+                match variable.default {
+                    Some(ref default) => return Ok(default.clone()),
+                    None => {
+                        writeln!(self.output, "{} has no default, please give a value.", name)?;
+                        continue;
+                    }
+                }
+            }
+
+            match variable.kind.parse(line) {
+                Ok(value) => return Ok(value),
+                Err(e) => writeln!(self.output, "{}, please try again.", e)?,
+            }
+        }
+    }
+}
+
+/// Fill in the variables which weren't given, first from the answers given before, then by asking
+/// if there is a prompt. Variables which are still missing fall back to their defaults.
+pub fn answer<R, W>(variables: &Variables, given: &mut BTreeMap<String, Value>,
+        previous: Option<&BTreeMap<String, Value>>, mut prompt: Option<&mut Prompt<R, W>>) -> io::Result<()>
+        where R: BufRead, W: Write {
+    for (name, variable) in variables.iter() {
+        if given.contains_key(name) {
+            continue;
+        }
+
+        match previous.and_then(|p| p.get(name)) {
+            Some(value) if variable.kind.matches(value) => {
+                given.insert(name.clone(), value.clone());
+                continue;
+            }
+            Some(value) => warn!("Ignoring the answer {} given to {}, it is not a {:?}.", display(value), name, variable.kind),
+            None => (),
+        }
+
+        if let Some(ref mut prompt) = prompt {
+            given.insert(name.clone(), prompt.ask(name, variable)?);
+        }
+    }
+
+    Ok(())
+}
+
+/// Display a value as it would be typed in answer to a prompt.
+pub fn display(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Bool(true) => "yes".to_string(),
+        Value::Bool(false) => "no".to_string(),
+        Value::Number(n) => n.to_string(),
+        Value::Sequence(items) => items.iter().map(display).collect::<Vec<_>>().join(", "),
+        other => serde_yaml::to_string(other).unwrap_or_default().trim().to_string(),
+    }
+}
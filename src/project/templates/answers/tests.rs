@@ -0,0 +1,55 @@
+use super::*;
+
+use crate::project::templates::pack::VariableType;
+
+use std::io::Cursor;
+
+fn variables() -> Variables {
+    vec![
+        ("jobs", Variable::new(VariableType::Integer, Some(Value::from(2)), "How many jobs?")),
+        ("name", Variable::new(VariableType::String, None, "What is it called?")),
+    ].into_iter().map(|(name, variable)| (name.to_string(), variable)).collect()
+}
+
+#[test]
+fn test_ask() {
+    let variables = variables();
+
+    let mut output = Vec::new();
+    let mut prompt = Prompt::new(Cursor::new("many\n8\n"), &mut output);
+    assert_eq!(Value::from(8), prompt.ask("jobs", variables.get("jobs").unwrap()).unwrap());
+
+    // invalid answers are asked again
+    assert_eq!("How many jobs? [2]: \"many\" is not an integer, please try again.\nHow many jobs? [2]: ",
+        String::from_utf8(output).unwrap());
+
+    // empty answers take the default, if there is one
+    let mut prompt = Prompt::new(Cursor::new("\n\nwidget\n"), Vec::new());
+    assert_eq!(Value::from(2), prompt.ask("jobs", variables.get("jobs").unwrap()).unwrap());
+    assert_eq!(Value::from("widget"), prompt.ask("name", variables.get("name").unwrap()).unwrap());
+
+    let mut prompt = Prompt::new(Cursor::new(""), Vec::new());
+    assert_eq!(io::ErrorKind::UnexpectedEof, prompt.ask("name", variables.get("name").unwrap()).unwrap_err().kind());
+}
+
+#[test]
+fn test_answer() {
+    let variables = variables();
+    let previous: BTreeMap<String, Value> = vec![("jobs".to_string(), Value::from(4)), ("name".to_string(), Value::from(5))]
+        .into_iter().collect();
+
+    // given values win over previous answers, which are only replayed when they're still valid
+    let mut given: BTreeMap<String, Value> = vec![("jobs".to_string(), Value::from(8))].into_iter().collect();
+    let mut prompt = Prompt::new(Cursor::new("widget\n"), Vec::new());
+    answer(&variables, &mut given, Some(&previous), Some(&mut prompt)).unwrap();
+
+    assert_eq!(Some(&Value::from(8)), given.get("jobs"));
+    assert_eq!(Some(&Value::from("widget")), given.get("name"));
+
+    // previous answers are replayed without asking
+    let mut given = BTreeMap::new();
+    answer(&variables, &mut given, Some(&previous), None::<&mut Prompt<Cursor<&str>, Vec<u8>>>).unwrap();
+
+    assert_eq!(Some(&Value::from(4)), given.get("jobs"));
+    assert_eq!(None, given.get("name"));
+}
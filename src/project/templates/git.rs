@@ -1,9 +1,10 @@
-use askama::Template;
+use serde_derive::{Deserialize, Serialize};
 
-use std::io::Write;
-
-#[derive(Default, Template, WritableTemplate)]
+/// The files git ignores, for each of the languages the project uses.
+#[derive(Default, Deserialize, Serialize, WritableTemplate)]
+#[serde(default)]
 #[template(path = "git/gitignore.j2")]
+#[writable(comment = "#", default_path = ".gitignore", newline = "ensure")]
 pub struct GitIgnore {
     pub golang: bool,
     pub java: bool,
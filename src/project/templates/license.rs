@@ -1,100 +1,113 @@
-use askama::Template;
+use serde_derive::{Deserialize, Serialize};
 
 use chrono::Datelike;
-use chrono::NaiveDate;
 use chrono::Utc;
 
-use crate::project::templates::WritableTemplate;
+use crate::project::render::RenderPlan;
 
-use log::debug;
 use log::info;
 
 use std::default::Default;
-use std::fs;
-use std::io::prelude::*;
-use std::io::BufWriter;
 use std::io::Result;
-use std::path::Path;
 
-#[derive(Template,WritableTemplate)]
+/// The Apache License, Version 2.0.
+#[derive(Deserialize, Serialize, WritableTemplate)]
 #[template(path = "licenses/APACHE.j2")]
-pub struct ApacheLicense<'a> {
-    pub author: &'a str,
-    pub date: NaiveDate,
+#[writable(default_path = "LICENSE-APACHE", newline = "ensure")]
+#[serde(default)]
+pub struct ApacheLicense {
+    /// The copyright holder.
+    pub author: String,
+    /// The year of the copyright.
+    pub year: i32,
 }
 
-impl ApacheLicense<'_> {
-    pub fn write(&self, dest: &Path) -> Result<()> {
-        let mut file = BufWriter::new(fs::File::create(dest)?);
-        file.write(self.render().unwrap().as_bytes())?;
-
-        Ok(())
-    }
-}
-
-impl Default for ApacheLicense<'_> {
+impl Default for ApacheLicense {
     fn default() -> Self {
         ApacheLicense {
             author: "Naftuli Kay".into(),
-            date: Utc::now().date().naive_utc(),
+            year: Utc::now().year(),
         }
     }
 }
 
-#[derive(Template,WritableTemplate)]
+/// The MIT License.
+#[derive(Deserialize, Serialize, WritableTemplate)]
 #[template(path = "licenses/MIT.j2")]
-pub struct MitLicense<'a> {
-    pub author: &'a str,
-    pub date: NaiveDate,
+#[writable(default_path = "LICENSE-MIT", newline = "ensure")]
+#[serde(default)]
+pub struct MitLicense {
+    /// The copyright holder.
+    pub author: String,
+    /// The year of the copyright.
+    pub year: i32,
 }
 
-impl Default for MitLicense<'_> {
+impl Default for MitLicense {
     fn default() -> Self {
         MitLicense {
             author: "Naftuli Kay".into(),
-            date: Utc::now().date().naive_utc(),
+            year: Utc::now().year(),
+        }
+    }
+}
+
+/// A license reserving all rights to the copyright holder.
+#[derive(Deserialize, Serialize, WritableTemplate)]
+#[template(path = "licenses/PRIVATE.j2")]
+#[writable(default_path = "LICENSE", newline = "ensure")]
+#[serde(default)]
+pub struct PrivateLicense {
+    /// The copyright holder.
+    pub author: String,
+    /// The year of the copyright.
+    pub year: i32,
+}
+
+impl Default for PrivateLicense {
+    fn default() -> Self {
+        PrivateLicense {
+            author: "Naftuli Kay".into(),
+            year: Utc::now().year(),
         }
     }
 }
 
-/// Apply licensing in the given project directory.
-pub fn apply(project_dir: &Path, private: bool) -> Result<()> {
+/// Plan licensing for the project being rendered.
+pub fn apply(plan: &mut RenderPlan, private: bool) -> Result<()> {
     if private {
-        apply_private(project_dir)
+        apply_private(plan)
     } else {
-        apply_public(project_dir)
+        apply_public(plan)
     }
 }
 
-/// Apply public licensing in the given project directory.
-pub fn apply_public(project_dir: &Path) -> Result<()> {
+/// Plan public licensing for the project being rendered.
+pub fn apply_public(plan: &mut RenderPlan) -> Result<()> {
+    let project_dir = plan.root().to_path_buf();
+
     info!(
         "Applying public Apache and MIT licensing to the project at {}",
         project_dir.display()
     );
 
-    // if LICENSE exists, attempt to remove it in favor of dual licensing
-    let old_license = &project_dir.join("LICENSE");
-
-    if old_license.exists() {
-        debug!("Removing old LICENSE");
-        fs::remove_file(&old_license)?;
-    }
+    // if LICENSE exists, remove it in favor of dual licensing
+    plan.remove(&project_dir.join("LICENSE"))?;
 
     // render apache
-    ApacheLicense::default().write(&project_dir.join("LICENSE-APACHE"))?;
+    plan.add_default(&ApacheLicense::default())?;
 
     // render mit
-    MitLicense::default().write(&project_dir.join("LICENSE-MIT"))?;
+    plan.add_default(&MitLicense::default())?;
 
     Ok(())
 }
 
-/// Apply private licensing in the given projec directory.
-pub fn apply_private(project_dir: &Path) -> Result<()> {
+/// Plan private licensing for the project being rendered.
+pub fn apply_private(plan: &mut RenderPlan) -> Result<()> {
     info!(
         "Applying private licensing to the project at {}",
-        project_dir.display()
+        plan.root().display()
     );
 
     Ok(())
@@ -0,0 +1,111 @@
+#[cfg(test)]
+mod tests;
+
+/// Start a managed block, e.g. `# BEGIN nfty managed`.
+pub fn begin(comment: &str) -> String {
+    format!("{} BEGIN nfty managed", comment)
+}
+
+/// End a managed block, e.g. `# END nfty managed`.
+pub fn end(comment: &str) -> String {
+    format!("{} END nfty managed", comment)
+}
+
+/// Wrap rendered contents in managed block markers.
+pub fn block(contents: &str, comment: &str) -> String {
+    format!("{}\n{}\n{}", begin(comment), contents, end(comment))
+}
+
+/// Replace the managed block in a file's existing contents, keeping everything before and after
+/// it. A file without a managed block is adopted by adding the block after its contents, or right
+/// after its shebang if it has one, unless it is exactly what was rendered, i.e. nfty wrote it
+/// before it had managed blocks, in which case it is replaced. A block missing its end marker
+/// extends to the end of the file.
+///
+/// A shebang has to be the first line for the file to run, so one starting the rendered contents
+/// is kept out of the block and on the first line, replacing any shebang the file had.
+pub fn replace(existing: Option<&str>, contents: &str, comment: &str) -> String {
+    let (shebang, contents) = split_shebang(contents);
+    let (existing_shebang, existing) = split_shebang(existing.unwrap_or(""));
+
+    let block = block(contents, comment);
+    let begin = begin(comment);
+    let end = end(comment);
+
+    let body = match find_line(existing, &begin, 0) {
+        Some(start) => {
+            let after = match find_line(existing, &end, start) {
+                Some(index) => {
+                    let rest = &existing[index..];
+                    &rest[rest.find('\n').unwrap_or(rest.len())..]
+                }
+                None => "\n",
+            };
+
+            format!("{}{}{}", &existing[..start], block, after)
+        }
+        None if existing.is_empty() => format!("{}\n", block),
+        // nfty wrote the file before it had managed blocks, so all of it is nfty's to replace
+        None if existing.trim_end() == contents.trim_end() => format!("{}\n", block),
+        // right after the shebang, the block comes before what the file had
+        None if existing_shebang.is_some() => format!("{}\n{}", block, existing),
+        None if existing.ends_with('\n') => format!("{}{}\n", existing, block),
+        None => format!("{}\n{}\n", existing, block),
+    };
+
+    match shebang.or(existing_shebang) {
+        Some(shebang) => format!("{}\n{}", shebang, body),
+        None => body,
+    }
+}
+
+/// Split a `#!` line from the start of a file's contents, returning it and the rest.
+fn split_shebang(contents: &str) -> (Option<&str>, &str) {
+    if !contents.starts_with("#!") {
+        return (None, contents);
+    }
+
+    match contents.find('\n') {
+        Some(index) => (Some(&contents[..index]), &contents[index + 1..]),
+        None => (Some(contents), ""),
+    }
+}
+
+/// The managed block in a file's contents, including its markers, or the whole of the contents if
+/// it has no managed block.
+pub fn owned<'a>(contents: &'a str, comment: &str) -> &'a str {
+    let start = match find_line(contents, &begin(comment), 0) {
+        Some(start) => start,
+        None => return contents,
+    };
+
+    let end = end(comment);
+
+    match find_line(contents, &end, start) {
+        Some(index) => &contents[start..index + end.len()],
+        None => &contents[start..],
+    }
+}
+
+/// The comment marking the managed block in a file's contents, if it has one.
+pub fn comment(contents: &str) -> Option<&str> {
+    contents.lines()
+        .filter_map(|line| line.trim_end().strip_suffix(" BEGIN nfty managed"))
+        .find(|comment| !comment.is_empty())
+}
+
+/// Find the byte offset of a line consisting of the given marker, ignoring trailing whitespace,
+/// starting the search at the given offset.
+fn find_line(text: &str, marker: &str, from: usize) -> Option<usize> {
+    let mut offset = from;
+
+    for line in text[from..].split_inclusive('\n') {
+        if line.trim_end() == marker {
+            return Some(offset);
+        }
+
+        offset += line.len();
+    }
+
+    None
+}
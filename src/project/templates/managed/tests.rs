@@ -0,0 +1,90 @@
+use super::*;
+
+static MANAGED: &str = "# BEGIN nfty managed\n*.pyc\n# END nfty managed";
+
+#[test]
+fn test_block() {
+    assert_eq!(MANAGED, block("*.pyc", "#"));
+    assert_eq!("; BEGIN nfty managed\nx\n; END nfty managed", block("x", ";"));
+}
+
+#[test]
+fn test_replace_new_file() {
+    assert_eq!(format!("{}\n", MANAGED), replace(None, "*.pyc", "#"));
+}
+
+#[test]
+fn test_replace_adopts_unmanaged_file() {
+    // nothing the project had is thrown away, the block is added after it
+    assert_eq!(format!("/build\n*.pyo\n{}\n", MANAGED), replace(Some("/build\n*.pyo\n"), "*.pyc", "#"));
+    assert_eq!(format!("/build\n{}\n", MANAGED), replace(Some("/build"), "*.pyc", "#"));
+    assert_eq!(format!("{}\n", MANAGED), replace(Some(""), "*.pyc", "#"));
+}
+
+#[test]
+fn test_replace_upgrades_rendering_without_markers() {
+    // a file nfty rendered before it had managed blocks is replaced rather than adopted
+    assert_eq!(format!("{}\n", MANAGED), replace(Some("*.pyc"), "*.pyc", "#"));
+    assert_eq!(format!("{}\n", MANAGED), replace(Some("*.pyc\n"), "*.pyc", "#"));
+
+    let rendered = "#!/usr/bin/make -f\n\nall:\n\ttrue";
+    assert_eq!("#!/usr/bin/make -f\n# BEGIN nfty managed\n\nall:\n\ttrue\n# END nfty managed\n",
+        replace(Some(rendered), rendered, "#"));
+}
+
+#[test]
+fn test_replace_keeps_shebang_first() {
+    let rendered = "#!/usr/bin/make -f\n\nall:\n\ttrue";
+    let managed = "#!/usr/bin/make -f\n# BEGIN nfty managed\n\nall:\n\ttrue\n# END nfty managed\n";
+
+    // a new file starts with the shebang rather than the marker
+    assert_eq!(managed, replace(None, rendered, "#"));
+
+    // re-rendering keeps it there
+    assert_eq!(managed, replace(Some(managed), rendered, "#"));
+
+    // a file with its own shebang gets the block straight after it, ahead of what it had
+    assert_eq!("#!/usr/bin/make -f\n# BEGIN nfty managed\n\nall:\n\ttrue\n# END nfty managed\nlocal:\n\ttrue\n",
+        replace(Some("#!/bin/make -f\nlocal:\n\ttrue\n"), rendered, "#"));
+    assert_eq!("#!/bin/sh\n# BEGIN nfty managed\necho hi\n# END nfty managed\necho local\n",
+        replace(Some("#!/bin/sh\necho local\n"), "echo hi", "#"));
+
+    // files rendered with the shebang inside the block are fixed
+    let inside = "# BEGIN nfty managed\n#!/usr/bin/make -f\n\nall:\n\ttrue\n# END nfty managed\n";
+    assert_eq!(managed, replace(Some(inside), rendered, "#"));
+}
+
+#[test]
+fn test_replace_keeps_surrounding_content() {
+    let existing = "# project files\n/build\n# BEGIN nfty managed\n*.pyo\n# END nfty managed  \n.env\n";
+
+    assert_eq!(format!("# project files\n/build\n{}\n.env\n", MANAGED), replace(Some(existing), "*.pyc", "#"));
+}
+
+#[test]
+fn test_replace_unterminated_block() {
+    let existing = "/build\n# BEGIN nfty managed\n*.pyo\n";
+
+    assert_eq!(format!("/build\n{}\n", MANAGED), replace(Some(existing), "*.pyc", "#"));
+}
+
+#[test]
+fn test_replace_is_stable() {
+    let once = replace(Some("/build\n"), "*.pyc", "#");
+    let with_extra = format!("{}/dist\n", once);
+
+    assert_eq!(with_extra, replace(Some(&with_extra), "*.pyc", "#"));
+}
+
+#[test]
+fn test_owned() {
+    assert_eq!(MANAGED, owned(&format!("/build\n{}\n.env\n", MANAGED), "#"));
+    assert_eq!("/build\n", owned("/build\n", "#"));
+}
+
+#[test]
+fn test_comment() {
+    assert_eq!(Some("#"), comment(&format!("/build\n{}\n", MANAGED)));
+    assert_eq!(Some("//"), comment(&block("{}", "//")));
+    assert_eq!(None, comment("/build\n"));
+}
@@ -0,0 +1,93 @@
+#[cfg(test)]
+mod tests;
+
+use crate::config::{CONFIG_ROOT, PROJECT_CONFIG_DIR};
+use crate::project::templates::render_str;
+
+use serde::Serialize;
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+include!(concat!(env!("OUT_DIR"), "/templates.rs"));
+
+/// The directory within both `~/.config/nfty` and a project's `.nfty` holding template overrides.
+static TEMPLATES_DIR: &str = "templates";
+
+/// The source of the named template compiled into nfty, if there is one. Templates are otherwise
+/// rendered by their registered type, but template packs render them by name.
+pub fn builtin(name: &str) -> Option<&'static str> {
+    SOURCES.iter().find(|(n, _)| *n == name).map(|(_, source)| *source)
+}
+
+/// Where a template resolves to.
+#[derive(Debug, Eq, PartialEq)]
+pub enum Source {
+    /// An override in the project's `.nfty/templates`.
+    Project(PathBuf),
+    /// An override in `~/.config/nfty/templates`.
+    User(PathBuf),
+    /// The template compiled into nfty.
+    Builtin,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Source::Project(path) => write!(f, "project ({})", path.display()),
+            Source::User(path) => write!(f, "user ({})", path.display()),
+            Source::Builtin => write!(f, "built-in"),
+        }
+    }
+}
+
+/// Templates overriding those compiled into nfty, found at the same path within `templates/` in
+/// a project's `.nfty` or in `~/.config/nfty`, in that order. Overrides are Jinja templates
+/// rendered with the fields of the template they override, just as the built-in template is.
+///
+/// ```text
+/// .nfty/templates/ansible/Makefile.j2
+/// ~/.config/nfty/templates/git/gitignore.j2
+/// ```
+pub struct Overrides {
+    project: Option<PathBuf>,
+    user: Option<PathBuf>,
+}
+
+impl Overrides {
+    /// The overrides for the project at the given root and the current user.
+    pub fn new(root: Option<&Path>) -> Self {
+        Overrides {
+            project: root.map(|r| r.join(PROJECT_CONFIG_DIR).join(TEMPLATES_DIR)),
+            user: Some(CONFIG_ROOT.join(TEMPLATES_DIR)),
+        }
+    }
+
+    /// Resolve the named template to its override, if it has one.
+    pub fn resolve(&self, name: &str) -> Source {
+        if let Some(path) = self.project.as_ref().map(|d| d.join(name)).filter(|p| p.is_file()) {
+            return Source::Project(path);
+        }
+
+        if let Some(path) = self.user.as_ref().map(|d| d.join(name)).filter(|p| p.is_file()) {
+            return Source::User(path);
+        }
+
+        Source::Builtin
+    }
+
+    /// Render the override of the named template with the given context, or nothing if the
+    /// template isn't overridden.
+    pub fn render<T>(&self, name: &str, context: &T) -> io::Result<Option<String>> where T: Serialize {
+        let path = match self.resolve(name) {
+            Source::Project(path) | Source::User(path) => path,
+            Source::Builtin => return Ok(None),
+        };
+
+        render_str(&fs::read_to_string(&path)?, context)
+            .map(Some)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path.display(), e)))
+    }
+}
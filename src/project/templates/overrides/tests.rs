@@ -0,0 +1,92 @@
+use super::*;
+
+use crate::project::templates::WritableTemplate;
+use crate::project::templates::ansible::{AnsibleConfig, AnsibleRequirements, DockerComposeConfig, DockerComposeMachine,
+    GalaxyRole};
+use crate::project::templates::git::GitIgnore;
+use crate::project::templates::license::ApacheLicense;
+use crate::project::templates::registry;
+use crate::project::templates::vagrant::VagrantPlaybook;
+use crate::testing::Scratch;
+
+#[test]
+fn test_builtin() {
+    assert!(BUILTIN.contains(&"git/gitignore.j2"));
+    assert!(BUILTIN.contains(&"ansible/tests/inventory/hosts.yml.j2"));
+}
+
+#[test]
+fn test_resolve_without_overrides() {
+    let overrides = Overrides { project: Some(PathBuf::from("/nonexistent/project/.nfty/templates")), user: None };
+
+    assert_eq!(Source::Builtin, overrides.resolve("git/gitignore.j2"));
+    assert!(overrides.render("git/gitignore.j2", &GitIgnore::new()).unwrap().is_none());
+}
+
+#[test]
+fn test_render_str() {
+    let source = "{% if python %}*.pyc{% endif %}\n{% if rust %}/target{% endif %}\n";
+
+    // overrides see the same fields as the template they override
+    assert_eq!("*.pyc", render_str(source, &GitIgnore::new().python()).unwrap());
+    assert!(render_str("{% if python %}", &GitIgnore::new()).is_err());
+}
+
+/// Overrides within a scratch project holding a copy of every built-in template, as someone
+/// starting an override would make one.
+fn copied_builtins(dir: &Path) -> Overrides {
+    let templates = dir.join(PROJECT_CONFIG_DIR).join(TEMPLATES_DIR);
+
+    for name in BUILTIN {
+        let path = templates.join(name);
+
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::copy(Path::new(env!("CARGO_MANIFEST_DIR")).join("templates").join(name), path).unwrap();
+    }
+
+    Overrides { project: Some(templates), user: None }
+}
+
+#[test]
+fn test_render_copied_builtins() {
+    let dir = Scratch::new("overrides-builtins");
+    let overrides = copied_builtins(&dir);
+    let builtin = Overrides { project: None, user: None };
+
+    // a copy of a built-in renders with its default context exactly as the built-in does
+    for template in registry::all() {
+        assert!(matches!(overrides.resolve(template.template), Source::Project(_)), "{} isn't overridden", template.name);
+
+        let defaults = template.defaults().unwrap();
+
+        assert_eq!(template.render(defaults.clone(), &builtin).unwrap(), template.render(defaults, &overrides).unwrap(),
+            "{} renders differently as an override", template.name);
+    }
+}
+
+#[test]
+fn test_render_copied_builtins_with_values() {
+    let dir = Scratch::new("overrides-values");
+    let overrides = copied_builtins(&dir);
+
+    // the branches defaults don't reach render the same too
+    let playbook = VagrantPlaybook::new().docker().go("github.com/naftulikay/nfty", "1.10").java().node("8")
+        .python("3.6").ruby("2.5").rust();
+
+    let requirements = AnsibleRequirements::new()
+        .role(GalaxyRole::from_src("naftulikay.python-dev"))
+        .role(GalaxyRole::new("https://github.com/naftulikay/ansible-role-java", Some("java"), Some("v1.0.0")));
+
+    let mut compose = DockerComposeConfig::new();
+    compose.with_dind().machine(DockerComposeMachine::new("centos".into(), "centos:7".into()).privileged());
+
+    let config = AnsibleConfig::new().role_path(".ansible/galaxy-roles".into()).role_path(".ansible/roles".into());
+
+    assert_eq!(Some(playbook.contents().unwrap()), overrides.render(playbook.name(), &playbook).unwrap());
+    assert_eq!(Some(requirements.contents().unwrap()), overrides.render(requirements.name(), &requirements).unwrap());
+    assert_eq!(Some(compose.contents().unwrap()), overrides.render(compose.name(), &compose).unwrap());
+    assert_eq!(Some(config.contents().unwrap()), overrides.render(config.name(), &config).unwrap());
+
+    let license = ApacheLicense { author: "Naftuli Kay".into(), year: 2018 };
+    assert!(overrides.render(license.name(), &license).unwrap().unwrap().starts_with("Copyright © 2018 Naftuli Kay\n"));
+}
@@ -0,0 +1,615 @@
+pub mod remote;
+
+#[cfg(test)]
+mod tests;
+
+use crate::config::{CONFIG_ROOT, PROJECT_CONFIG_DIR};
+use crate::project::render::RenderPlan;
+use crate::project::templates::registry::Registered;
+use crate::project::templates::{overrides, validate, Placement};
+
+use anyhow::{anyhow, bail, Context, Result};
+
+use minijinja::{Environment, UndefinedBehavior};
+
+use serde::de::{Deserialize, Deserializer, Error};
+
+use serde_derive::Deserialize;
+
+use serde_yaml::{Mapping, Value};
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io;
+use std::iter::FromIterator;
+use std::path::{Component, Path, PathBuf};
+
+mod builtin {
+    include!(concat!(env!("OUT_DIR"), "/packs.rs"));
+}
+
+/// The file describing a template pack, at the top of its directory.
+static PACK_FILE: &str = "pack.yml";
+
+/// The directory within both `~/.config/nfty` and a project's `.nfty` holding template packs.
+static PACKS_DIR: &str = "packs";
+
+/// A template pack: a directory of templates described by a `pack.yml`, rendered into a project
+/// without any code specific to the kind of project.
+///
+/// ```yaml
+/// name: rust
+/// description: A Rust crate.
+/// variables:
+///   edition:
+///     type: string
+///     default: "2018"
+///   ci:
+///     type: bool
+///     default: true
+/// directories: [src]
+/// files:
+///   - template: Cargo.toml.j2
+///     newline: ensure
+///   - template: travis.yml.j2
+///     dest: .travis.yml
+///     comment: "#"
+///     when: ci
+///   - template: ci.sh.j2
+///     mode: 0o755
+///   - template: git/gitignore.j2
+///     builtin: true
+///     dest: .gitignore
+///     vars:
+///       rust: "true"
+///     comment: "#"
+/// commands:
+///   - run: cargo generate-lockfile
+/// ```
+#[derive(Debug, Deserialize)]
+pub struct Pack {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// The variables templates are rendered with, by name.
+    #[serde(default)]
+    pub variables: Variables,
+    /// Directories to create, relative to the project root.
+    #[serde(default)]
+    pub directories: Vec<String>,
+    #[serde(default)]
+    pub files: Vec<PackFile>,
+    /// Commands to run in the project root once the pack has been rendered.
+    #[serde(default)]
+    pub commands: Vec<PackCommand>,
+    /// Where the pack's templates are read from.
+    #[serde(skip)]
+    source: PackSource,
+    /// Whether the pack is compiled into nfty or is one of the user's own, rather than committed to
+    /// a project or fetched from elsewhere, so that its commands can be run in any project.
+    #[serde(skip)]
+    trusted: bool,
+}
+
+/// Variables by name, in the order they are declared, which is the order they are asked for.
+#[derive(Debug, Default)]
+pub struct Variables(Vec<(String, Variable)>);
+
+/// A variable templates in a pack are rendered with.
+#[derive(Debug, Deserialize)]
+pub struct Variable {
+    #[serde(rename = "type", default)]
+    pub kind: VariableType,
+    /// The value used when none is given. Variables without one must be given a value.
+    #[serde(default)]
+    pub default: Option<Value>,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum VariableType {
+    #[default]
+    String,
+    Bool,
+    Integer,
+    List,
+}
+
+/// A file rendered from a template in a pack, or from a template compiled into nfty.
+#[derive(Debug, Deserialize)]
+pub struct PackFile {
+    /// The path of the template within the pack, or within `templates/` if it is built in.
+    pub template: String,
+    /// Render a template compiled into nfty, or its override, rather than one in the pack.
+    #[serde(default)]
+    pub builtin: bool,
+    /// The path to render to within the project, which is itself a template. Defaults to the
+    /// template's path without its `.j2` extension, and must be given for built-in templates.
+    #[serde(default)]
+    pub dest: Option<String>,
+    /// An expression over the variables deciding whether the file is rendered at all.
+    #[serde(default)]
+    pub when: Option<String>,
+    /// Variables only this file is rendered with, by name, each a template over the pack's
+    /// variables rendering its value as YAML. Built-in templates are given their fields this way.
+    #[serde(default)]
+    pub vars: BTreeMap<String, String>,
+    /// Keep the lines already in the file along with those rendered, sorted and without blank lines
+    /// or comments, e.g. for a `requirements.txt` which projects add their own requirements to.
+    #[serde(default)]
+    pub keep_lines: bool,
+    /// The schema YAML is validated against, a type in `validate`, e.g. `Inventory`. Files are
+    /// otherwise only checked to be YAML or INI by their extension.
+    #[serde(default)]
+    pub schema: Option<String>,
+    /// The managed block, permissions, ending and overwriting of the file.
+    #[serde(flatten)]
+    pub placement: Placement,
+}
+
+/// A command run once a pack has been rendered.
+#[derive(Debug, Deserialize)]
+pub struct PackCommand {
+    /// The shell command to run, which is itself a template.
+    pub run: String,
+    /// An expression over the variables deciding whether the command is run.
+    #[serde(default)]
+    pub when: Option<String>,
+}
+
+#[derive(Debug, Default)]
+enum PackSource {
+    /// A pack in a directory on disk.
+    Directory(PathBuf),
+    /// A pack compiled into nfty, by its name.
+    #[default]
+    Builtin,
+}
+
+impl Variables {
+    pub fn get(&self, name: &str) -> Option<&Variable> {
+        self.0.iter().find(|(n, _)| n == name).map(|(_, v)| v)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Variable)> {
+        self.0.iter().map(|(n, v)| (n, v))
+    }
+}
+
+impl FromIterator<(String, Variable)> for Variables {
+    fn from_iter<I>(iter: I) -> Self where I: IntoIterator<Item = (String, Variable)> {
+        Variables(iter.into_iter().collect())
+    }
+}
+
+impl<'de> Deserialize<'de> for Variables {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error> where D: Deserializer<'de> {
+        // mappings keep the order of their keys
+        Mapping::deserialize(deserializer)?.into_iter()
+            .map(|(name, variable)| match name {
+                Value::String(name) => serde_yaml::from_value(variable)
+                    .map(|variable| (name, variable))
+                    .map_err(D::Error::custom),
+                name => Err(D::Error::custom(format!("variable names must be strings, not {:?}", name))),
+            })
+            .collect()
+    }
+}
+
+impl Variable {
+    pub fn new(kind: VariableType, default: Option<Value>, description: &str) -> Self {
+        Variable { kind, default, description: Some(description.to_string()) }
+    }
+}
+
+impl VariableType {
+    /// Parse a value given on the command line, where lists are comma separated.
+    pub fn parse(&self, raw: &str) -> Result<Value> {
+        Ok(match self {
+            VariableType::String => Value::String(raw.to_string()),
+            VariableType::Bool => match raw.to_lowercase().as_str() {
+                "true" | "yes" | "y" | "1" => Value::Bool(true),
+                "false" | "no" | "n" | "0" => Value::Bool(false),
+                _ => bail!("{:?} is not a bool", raw),
+            },
+            VariableType::Integer => Value::Number(raw.trim().parse::<i64>()
+                .map_err(|_| anyhow!("{:?} is not an integer", raw))?.into()),
+            VariableType::List => Value::Sequence(raw.split(',')
+                .map(|s| s.trim())
+                .filter(|s| !s.is_empty())
+                .map(|s| Value::String(s.to_string()))
+                .collect()),
+        })
+    }
+
+    /// Whether a value is of this type.
+    pub fn matches(&self, value: &Value) -> bool {
+        match self {
+            VariableType::String => value.is_string(),
+            VariableType::Bool => value.is_bool(),
+            VariableType::Integer => value.is_i64() || value.is_u64(),
+            VariableType::List => value.is_sequence(),
+        }
+    }
+}
+
+impl Pack {
+    /// Find a pack by its path, or by its name in `~/.config/nfty/packs`, among the packs compiled
+    /// into nfty, or in the project's `.nfty/packs`, in that order, so that a project can't shadow
+    /// the packs nfty and the user provide.
+    pub fn find(name: &str, root: &Path) -> Result<Pack> {
+        let path = Path::new(name);
+
+        if path.components().count() > 1 || path.join(PACK_FILE).is_file() {
+            return Pack::load(path);
+        }
+
+        let user = CONFIG_ROOT.join(PACKS_DIR).join(name);
+
+        if user.join(PACK_FILE).is_file() {
+            let mut pack = Pack::load(&user)?;
+            pack.trusted = true;
+
+            return Ok(pack);
+        }
+
+        if let Some(pack) = Pack::builtin(name) {
+            return Ok(pack);
+        }
+
+        let project = root.join(PROJECT_CONFIG_DIR).join(PACKS_DIR).join(name);
+
+        if project.join(PACK_FILE).is_file() {
+            return Pack::load(&project);
+        }
+
+        bail!("no template pack named {}", name)
+    }
+
+    /// Load the pack in the given directory.
+    pub fn load(dir: &Path) -> Result<Pack> {
+        let path = dir.join(PACK_FILE);
+        let contents = fs::read_to_string(&path).with_context(|| format!("unable to read {}", path.display()))?;
+
+        let mut pack = Pack::parse(&contents).with_context(|| format!("invalid template pack {}", path.display()))?;
+        pack.source = PackSource::Directory(dir.to_path_buf());
+
+        Ok(pack)
+    }
+
+    /// The pack of the given name compiled into nfty, if there is one.
+    pub fn builtin(name: &str) -> Option<Pack> {
+        let contents = builtin_file(&format!("{}/{}", name, PACK_FILE))?;
+
+        // built-in packs are checked by the tests
+        let mut pack = Pack::parse(contents).ok()?;
+        pack.trusted = true;
+
+        Some(pack)
+    }
+
+    /// Parse a `pack.yml`.
+    pub fn parse(contents: &str) -> Result<Pack> {
+        let pack: Pack = serde_yaml::from_str(contents)?;
+
+        for (name, variable) in pack.variables.iter() {
+            if let Some(default) = variable.default.as_ref().filter(|d| !variable.kind.matches(d)) {
+                bail!("the default of variable {}, {:?}, is not a {:?}", name, default, variable.kind);
+            }
+        }
+
+        for file in &pack.files {
+            if file.builtin && overrides::builtin(&file.template).is_none() {
+                bail!("there is no built-in template {}", file.template);
+            }
+
+            if file.builtin && file.dest.is_none() {
+                bail!("the built-in template {} must be given a dest", file.template);
+            }
+
+            if let Some(schema) = file.schema.as_ref().filter(|s| validate::schema(s).is_none()) {
+                bail!("{} has no schema {}", file.template, schema);
+            }
+
+            if let Some(mode) = file.placement.mode.filter(|m| *m > 0o7777) {
+                bail!("{} has mode {:o}, which is not a file mode", file.template, mode);
+            }
+        }
+
+        Ok(pack)
+    }
+
+    /// Resolve the value of every variable from the given values, falling back to their defaults.
+    pub fn resolve(&self, given: &BTreeMap<String, Value>) -> Result<Mapping> {
+        resolve(&format!("template pack {}", self.name), &self.variables, given)
+    }
+
+    /// Parse `name=value` assignments of the pack's variables, as given on the command line.
+    pub fn parse_assignments(&self, assignments: &[String]) -> Result<BTreeMap<String, Value>> {
+        parse_assignments(&format!("template pack {}", self.name), &self.variables, assignments)
+    }
+
+    /// Plan every directory and file in the pack with the given variables, returning every error
+    /// encountered rather than stopping at the first.
+    pub fn plan(&self, plan: &mut RenderPlan, variables: &Mapping) -> Vec<anyhow::Error> {
+        let root = plan.root().to_path_buf();
+        let env = environment(&root);
+        let mut errors = Vec::new();
+
+        for dir in &self.directories {
+            match render(&env, dir, variables).and_then(|rendered| within(&root, &rendered)) {
+                Ok(dir) => plan.add_directory(&dir),
+                Err(e) => errors.push(e.context(format!("unable to render directory {}", dir))),
+            }
+        }
+
+        for file in &self.files {
+            if let Err(e) = self.plan_file(plan, &env, file, variables) {
+                errors.push(e.context(format!("unable to render {}", file.template)));
+            }
+        }
+
+        errors
+    }
+
+    fn plan_file(&self, plan: &mut RenderPlan, env: &Environment, file: &PackFile, variables: &Mapping) -> Result<()> {
+        if !is_true(env, file.when.as_deref(), variables)? {
+            return Ok(());
+        }
+
+        let dest = match file.dest {
+            Some(ref dest) => render(env, dest, variables)?,
+            None => file.template.trim_end_matches(".j2").to_string(),
+        };
+
+        let path = within(plan.root(), &dest)?;
+        let context = file.context(env, variables)?;
+
+        // built-in templates are rendered from their overrides just as they are by the built-in conform
+        let overridden = match file.builtin {
+            true => plan.overrides().render(&file.template, &context)?,
+            false => None,
+        };
+
+        let mut rendered = match overridden {
+            Some(rendered) => rendered,
+            None => render(env, &self.source(file)?, &context)?,
+        };
+
+        if file.keep_lines {
+            rendered = keep_lines(&path, &rendered)?;
+        }
+
+        // what is checked is what is written, including anything around the managed block
+        let contents = plan.placed(&path, rendered, &file.placement)?;
+
+        let checked = match file.schema.as_deref().and_then(validate::schema) {
+            Some(check) => check(&contents),
+            None => validate::by_extension(Path::new(&dest), &contents),
+        };
+
+        checked.map_err(|invalid| {
+            anyhow!("{} is {}", dest, validate::blame(&invalid, &contents, &Value::Mapping(context.clone())))
+        })?;
+
+        let name = match file.builtin {
+            true => file.template.clone(),
+            false => format!("{}/{}", self.name, file.template),
+        };
+
+        plan.add_placed(&path, contents, name, &file.placement)?;
+
+        Ok(())
+    }
+
+    /// The commands to run in the project at the given root once the pack has been rendered with
+    /// the given variables.
+    pub fn commands(&self, root: &Path, variables: &Mapping) -> Result<Vec<String>> {
+        let env = environment(root);
+        let mut commands = Vec::new();
+
+        for command in &self.commands {
+            if is_true(&env, command.when.as_deref(), variables)? {
+                commands.push(render(&env, &command.run, variables)?);
+            }
+        }
+
+        Ok(commands)
+    }
+
+    /// Whether the pack's commands can be run in any project: packs compiled into nfty and the user's
+    /// own packs are, packs loaded from anywhere else are only run in trusted projects.
+    pub fn is_trusted(&self) -> bool {
+        self.trusted
+    }
+
+    /// The source of the template a file is rendered from.
+    fn source(&self, file: &PackFile) -> Result<String> {
+        match file.builtin {
+            true => overrides::builtin(&file.template)
+                .map(|s| s.to_string())
+                .ok_or_else(|| anyhow!("there is no built-in template {}", file.template)),
+            false => self.read(&file.template),
+        }
+    }
+
+    /// Read a file in the pack, given its path within the pack.
+    fn read(&self, relative: &str) -> Result<String> {
+        match self.source {
+            PackSource::Directory(ref dir) => {
+                let path = within(dir, relative)?;
+                fs::read_to_string(&path).with_context(|| format!("unable to read {}", path.display()))
+            }
+            PackSource::Builtin => builtin_file(&format!("{}/{}", self.name, relative))
+                .map(|s| s.to_string())
+                .ok_or_else(|| anyhow!("template pack {} has no file {}", self.name, relative)),
+        }
+    }
+}
+
+impl PackFile {
+    /// The variables the file is rendered with: the pack's, along with its own. Built-in templates
+    /// are rendered with the values of their fields, just as the built-in conform renders them, so
+    /// that fields the pack doesn't give are still defined.
+    fn context(&self, env: &Environment, variables: &Mapping) -> Result<Mapping> {
+        let mut context = variables.clone();
+
+        for (name, source) in &self.vars {
+            let rendered = render(env, source, variables).with_context(|| format!("unable to render variable {}", name))?;
+
+            let value = match rendered.is_empty() {
+                true => Value::Null,
+                false => serde_yaml::from_str(&rendered)
+                    .with_context(|| format!("variable {} is not YAML: {:?}", name, rendered))?,
+            };
+
+            context.insert(Value::String(name.clone()), value);
+        }
+
+        match Registered::find(&self.template).filter(|_| self.builtin) {
+            Some(registered) => registered.values(context),
+            None => Ok(context),
+        }
+    }
+}
+
+/// Parse `name=value` assignments of the variables of a template, as given on the command line.
+pub fn parse_assignments(template: &str, variables: &Variables, assignments: &[String]) -> Result<BTreeMap<String, Value>> {
+    let mut given = BTreeMap::new();
+
+    for assignment in assignments {
+        let (name, raw) = assignment.split_once('=')
+            .ok_or_else(|| anyhow!("expected name=value, not {:?}", assignment))?;
+
+        let variable = variables.get(name).ok_or_else(|| anyhow!("{} has no variable {}", template, name))?;
+
+        given.insert(name.to_string(), variable.kind.parse(raw).with_context(|| format!("invalid value for {}", name))?);
+    }
+
+    Ok(given)
+}
+
+/// Resolve the value of every variable of a template from the given values, falling back to their
+/// defaults.
+pub fn resolve(template: &str, variables: &Variables, given: &BTreeMap<String, Value>) -> Result<Mapping> {
+    if let Some(name) = given.keys().find(|name| !variables.contains(name)) {
+        bail!("{} has no variable {}", template, name);
+    }
+
+    let mut resolved = Mapping::new();
+
+    for (name, variable) in variables.iter() {
+        let value = match given.get(name).or(variable.default.as_ref()) {
+            Some(value) if variable.kind.matches(value) => value.clone(),
+            Some(value) => bail!("variable {} must be a {:?}, not {:?}", name, variable.kind, value),
+            None => bail!("variable {} has no default and must be set", name),
+        };
+
+        resolved.insert(Value::String(name.clone()), value);
+    }
+
+    Ok(resolved)
+}
+
+/// The contents of a file in the packs compiled into nfty, by its path within `packs/`.
+fn builtin_file(path: &str) -> Option<&'static str> {
+    builtin::BUILTIN.iter().find(|(p, _)| *p == path).map(|(_, contents)| *contents)
+}
+
+/// The environment a pack's templates are rendered in for the project at the given root, where
+/// `project_yaml(path)` is the YAML file at the path within the project, parsed, or none if there
+/// is no such file, e.g. to read the dependencies of an Ansible role from its `meta/main.yml`.
+fn environment(root: &Path) -> Environment<'static> {
+    let root = root.to_path_buf();
+    let mut env = Environment::new();
+    env.set_undefined_behavior(UndefinedBehavior::Strict);
+
+    env.add_function("project_yaml", move |path: &str| -> std::result::Result<minijinja::Value, minijinja::Error> {
+        let path = within(&root, path)
+            .map_err(|e| minijinja::Error::new(minijinja::ErrorKind::InvalidOperation, e.to_string()))?;
+        let invalid = |e: &dyn std::fmt::Display| {
+            minijinja::Error::new(minijinja::ErrorKind::InvalidOperation, format!("{}: {}", path.display(), e))
+        };
+
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(minijinja::Value::from(())),
+            Err(e) => return Err(invalid(&e)),
+        };
+
+        let value: Value = serde_yaml::from_str(&contents).map_err(|e| invalid(&e))?;
+        Ok(minijinja::Value::from_serialize(&value))
+    });
+
+    env
+}
+
+/// Join a path given by a pack onto the directory it is relative to, refusing any which is absolute
+/// or leaves the directory, so that a pack can't read or write anything outside of the project,
+/// wherever it came from.
+pub fn within(dir: &Path, relative: &str) -> Result<PathBuf> {
+    let mut depth = 0usize;
+
+    for component in Path::new(relative).components() {
+        match component {
+            Component::Normal(_) => depth += 1,
+            Component::CurDir => (),
+            Component::ParentDir if depth > 0 => depth -= 1,
+            Component::ParentDir => bail!("{} is outside of {}", relative, dir.display()),
+            Component::RootDir | Component::Prefix(_) => bail!("{} is not a relative path", relative),
+        }
+    }
+
+    let path = dir.join(relative);
+
+    // nor can it through a symlink, which the project could have committed
+    if let Ok(canonical) = dir.canonicalize() {
+        let existing = path.ancestors().find(|p| fs::symlink_metadata(p).is_ok()).unwrap_or(dir);
+
+        if !existing.canonicalize().is_ok_and(|e| e.starts_with(&canonical)) {
+            bail!("{} is outside of {}", relative, dir.display());
+        }
+    }
+
+    Ok(path)
+}
+
+/// Render a template in a pack with its variables, trimmed as built-in templates are.
+fn render(env: &Environment, source: &str, variables: &Mapping) -> Result<String> {
+    Ok(env.render_str(source, variables)?.trim().to_string())
+}
+
+/// Keep the lines already in the file at the given path along with the rendered lines, sorted and
+/// without blank lines or comments.
+fn keep_lines(path: &Path, rendered: &str) -> Result<String> {
+    let existing = match fs::read_to_string(path) {
+        Ok(existing) => existing,
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(anyhow!("unable to read {}: {}", path.display(), e)),
+    };
+
+    let lines: BTreeSet<&str> = existing.lines().chain(rendered.lines())
+        .map(|l| l.trim())
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .collect();
+
+    Ok(lines.into_iter().collect::<Vec<_>>().join("\n"))
+}
+
+/// Evaluate an optional condition over a pack's variables, where no condition is true.
+fn is_true(env: &Environment, condition: Option<&str>, variables: &Mapping) -> Result<bool> {
+    let condition = match condition {
+        Some(condition) => condition,
+        None => return Ok(true),
+    };
+
+    let expression = env.compile_expression(condition).with_context(|| format!("invalid condition {:?}", condition))?;
+
+    Ok(expression.eval(variables)?.is_true())
+}
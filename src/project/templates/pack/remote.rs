@@ -0,0 +1,156 @@
+#[cfg(test)]
+mod tests;
+
+use crate::project::Project;
+use crate::util::{Checksum, Git};
+
+use dirs::{cache_dir, home_dir};
+
+use git2::build::{CheckoutBuilder, RepoBuilder};
+use git2::{FetchOptions, Oid, Repository};
+
+use lazy_static::lazy_static;
+
+use log::{debug, info};
+
+use parking_lot::Mutex;
+
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+lazy_static! {
+    /// The directory holding clones of template pack repositories, i.e. `~/.cache/nfty/packs`.
+    pub static ref PACK_CACHE: PathBuf = cache_dir()
+        .or_else(|| home_dir().map(|h| h.join(".cache")))
+        .expect("unable to get cache dir")
+        .join("nfty")
+        .join("packs");
+
+    /// A lock on each clone in the cache, which projects conformed in parallel share.
+    static ref CLONE_LOCKS: Mutex<HashMap<PathBuf, Arc<Mutex<()>>>> = Mutex::new(HashMap::new());
+}
+
+/// A git repository of template packs, at an optional branch, tag or commit, given as
+/// `owner/repository@ref` or any URL git understands followed by `@ref`.
+#[derive(Debug, Eq, PartialEq)]
+pub struct PackRepository {
+    pub url: String,
+    pub reference: Option<String>,
+}
+
+/// A checkout of a template pack repository.
+#[derive(Clone, Debug)]
+pub struct Checkout {
+    pub dir: PathBuf,
+    /// The commit the reference resolved to.
+    pub commit: String,
+}
+
+impl fmt::Display for PackRepository {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.reference {
+            Some(ref reference) => write!(f, "{}@{}", self.url, reference),
+            None => write!(f, "{}", self.url),
+        }
+    }
+}
+
+impl PackRepository {
+    /// Parse a repository, where short forms like `owner/repository` are resolved as projects are.
+    pub fn parse(value: &str) -> io::Result<Self> {
+        // the reference follows the last @, unless that @ is the user of an SSH URL
+        let (repository, reference) = match value.rsplit_once('@') {
+            Some((repository, reference)) if repository.contains('/') && !reference.contains(':') => {
+                (repository, Some(reference.to_string()))
+            }
+            _ => (value, None),
+        };
+
+        let url = if repository.contains("://") || repository.starts_with('/') || repository.starts_with('.') {
+            repository.to_string()
+        } else {
+            Project::from(repository)?.url()
+        };
+
+        Ok(PackRepository { url, reference })
+    }
+
+    /// The directory the repository is cloned into within the given cache.
+    pub fn dir(&self, cache: &Path) -> PathBuf {
+        cache.join(&Checksum::sha256(self.url.as_bytes())[..16])
+    }
+
+    /// The lock on the repository's clone in the given cache, to be held from fetching into the
+    /// clone until its checkout has been read, as checking out another commit changes it.
+    pub fn lock(&self, cache: &Path) -> Arc<Mutex<()>> {
+        CLONE_LOCKS.lock().entry(self.dir(cache)).or_default().clone()
+    }
+
+    /// Clone the repository into the given cache, or update the clone already there, and check out
+    /// its reference, or the remote's default branch if it has none.
+    pub fn fetch(&self, cache: &Path) -> io::Result<Checkout> {
+        let dir = self.dir(cache);
+
+        let repo = match Repository::open(&dir) {
+            Ok(repo) => {
+                info!("Updating template packs from {}", self.url);
+                self.update(&repo)?;
+                repo
+            }
+            Err(_) => {
+                info!("Cloning template packs from {}", self.url);
+                RepoBuilder::new().fetch_options(fetch_options()).clone(&self.url, &dir).map_err(to_io)?
+            }
+        };
+
+        let commit = self.resolve(&repo)?;
+        debug!("Checking out {} in {}", commit, dir.display());
+
+        repo.set_head_detached(commit).map_err(to_io)?;
+        repo.checkout_head(Some(CheckoutBuilder::new().force())).map_err(to_io)?;
+
+        Ok(Checkout { dir, commit: commit.to_string() })
+    }
+
+    /// Fetch every branch and tag, along with the remote's default branch.
+    fn update(&self, repo: &Repository) -> io::Result<()> {
+        let mut remote = repo.find_remote("origin").map_err(to_io)?;
+
+        // tags which already exist aren't updated by a fetch, so they're fetched anew in case they moved
+        for tag in repo.tag_names(None).map_err(to_io)?.iter().flatten() {
+            repo.tag_delete(tag).map_err(to_io)?;
+        }
+
+        remote.fetch(&["+refs/heads/*:refs/remotes/origin/*", "+refs/tags/*:refs/tags/*", "+HEAD:refs/remotes/origin/HEAD"],
+            Some(&mut fetch_options()), None).map_err(to_io)
+    }
+
+    /// Resolve the reference to a commit, as a branch of the remote, a tag or a commit.
+    fn resolve(&self, repo: &Repository) -> io::Result<Oid> {
+        let reference = match self.reference {
+            Some(ref reference) => reference,
+            None => return repo.refname_to_id("refs/remotes/origin/HEAD")
+                .or_else(|_| repo.head().and_then(|h| h.peel_to_commit()).map(|c| c.id()))
+                .map_err(to_io),
+        };
+
+        [format!("refs/remotes/origin/{}", reference), format!("refs/tags/{}", reference), reference.clone()].iter()
+            .filter_map(|r| repo.revparse_single(r).ok())
+            .find_map(|o| o.peel_to_commit().ok())
+            .map(|c| c.id())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("no branch, tag or commit {} in {}", reference, self.url)))
+    }
+}
+
+fn fetch_options<'a>() -> FetchOptions<'a> {
+    let mut options = FetchOptions::new();
+    options.remote_callbacks(Git::remote_callbacks());
+    options
+}
+
+fn to_io(e: git2::Error) -> io::Error {
+    io::Error::other(e.message().trim().to_string())
+}
@@ -0,0 +1,70 @@
+use super::*;
+
+use crate::testing::{self, Scratch};
+
+use std::fs;
+
+#[test]
+fn test_parse() {
+    let repository = PackRepository::parse("naftulikay/nfty-templates@v3").unwrap();
+    assert_eq!("git@github.com:naftulikay/nfty-templates", repository.url);
+    assert_eq!(Some("v3"), repository.reference.as_deref());
+
+    // the @ of an SSH user isn't a reference
+    let repository = PackRepository::parse("git@github.com:naftulikay/nfty-templates").unwrap();
+    assert_eq!("git@github.com:naftulikay/nfty-templates", repository.url);
+    assert_eq!(None, repository.reference);
+
+    let repository = PackRepository::parse("file:///srv/templates@release/2.x").unwrap();
+    assert_eq!("file:///srv/templates", repository.url);
+    assert_eq!(Some("release/2.x"), repository.reference.as_deref());
+}
+
+#[test]
+fn test_fetch_file_remote() {
+    let scratch = Scratch::new("pack-remote");
+
+    let upstream = Repository::init(scratch.join("upstream")).unwrap();
+    let first = testing::commit_file(&upstream, "pack.yml", "name: first\n", "first");
+    upstream.tag_lightweight("v1", &upstream.find_object(first, None).unwrap(), false).unwrap();
+
+    let url = format!("file://{}", scratch.join("upstream").display());
+    let cache = scratch.join("cache");
+
+    // the default branch is cloned into the cache
+    let checkout = PackRepository::parse(&url).unwrap().fetch(&cache).unwrap();
+    assert_eq!(first.to_string(), checkout.commit);
+    assert!(checkout.dir.starts_with(&cache));
+
+    // later fetches update the clone
+    let second = testing::commit_file(&upstream, "pack.yml", "name: second\n", "second");
+    let checkout = PackRepository::parse(&url).unwrap().fetch(&cache).unwrap();
+    assert_eq!(second.to_string(), checkout.commit);
+    assert_eq!("name: second\n", fs::read_to_string(checkout.dir.join("pack.yml")).unwrap());
+
+    // tags check out the commit they point to
+    let checkout = PackRepository::parse(&format!("{}@v1", url)).unwrap().fetch(&cache).unwrap();
+    assert_eq!(first.to_string(), checkout.commit);
+    assert_eq!("name: first\n", fs::read_to_string(checkout.dir.join("pack.yml")).unwrap());
+
+    assert!(PackRepository::parse(&format!("{}@v2", url)).unwrap().fetch(&cache).is_err());
+
+    // as do tags which have moved since they were last fetched
+    upstream.tag_lightweight("v1", &upstream.find_object(second, None).unwrap(), true).unwrap();
+    let checkout = PackRepository::parse(&format!("{}@v1", url)).unwrap().fetch(&cache).unwrap();
+    assert_eq!(second.to_string(), checkout.commit);
+}
+
+#[test]
+fn test_lock() {
+    let cache = Path::new("/nonexistent/cache");
+
+    let release = PackRepository::parse("naftulikay/nfty-templates@release").unwrap();
+    let main = PackRepository::parse("naftulikay/nfty-templates").unwrap();
+    let other = PackRepository::parse("naftulikay/other-templates").unwrap();
+
+    // references of a repository share its clone, so they share its lock
+    assert!(Arc::ptr_eq(&release.lock(cache), &main.lock(cache)));
+    assert!(!Arc::ptr_eq(&release.lock(cache), &other.lock(cache)));
+    assert!(!Arc::ptr_eq(&release.lock(cache), &release.lock(Path::new("/nonexistent/other-cache"))));
+}
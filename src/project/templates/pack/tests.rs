@@ -0,0 +1,273 @@
+use super::*;
+
+use crate::project::templates::{Newline, Overwrite};
+use crate::testing::Scratch;
+
+static PACK: &str = r#"
+name: example
+variables:
+  name:
+    type: string
+  ci:
+    type: bool
+    default: true
+  jobs:
+    type: integer
+    default: 2
+  targets:
+    type: list
+    default: [x86_64]
+directories: ["{{ name }}"]
+files:
+  - template: README.md.j2
+  - template: ci.yml.j2
+    dest: .ci/{{ name }}.yml
+    when: ci and jobs > 1
+commands:
+  - run: make {{ targets | join(" ") }}
+  - run: git init
+    when: not ci
+"#;
+
+#[test]
+fn test_builtin_packs_parse() {
+    let names: std::collections::BTreeSet<&str> = builtin::BUILTIN.iter()
+        .filter_map(|(path, _)| path.strip_suffix("/pack.yml"))
+        .collect();
+
+    assert!(names.contains("ansible"));
+
+    for name in names {
+        let pack = Pack::parse(builtin_file(&format!("{}/{}", name, PACK_FILE)).unwrap()).unwrap();
+
+        // every template a built-in pack renders is compiled in with it, or is a built-in template
+        for file in pack.files.iter().filter(|f| !f.builtin) {
+            assert!(builtin_file(&format!("{}/{}", name, file.template)).is_some(), "{} is missing", file.template);
+        }
+    }
+}
+
+#[test]
+fn test_parse_rejects_mistyped_defaults() {
+    assert!(Pack::parse("name: x\nvariables:\n  ci:\n    type: bool\n    default: yes please\n").is_err());
+}
+
+#[test]
+fn test_parse_files() {
+    let pack = Pack::parse(r#"
+name: x
+files:
+  - template: run.sh.j2
+    mode: 0o755
+    newline: ensure
+    overwrite: never
+  - template: git/gitignore.j2
+    builtin: true
+    dest: .gitignore
+"#).unwrap();
+
+    assert_eq!(Some(0o755), pack.files[0].placement.mode);
+    assert_eq!(Newline::Ensure, pack.files[0].placement.newline);
+    assert_eq!(Overwrite::Never, pack.files[0].placement.overwrite);
+    assert!(pack.files[1].builtin);
+
+    // built-in templates must exist and be given somewhere to render to, and schemas must exist
+    assert!(Pack::parse("name: x\nfiles:\n  - template: git/gitignore.j2\n    builtin: true\n").is_err());
+    assert!(Pack::parse("name: x\nfiles:\n  - template: nope.j2\n    builtin: true\n    dest: nope\n").is_err());
+    assert!(Pack::parse("name: x\nfiles:\n  - template: a.yml.j2\n    schema: Nope\n").is_err());
+    assert!(Pack::parse("name: x\nfiles:\n  - template: a.sh.j2\n    mode: 0o17777\n").is_err());
+}
+
+#[test]
+fn test_plan_files() {
+    let scratch = Scratch::new("pack-plan-files");
+    fs::write(scratch.join("requirements.txt"), "requests\n\n# pinned\nboto3\n").unwrap();
+    fs::write(scratch.join("hosts.yml"), "---\nall: {}\n").unwrap();
+
+    let mut pack = Pack::parse(r#"
+name: x
+variables:
+  hosts:
+    type: list
+files:
+  - template: python/requirements.txt.j2
+    builtin: true
+    dest: requirements.txt
+    vars:
+      requirements: "[ansible, requests]"
+    keep_lines: true
+    newline: ensure
+  - template: hosts.yml.j2
+    vars:
+      hosts: "{{ hosts | sort | join(', ') }}"
+    schema: Inventory
+"#).unwrap();
+
+    let pack_dir = scratch.join("pack");
+    fs::create_dir_all(&pack_dir).unwrap();
+    fs::write(pack_dir.join("hosts.yml.j2"), "---\nall:\n  hosts: {{ hosts }}\n").unwrap();
+    pack.source = PackSource::Directory(pack_dir);
+
+    let variables = pack.resolve(&pack.parse_assignments(&["hosts=b,a".to_string()]).unwrap()).unwrap();
+
+    let mut plan = RenderPlan::new(&scratch);
+    let errors = pack.plan(&mut plan, &variables);
+
+    // existing lines are kept, and the hosts aren't the mapping the schema requires
+    assert_eq!("ansible\nboto3\nrequests\n", plan.files()[0].planned.as_deref().unwrap());
+    assert_eq!(Some("python/requirements.txt.j2"), plan.files()[0].template.as_deref());
+
+    assert_eq!(1, errors.len());
+    assert!(format!("{:#}", errors[0]).contains("hosts = \"a, b\""), "{:#}", errors[0]);
+}
+
+#[test]
+fn test_variable_type_parse() {
+    assert_eq!(Value::Bool(false), VariableType::Bool.parse("no").unwrap());
+    assert_eq!(Value::Number(8.into()), VariableType::Integer.parse("8").unwrap());
+    assert!(VariableType::Integer.parse("eight").is_err());
+    assert_eq!(2, VariableType::List.parse("a, b,").unwrap().as_sequence().unwrap().len());
+}
+
+#[test]
+fn test_resolve() {
+    let pack = Pack::parse(PACK).unwrap();
+
+    // variables keep the order they're declared in, which is the order they're asked for
+    let names: Vec<&str> = pack.variables.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(vec!["name", "ci", "jobs", "targets"], names);
+
+    // variables without defaults must be given
+    assert!(pack.resolve(&BTreeMap::new()).is_err());
+    assert!(pack.parse_assignments(&["unknown=1".to_string()]).is_err());
+    assert!(pack.parse_assignments(&["jobs".to_string()]).is_err());
+
+    let given = pack.parse_assignments(&["name=widget".to_string(), "jobs=4".to_string()]).unwrap();
+    let resolved = pack.resolve(&given).unwrap();
+
+    assert_eq!(Some("widget"), resolved.get(&Value::from("name")).and_then(Value::as_str));
+    assert_eq!(Some(4), resolved.get(&Value::from("jobs")).and_then(Value::as_i64));
+    assert_eq!(Some(true), resolved.get(&Value::from("ci")).and_then(Value::as_bool));
+}
+
+#[test]
+fn test_commands() {
+    let pack = Pack::parse(PACK).unwrap();
+    let given = pack.parse_assignments(&["name=widget".to_string(), "targets=x86_64,aarch64".to_string()]).unwrap();
+
+    assert_eq!(vec!["make x86_64 aarch64".to_string()], pack.commands(Path::new("/nonexistent/project"), &pack.resolve(&given).unwrap()).unwrap());
+}
+
+#[test]
+fn test_find_order() {
+    let scratch = Scratch::new("pack-find-order");
+    let packs = scratch.join(PROJECT_CONFIG_DIR).join(PACKS_DIR);
+
+    for name in ["ansible", "local"] {
+        fs::create_dir_all(packs.join(name)).unwrap();
+        fs::write(packs.join(name).join(PACK_FILE), "name: shadow\ncommands:\n  - run: echo shadow\n").unwrap();
+    }
+
+    // the project can't shadow a built-in pack
+    let builtin = Pack::find("ansible", &scratch).unwrap();
+    assert_eq!("ansible", builtin.name);
+    assert!(builtin.is_trusted());
+
+    let local = Pack::find("local", &scratch).unwrap();
+    assert_eq!("shadow", local.name);
+    assert!(!local.is_trusted());
+
+    assert!(!Pack::load(&packs.join("local")).unwrap().is_trusted());
+    assert!(Pack::find("nonexistent", &scratch).is_err());
+}
+
+#[test]
+fn test_plan_builtin_ansible() {
+    let pack = Pack::builtin("ansible").unwrap();
+    let given = pack.parse_assignments(&["machines=xenial".to_string()]).unwrap();
+
+    let mut plan = RenderPlan::new(Path::new("/nonexistent/project"));
+    assert!(pack.plan(&mut plan, &pack.resolve(&given).unwrap()).is_empty());
+
+    let hosts = plan.files().iter().find(|f| f.path.ends_with("tests/inventory/hosts.yml")).unwrap();
+    assert_eq!(Some("ansible/tests/inventory/hosts.yml.j2"), hosts.template.as_deref());
+    assert!(hosts.planned.as_deref().unwrap().contains("    xenial: {}\n"));
+
+    let gitignore = plan.files().iter().find(|f| f.path.ends_with(".gitignore")).unwrap();
+    assert!(gitignore.planned.as_deref().unwrap().starts_with("# BEGIN nfty managed\n# Ansible\n"));
+
+    assert_eq!(vec![PathBuf::from("/nonexistent/project/tests/inventory")], plan.directories());
+}
+
+#[test]
+fn test_plan_builtin_ansible_role_dependencies() {
+    let scratch = Scratch::new("pack-role-dependencies");
+    let pack = Pack::builtin("ansible").unwrap();
+    let variables = pack.resolve(&BTreeMap::new()).unwrap();
+
+    let requirements = |meta: &str| {
+        fs::create_dir_all(scratch.join("meta")).unwrap();
+        fs::write(scratch.join("meta/main.yml"), meta).unwrap();
+
+        let mut plan = RenderPlan::new(&scratch);
+        let errors = pack.plan(&mut plan, &variables);
+        assert!(errors.is_empty(), "{:?}", errors);
+
+        plan.files().iter().find(|f| f.path.ends_with("tests/requirements.yml")).unwrap().planned.clone().unwrap()
+    };
+
+    assert_eq!("---\n- src: naftulikay.degoss\n", requirements("galaxy_info:\n  author: naftulikay\n"));
+    assert_eq!("---\n- src: naftulikay.degoss\n", requirements("galaxy_info: {}\ndependencies:\n"));
+    assert_eq!("---\n- src: a.b\n- src: c.d\n- src: naftulikay.degoss\n",
+        requirements("galaxy_info: {}\ndependencies:\n  - role: a.b\n  - c.d\n"));
+}
+
+#[test]
+fn test_within() {
+    let dir = Path::new("/nonexistent/project");
+
+    assert_eq!(dir.join("tests/./hosts.yml"), within(dir, "tests/./hosts.yml").unwrap());
+    assert_eq!(dir.join("tests/../hosts.yml"), within(dir, "tests/../hosts.yml").unwrap());
+    assert!(within(dir, "../pwned.txt").is_err());
+    assert!(within(dir, "tests/../../pwned.txt").is_err());
+    assert!(within(dir, "/tmp/pwned.txt").is_err());
+}
+
+#[test]
+fn test_plan_stays_within_project() {
+    let scratch = Scratch::new("pack-hostile");
+    let root = scratch.join("project");
+    let pack_dir = scratch.join("pack");
+
+    fs::create_dir_all(&root).unwrap();
+    fs::create_dir_all(&pack_dir).unwrap();
+    fs::write(scratch.join("secret.yml"), "token: hunter2\n").unwrap();
+    std::os::unix::fs::symlink(&scratch, root.join("escape")).unwrap();
+
+    fs::write(pack_dir.join("pwned.txt.j2"), "pwned").unwrap();
+    fs::write(pack_dir.join("secret.txt.j2"), "{{ project_yaml('../secret.yml') }}").unwrap();
+
+    let mut pack = Pack::parse(r#"
+name: hostile
+directories: ["../outside", "/tmp/outside"]
+files:
+  - template: pwned.txt.j2
+    dest: ../pwned.txt
+  - template: pwned.txt.j2
+    dest: /tmp/pwned.txt
+  - template: pwned.txt.j2
+    dest: escape/pwned.txt
+  - template: ../secret.yml
+  - template: secret.txt.j2
+"#).unwrap();
+
+    pack.source = PackSource::Directory(pack_dir);
+
+    let mut plan = RenderPlan::new(&root);
+    let errors = pack.plan(&mut plan, &Mapping::new());
+
+    // every directory and file is refused, and nothing is planned
+    assert_eq!(7, errors.len(), "{:?}", errors);
+    assert!(plan.files().is_empty());
+    assert!(plan.directories().is_empty());
+}
@@ -1,13 +1,16 @@
-use askama::Template;
+use serde_derive::{Deserialize, Serialize};
 
 use std::collections::BTreeSet;
 use std::fs;
 use std::io::Result;
-use std::io::Write;
 use std::path::Path;
 
-#[derive(Default, Template, WritableTemplate)]
+/// The Python packages a project requires, one per line. It is rendered from the requirements
+/// already in the file, so it always overwrites it.
+#[derive(Default, Deserialize, Serialize, WritableTemplate)]
+#[serde(default)]
 #[template(path = "python/requirements.txt.j2")]
+#[writable(default_path = "requirements.txt", newline = "ensure", overwrite = "always")]
 pub struct PythonRequirements {
     pub requirements: BTreeSet<String>,
 }
@@ -0,0 +1,141 @@
+#[cfg(test)]
+mod tests;
+
+use crate::project::templates::{ansible, git, license, python, vagrant, Field, WritableTemplate};
+use crate::project::templates::overrides::Overrides;
+use crate::project::templates::validate;
+
+use anyhow::{anyhow, Context, Result};
+
+use serde::de::DeserializeOwned;
+
+use serde_yaml::{Mapping, Value};
+
+/// A template type built into nfty, which can be rendered by name from the values of its fields.
+pub struct Registered {
+    /// The name the template is found by, which is its path within `templates/` unless it shares
+    /// that template with another type.
+    pub name: &'static str,
+    /// The path of the template it renders within `templates/`, which overrides of it are found by.
+    pub template: &'static str,
+    pub description: &'static str,
+    pub fields: &'static [Field],
+    defaults: fn() -> Result<Value>,
+    values: fn(Value) -> Result<Value>,
+    render: fn(Value, &Overrides) -> Result<String>,
+}
+
+impl Registered {
+    fn of<T>() -> Self where T: WritableTemplate + Default + DeserializeOwned {
+        Registered {
+            name: T::default().name(),
+            template: T::default().name(),
+            description: T::description(),
+            fields: T::fields(),
+            defaults: defaults::<T>,
+            values: values::<T>,
+            render: render::<T>,
+        }
+    }
+
+    /// A type rendering the same template as another, found by a name of its own.
+    fn named<T>(name: &'static str) -> Self where T: WritableTemplate + Default + DeserializeOwned {
+        Registered { name, ..Registered::of::<T>() }
+    }
+
+    /// Find a template by its name, with or without the `.j2` extension.
+    pub fn find(name: &str) -> Option<Registered> {
+        all().into_iter().find(|t| t.name == name || t.name.trim_end_matches(".j2") == name)
+    }
+
+    /// The values the fields of the template take when they aren't given.
+    pub fn defaults(&self) -> Result<Mapping> {
+        match (self.defaults)()? {
+            Value::Mapping(mapping) => Ok(mapping),
+            _ => Ok(Mapping::new()),
+        }
+    }
+
+    /// The values of the template's fields taken from the given values, which are those it is
+    /// rendered with: fields which aren't given take their defaults, and anything else is dropped.
+    pub fn values(&self, values: Mapping) -> Result<Mapping> {
+        match (self.values)(Value::Mapping(values)).with_context(|| format!("invalid values for {}", self.name))? {
+            Value::Mapping(mapping) => Ok(mapping),
+            _ => Ok(Mapping::new()),
+        }
+    }
+
+    /// Parse `name=value` assignments of the template's fields, as given on the command line.
+    /// Strings are taken as given, bools as yes or no, and lists of strings are comma separated.
+    /// Variables without a kind are parsed as YAML, e.g. `machines=[{name: a, image: centos:7}]`.
+    pub fn parse_assignments(&self, assignments: &[String]) -> Result<Mapping> {
+        let mut values = Mapping::new();
+
+        for assignment in assignments {
+            let (name, raw) = assignment.split_once('=')
+                .ok_or_else(|| anyhow!("expected name=value, not {:?}", assignment))?;
+
+            let field = self.fields.iter().find(|f| f.name == name)
+                .ok_or_else(|| anyhow!("template {} has no variable {}", self.name, name))?;
+
+            let value = match field.kind {
+                Some(kind) => kind.parse(raw),
+                None => serde_yaml::from_str(raw).map_err(anyhow::Error::from),
+            };
+
+            values.insert(Value::String(name.to_string()), value.with_context(|| format!("invalid value for {}", name))?);
+        }
+
+        Ok(values)
+    }
+
+    /// Render the template with the given values of its fields, or their defaults where they
+    /// aren't given, using any override of it.
+    pub fn render(&self, values: Mapping, overrides: &Overrides) -> Result<String> {
+        (self.render)(Value::Mapping(values), overrides).with_context(|| format!("unable to render {}", self.name))
+    }
+}
+
+/// Every template type built into nfty.
+pub fn all() -> Vec<Registered> {
+    vec![
+        Registered::of::<ansible::AnsibleConfig>(),
+        Registered::of::<ansible::DockerComposeConfig>(),
+        Registered::of::<ansible::AnsibleRequirements>(),
+        Registered::of::<ansible::TravisConfig>(),
+        Registered::of::<ansible::Makefile>(),
+        Registered::of::<ansible::TestsGitIgnore>(),
+        Registered::of::<ansible::TestsMakefile>(),
+        Registered::named::<ansible::TestsAnsibleConfig>("ansible/tests/ansible.cfg.j2"),
+        Registered::named::<ansible::TestsAnsibleRequirements>("ansible/tests/galaxy-requirements.yml.j2"),
+        Registered::of::<ansible::InventoryYaml>(),
+        Registered::of::<git::GitIgnore>(),
+        Registered::of::<license::ApacheLicense>(),
+        Registered::of::<license::MitLicense>(),
+        Registered::of::<license::PrivateLicense>(),
+        Registered::of::<python::PythonRequirements>(),
+        Registered::of::<vagrant::Vagrantfile>(),
+        Registered::of::<vagrant::VagrantPlaybook>(),
+    ]
+}
+
+fn defaults<T>() -> Result<Value> where T: WritableTemplate + Default {
+    Ok(serde_yaml::to_value(T::default())?)
+}
+
+fn values<T>(values: Value) -> Result<Value> where T: WritableTemplate + DeserializeOwned {
+    Ok(serde_yaml::to_value(serde_yaml::from_value::<T>(values)?)?)
+}
+
+fn render<T>(values: Value, overrides: &Overrides) -> Result<String> where T: WritableTemplate + DeserializeOwned {
+    let template: T = serde_yaml::from_value(values)?;
+
+    let contents = match overrides.render(template.name(), &template)? {
+        Some(contents) => contents,
+        None => template.contents()?,
+    };
+
+    validate::check(&template, &contents)?;
+
+    Ok(contents)
+}
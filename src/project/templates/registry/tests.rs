@@ -0,0 +1,145 @@
+use super::*;
+
+use crate::project::templates::overrides::BUILTIN;
+use crate::project::templates::pack::VariableType;
+
+use std::path::PathBuf;
+
+fn overrides() -> Overrides {
+    Overrides::new(Some(&PathBuf::from("/nonexistent/project")))
+}
+
+fn assignments(values: &[&str]) -> Vec<String> {
+    values.iter().map(|v| v.to_string()).collect()
+}
+
+#[test]
+fn test_every_template_registered() {
+    for name in BUILTIN {
+        assert!(Registered::find(name).is_some(), "{} has no registered type", name);
+    }
+
+    for template in all() {
+        assert!(BUILTIN.contains(&template.template), "{} is not compiled in", template.template);
+        assert!(!template.description.is_empty(), "{} has no description", template.name);
+
+        // types sharing a template are found by names of their own
+        assert_eq!(1, all().iter().filter(|t| t.name == template.name).count(), "{} is registered twice", template.name);
+    }
+}
+
+#[test]
+fn test_find() {
+    assert_eq!("git/gitignore.j2", Registered::find("git/gitignore.j2").unwrap().name);
+    assert_eq!("git/gitignore.j2", Registered::find("git/gitignore").unwrap().name);
+    assert!(Registered::find("git/nonexistent").is_none());
+
+    // the tests of a role render the same templates as the role itself
+    let config = Registered::find("ansible/tests/ansible.cfg").unwrap();
+    assert_eq!("ansible/ansible.cfg.j2", config.template);
+    assert_eq!("ansible/ansible.cfg.j2", Registered::find("ansible/ansible.cfg").unwrap().name);
+
+    let requirements = Registered::find("ansible/tests/galaxy-requirements.yml").unwrap();
+    assert_eq!("ansible/galaxy-requirements.yml.j2", requirements.template);
+
+    let values = requirements.parse_assignments(&assignments(&["roles=[{src: naftulikay.degoss}]"])).unwrap();
+    let expected = ansible::TestsAnsibleRequirements::new()
+        .with_roles(vec![ansible::GalaxyRole::from_src("naftulikay.degoss")])
+        .contents().unwrap();
+
+    assert_eq!(expected, requirements.render(values, &overrides()).unwrap());
+}
+
+#[test]
+fn test_fields() {
+    let template = Registered::find("vagrant/playbook.yml").unwrap();
+
+    let fields: Vec<(&str, &str, Option<VariableType>)> = template.fields.iter().map(|f| (f.name, f.ty, f.kind)).collect();
+    assert_eq!(("include_docker", "bool", Some(VariableType::Bool)), fields[0]);
+    assert_eq!(("go_version", "Option<String>", Some(VariableType::String)), fields[1]);
+
+    let defaults = template.defaults().unwrap();
+    assert_eq!(Some(&Value::Bool(false)), defaults.get(&Value::String("include_docker".into())));
+    assert_eq!(Some(&Value::Null), defaults.get(&Value::String("go_version".into())));
+
+    // lists of anything but strings have no kind, and are given as YAML
+    let template = Registered::find("ansible/docker-compose.yml").unwrap();
+    assert_eq!(None, template.fields.iter().find(|f| f.name == "machines").unwrap().kind);
+}
+
+#[test]
+fn test_render() {
+    let template = Registered::find("git/gitignore").unwrap();
+
+    let values = template.parse_assignments(&assignments(&["python=yes"])).unwrap();
+    let rendered = template.render(values, &overrides()).unwrap();
+
+    assert!(rendered.contains("*.pyc"));
+    assert_eq!(rendered, git::GitIgnore::new().python().contents().unwrap());
+}
+
+#[test]
+fn test_render_lists() {
+    let template = Registered::find("ansible/tests/inventory/hosts.yml").unwrap();
+    let values = template.parse_assignments(&assignments(&["hosts=centos, ubuntu"])).unwrap();
+
+    let mut expected = ansible::InventoryYaml::new();
+    expected.add_host("centos".into());
+    expected.add_host("ubuntu".into());
+
+    assert_eq!(expected.contents().unwrap(), template.render(values, &overrides()).unwrap());
+
+    // lists of anything else are given as YAML
+    let template = Registered::find("ansible/docker-compose.yml").unwrap();
+    let values = template.parse_assignments(&assignments(&["machines=[{name: centos, image: \"centos:7\"}]"])).unwrap();
+
+    let expected = ansible::DockerComposeConfig::new()
+        .machine(ansible::DockerComposeMachine::new("centos".into(), "centos:7".into()))
+        .contents().unwrap();
+
+    assert_eq!(expected, template.render(values, &overrides()).unwrap());
+}
+
+#[test]
+fn test_render_every_template() {
+    // undefined variables are errors, so every variable a template uses has to be one of its fields
+    for template in all() {
+        template.render(Mapping::new(), &overrides()).unwrap_or_else(|e| panic!("{:#}", e));
+
+        // with every field given, so that every conditional and loop in the template is rendered
+        let given = template.fields.iter().map(|field| match (field.kind, field.name) {
+            (Some(VariableType::Bool), name) => format!("{}=yes", name),
+            (Some(VariableType::Integer), name) => format!("{}=2019", name),
+            (Some(VariableType::List), name) => format!("{}=a, b", name),
+            (Some(VariableType::String), name) => format!("{}=1.0", name),
+            (None, "machines") => "machines=[{name: centos, image: \"centos:7\", is_privileged: true}]".to_string(),
+            (None, "roles") => "roles=[{src: a}, {src: b, name: b, version: \"1.0\"}]".to_string(),
+            (None, name) => panic!("{} has a field {} given as YAML, add a value for it", template.name, name),
+        }).collect::<Vec<_>>();
+
+        let values = template.parse_assignments(&given).unwrap();
+        template.render(values, &overrides()).unwrap_or_else(|e| panic!("{:#}", e));
+    }
+}
+
+#[test]
+fn test_parse_assignments_by_kind() {
+    let template = Registered::find("licenses/MIT").unwrap();
+    let values = template.parse_assignments(&assignments(&["author=1984", "year=2018"])).unwrap();
+
+    // strings are taken as given rather than as YAML, which would make this a number
+    assert_eq!(Some(&Value::String("1984".into())), values.get(&Value::String("author".into())));
+    assert_eq!(Some(&Value::Number(2018.into())), values.get(&Value::String("year".into())));
+
+    assert!(template.parse_assignments(&assignments(&["year=soon"])).is_err());
+    assert!(template.render(values, &overrides()).unwrap().starts_with("Copyright © 2018 1984\n"));
+}
+
+#[test]
+fn test_parse_assignments_invalid() {
+    let template = Registered::find("git/gitignore").unwrap();
+
+    assert!(template.parse_assignments(&assignments(&["python"])).is_err());
+    assert!(template.parse_assignments(&assignments(&["cobol=yes"])).is_err());
+    assert!(template.parse_assignments(&assignments(&["python=maybe"])).is_err());
+}
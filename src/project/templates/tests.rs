@@ -0,0 +1,98 @@
+use super::*;
+
+use serde_derive::Serialize;
+
+use crate::testing::Scratch;
+
+#[derive(Default, Serialize, WritableTemplate)]
+#[template(source = "#!/bin/sh\necho {{ greeting }}\n")]
+#[writable(mode = 0o750, default_path = "bin/greet", newline = "ensure", overwrite = "never")]
+struct Script {
+    greeting: String,
+}
+
+#[derive(Default, Serialize, WritableTemplate)]
+#[template(source = "{{ greeting }}")]
+#[writable(comment = "#")]
+struct Greeting {
+    greeting: String,
+}
+
+#[derive(Default, Serialize, WritableTemplate)]
+#[template(source = "{{ greeting }}, {{ name }}")]
+struct Misspelled {
+    greeting: String,
+}
+
+#[test]
+fn test_attributes() {
+    let script = Script { greeting: "hi".into() };
+
+    assert_eq!(Some(0o750), script.mode());
+    assert_eq!(Some("bin/greet"), Script::default_path());
+    assert_eq!(Newline::Ensure, script.newline());
+    assert_eq!(Overwrite::Never, script.overwrite());
+
+    let greeting = Greeting::default();
+
+    assert_eq!(None, greeting.mode());
+    assert_eq!(None, Greeting::default_path());
+    assert_eq!(Newline::Trim, greeting.newline());
+    assert_eq!(Overwrite::IfUnmodified, greeting.overwrite());
+}
+
+#[test]
+fn test_newline() {
+    // rendered contents are trimmed, and only templates ensuring a newline get one back
+    assert_eq!("#!/bin/sh\necho hi\n", Script { greeting: "hi".into() }.contents_over(None).unwrap());
+    assert_eq!("hi", Greeting { greeting: "hi\n".into() }.contents().unwrap());
+}
+
+#[test]
+fn test_undefined() {
+    // a variable which isn't a field is an error, rather than rendering as nothing
+    let error = Misspelled { greeting: "hi".into() }.contents().unwrap_err();
+
+    assert_eq!(ErrorKind::InvalidData, error.kind());
+    assert!(error.to_string().starts_with("Misspelled: undefined value"), "{}", error);
+
+    assert!(render_str("{{ name }}", &Greeting::default()).is_err());
+    assert_eq!("", render_str("{{ name|default('') }}", &Greeting::default()).unwrap());
+}
+
+#[test]
+fn test_write() {
+    let dir = Scratch::new("templates-write");
+    let path = dir.join("greet");
+
+    Script { greeting: "hi".into() }.write(&path).unwrap();
+
+    assert_eq!("#!/bin/sh\necho hi\n", fs::read_to_string(&path).unwrap());
+    assert_eq!(0o750, fs::metadata(&path).unwrap().permissions().mode() & 0o777);
+
+    // the script is never overwritten once it exists
+    Script { greeting: "hello".into() }.write(&path).unwrap();
+    assert_eq!("#!/bin/sh\necho hi\n", fs::read_to_string(&path).unwrap());
+
+    // nothing is left behind but the file itself
+    assert_eq!(1, fs::read_dir(&dir).unwrap().count());
+
+}
+
+#[test]
+fn test_write_keeps_permissions() {
+    let dir = Scratch::new("templates-permissions");
+    let path = dir.join("greeting");
+
+    fs::write(&path, "hello").unwrap();
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).unwrap();
+
+    write(&path, "hi", None).unwrap();
+
+    assert_eq!("hi", fs::read_to_string(&path).unwrap());
+    assert_eq!(0o600, fs::metadata(&path).unwrap().permissions().mode() & 0o777);
+
+    write(&path, "hey", Some(0o644)).unwrap();
+    assert_eq!(0o644, fs::metadata(&path).unwrap().permissions().mode() & 0o777);
+
+}
@@ -1,12 +1,13 @@
 #[cfg(test)]
 mod tests;
 
-use askama::Template;
+use serde_derive::{Deserialize, Serialize};
 
-use std::io::Write;
-
-#[derive(Default, Template, WritableTemplate)]
+/// The Vagrantfile of a development machine, provisioned with its playbook.
+#[derive(Default, Deserialize, Serialize, WritableTemplate)]
+#[serde(default)]
 #[template(path = "vagrant/Vagrantfile.j2")]
+#[writable(default_path = "Vagrantfile", newline = "ensure")]
 pub struct Vagrantfile {}
 
 impl Vagrantfile {
@@ -15,8 +16,11 @@ impl Vagrantfile {
     }
 }
 
-#[derive(Default, Template, WritableTemplate)]
+/// The Ansible playbook provisioning a development machine with the toolchains a project uses.
+#[derive(Default, Deserialize, Serialize, WritableTemplate)]
+#[serde(default)]
 #[template(path = "vagrant/playbook.yml.j2")]
+#[writable(default_path = "vagrant.yml", newline = "ensure", format = "yaml", schema = "Playbook")]
 pub struct VagrantPlaybook {
     pub include_docker: bool,
     pub go_version: Option<String>,
@@ -68,39 +72,4 @@ impl VagrantPlaybook {
         self.include_rust = true;
         self
     }
-
-    #[inline]
-    pub fn has_docker(&self) -> bool {
-        self.include_docker
-    }
-
-    #[inline]
-    pub fn has_go(&self) -> bool {
-        self.go_version.is_some()
-    }
-
-    #[inline]
-    pub fn has_java(&self) -> bool {
-        self.java
-    }
-
-    #[inline]
-    pub fn has_node(&self) -> bool {
-        self.node_version.is_some()
-    }
-
-    #[inline]
-    pub fn has_python(&self) -> bool {
-        self.python_version.is_some()
-    }
-
-    #[inline]
-    pub fn has_ruby(&self) -> bool {
-        self.ruby_version.is_some()
-    }
-
-    #[inline]
-    pub fn has_rust(&self) -> bool {
-        self.include_rust
-    }
 }
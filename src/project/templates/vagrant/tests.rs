@@ -1,8 +1,10 @@
 use super::*;
 
+use crate::project::templates::WritableTemplate;
+
 #[test]
 fn test_docker_playbook() {
-    let rendered = VagrantPlaybook::new().docker().render().unwrap();
+    let rendered = VagrantPlaybook::new().docker().contents().unwrap();
 
     assert_eq!(
         include_str!("fixtures/playbooks/docker.yml").trim(),
@@ -14,8 +16,7 @@ fn test_docker_playbook() {
 fn test_go_playbook() {
     let rendered = VagrantPlaybook::new()
         .go("GO_PACKAGE", "GO_VERSION")
-        .render()
-        .unwrap();
+        .contents().unwrap();
 
     assert_eq!(
         include_str!("fixtures/playbooks/go.yml").trim(),
@@ -28,8 +29,7 @@ fn test_hybrid_playbook() {
     let rendered = VagrantPlaybook::new()
         .docker()
         .python("PYTHON_VERSION")
-        .render()
-        .unwrap();
+        .contents().unwrap();
 
     assert_eq!(
         include_str!("fixtures/playbooks/hybrid.yml").trim(),
@@ -39,7 +39,7 @@ fn test_hybrid_playbook() {
 
 #[test]
 fn test_java_playbook() {
-    let rendered = VagrantPlaybook::new().java().render().unwrap();
+    let rendered = VagrantPlaybook::new().java().contents().unwrap();
 
     assert_eq!(
         include_str!("fixtures/playbooks/java.yml").trim(),
@@ -51,8 +51,7 @@ fn test_java_playbook() {
 fn test_node_playbook() {
     let rendered = VagrantPlaybook::new()
         .node("NODE_VERSION")
-        .render()
-        .unwrap();
+        .contents().unwrap();
 
     assert_eq!(
         include_str!("fixtures/playbooks/node.yml").trim(),
@@ -64,8 +63,7 @@ fn test_node_playbook() {
 fn test_python_playbook() {
     let rendered = VagrantPlaybook::new()
         .python("PYTHON_VERSION")
-        .render()
-        .unwrap();
+        .contents().unwrap();
 
     assert_eq!(
         include_str!("fixtures/playbooks/python.yml").trim(),
@@ -77,8 +75,7 @@ fn test_python_playbook() {
 fn test_ruby_playbook() {
     let rendered = VagrantPlaybook::new()
         .ruby("RUBY_VERSION")
-        .render()
-        .unwrap();
+        .contents().unwrap();
 
     assert_eq!(
         include_str!("fixtures/playbooks/ruby.yml").trim(),
@@ -88,7 +85,7 @@ fn test_ruby_playbook() {
 
 #[test]
 fn test_rust_playbook() {
-    let rendered = VagrantPlaybook::new().rust().render().unwrap();
+    let rendered = VagrantPlaybook::new().rust().contents().unwrap();
 
     assert_eq!(
         include_str!("fixtures/playbooks/rust.yml").trim(),
@@ -0,0 +1,213 @@
+#[cfg(test)]
+mod tests;
+
+use crate::project::templates::WritableTemplate;
+use crate::project::templates::ansible::GalaxyRole;
+
+use serde::de::DeserializeOwned;
+
+use serde_derive::Deserialize;
+
+use serde_yaml::{Mapping, Value};
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io;
+use std::path::Path;
+
+/// Why rendered contents aren't valid, and where.
+#[derive(Debug)]
+pub struct Invalid {
+    /// What the contents were expected to be, e.g. `YAML`.
+    pub format: &'static str,
+    pub message: String,
+    /// The line the problem is on, starting from 1, if it is known.
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+}
+
+impl fmt::Display for Invalid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid {}", self.format)?;
+
+        match (self.line, self.column) {
+            (Some(line), Some(column)) => write!(f, " at line {} column {}", line, column)?,
+            (Some(line), None) => write!(f, " at line {}", line)?,
+            _ => (),
+        }
+
+        write!(f, ": {}", self.message)
+    }
+}
+
+/// Check that contents are YAML, which deserializes into the given schema.
+pub fn yaml<T>(contents: &str) -> Result<(), Invalid> where T: DeserializeOwned {
+    serde_yaml::from_str::<T>(contents).map(|_| ()).map_err(|e| {
+        let location = e.location();
+
+        Invalid {
+            format: "YAML",
+            // the location is given separately, rather than at the end of the message
+            message: strip_location(&e.to_string()),
+            line: location.as_ref().map(|l| l.line()),
+            column: location.as_ref().map(|l| l.column() + 1),
+        }
+    })
+}
+
+/// Check that contents are INI.
+pub fn ini(contents: &str) -> Result<(), Invalid> {
+    ini::Ini::load_from_str(contents).map(|_| ()).map_err(|e| Invalid {
+        format: "INI",
+        message: e.msg.to_string(),
+        line: Some(e.line),
+        column: Some(e.col),
+    })
+}
+
+/// Check contents by the extension of the file they are written to, for files which aren't
+/// rendered from a template declaring their format.
+pub fn by_extension(path: &Path, contents: &str) -> Result<(), Invalid> {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("yml") | Some("yaml") => yaml::<Value>(contents),
+        Some("cfg") | Some("ini") => ini(contents),
+        _ => Ok(()),
+    }
+}
+
+/// A check of rendered contents.
+pub type Check = fn(&str) -> Result<(), Invalid>;
+
+/// The check of YAML against the schema of the given name, e.g. `Inventory`, for template packs,
+/// which name the schemas of their files rather than declaring them on a type.
+pub fn schema(name: &str) -> Option<Check> {
+    match name {
+        "ComposeFile" => Some(yaml::<ComposeFile>),
+        "GalaxyRequirements" => Some(yaml::<GalaxyRequirements>),
+        "Inventory" => Some(yaml::<Inventory>),
+        "Playbook" => Some(yaml::<Playbook>),
+        _ => None,
+    }
+}
+
+/// Check what was rendered for a template, blaming any problem on its variables.
+pub fn check<T>(template: &T, contents: &str) -> io::Result<()> where T: WritableTemplate {
+    template.validate(contents).map_err(|invalid| {
+        let context = serde_yaml::to_value(template).unwrap_or(Value::Null);
+        io::Error::new(io::ErrorKind::InvalidData, blame(&invalid, contents, &context))
+    })
+}
+
+/// Describe why rendered contents are invalid, pointing at the variables of the context they were
+/// rendered with whose values appear on the offending line or in the problem itself.
+pub fn blame(invalid: &Invalid, contents: &str, context: &Value) -> String {
+    let line = invalid.line.and_then(|l| contents.lines().nth(l.saturating_sub(1)));
+
+    let culprits: Vec<String> = variables(context)
+        .into_iter()
+        .filter(|(_, v)| !v.is_empty())
+        .filter(|(_, v)| line.is_some_and(|l| l.contains(v.as_str())) || invalid.message.contains(&format!("`{}`", v)))
+        .map(|(name, v)| format!("{} = {:?}", name, v))
+        .collect();
+
+    let mut message = invalid.to_string();
+
+    match (culprits.is_empty(), line) {
+        (false, _) => message.push_str(&format!(", check {}", culprits.join(", "))),
+        (true, Some(line)) => message.push_str(&format!(", in {:?}", line.trim())),
+        (true, None) => (),
+    }
+
+    message
+}
+
+/// Every scalar variable of a template by its path, e.g. `machines[0].image`, and its value as
+/// it would be rendered.
+fn variables(value: &Value) -> Vec<(String, String)> {
+    let mut result = Vec::new();
+    collect("", value, &mut result);
+    result
+}
+
+fn collect(path: &str, value: &Value, result: &mut Vec<(String, String)>) {
+    match value {
+        Value::Mapping(mapping) => {
+            for (key, value) in mapping {
+                let key = key.as_str().map(|k| k.to_string()).unwrap_or_else(|| format!("{:?}", key));
+
+                match path {
+                    "" => collect(&key, value, result),
+                    path => collect(&format!("{}.{}", path, key), value, result),
+                }
+            }
+        }
+        Value::Sequence(items) => {
+            for (index, item) in items.iter().enumerate() {
+                collect(&format!("{}[{}]", path, index), item, result);
+            }
+        }
+        Value::String(s) => result.push((path.to_string(), s.clone())),
+        Value::Number(n) => result.push((path.to_string(), n.to_string())),
+        Value::Bool(_) | Value::Null => (),
+    }
+}
+
+/// Remove the location serde_yaml appends to its messages.
+fn strip_location(message: &str) -> String {
+    match message.rfind(" at line ") {
+        Some(index) => message[..index].to_string(),
+        None => message.to_string(),
+    }
+}
+
+/// A Docker Compose file, as far as the machines a role is tested against are concerned.
+#[derive(Debug, Deserialize)]
+pub struct ComposeFile {
+    pub version: String,
+    /// The services, which are null when there are none.
+    pub services: Option<BTreeMap<String, ComposeService>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ComposeService {
+    pub container_name: String,
+    pub image: String,
+    #[serde(default)]
+    pub privileged: bool,
+}
+
+/// The roles to install from Ansible Galaxy, which are null when there are none.
+pub type GalaxyRequirements = Option<Vec<GalaxyRole>>;
+
+/// An Ansible inventory of hosts without variables of their own.
+#[derive(Debug, Deserialize)]
+pub struct Inventory {
+    pub all: InventoryGroup,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct InventoryGroup {
+    /// The hosts, which are null when there are none.
+    pub hosts: Option<BTreeMap<String, Mapping>>,
+    #[serde(default)]
+    pub vars: BTreeMap<String, Value>,
+}
+
+/// An Ansible playbook applying roles, whose variables are all strings.
+pub type Playbook = Vec<Play>;
+
+#[derive(Debug, Deserialize)]
+pub struct Play {
+    pub name: String,
+    pub hosts: String,
+    /// The roles, which are null when there are none.
+    #[serde(default)]
+    pub roles: Option<Vec<PlayRole>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PlayRole {
+    pub role: String,
+    #[serde(flatten)]
+    pub variables: BTreeMap<String, String>,
+}
@@ -0,0 +1,75 @@
+use super::*;
+
+use crate::project::templates::ansible::{AnsibleConfig, AnsibleRequirements, DockerComposeConfig, DockerComposeMachine};
+use crate::project::templates::vagrant::VagrantPlaybook;
+
+#[test]
+fn test_rendered_templates_are_valid() {
+    let mut compose = DockerComposeConfig::new();
+    compose.with_dind()
+        .machine(DockerComposeMachine::new("centos7".into(), "naftulikay/centos7-vm:latest".into()).privileged());
+
+    check(&compose, &compose.contents().unwrap()).unwrap();
+    check(&DockerComposeConfig::new(), &DockerComposeConfig::new().contents().unwrap()).unwrap();
+
+    let requirements = AnsibleRequirements::new().role(GalaxyRole::new("naftulikay.degoss", Some("degoss"), None));
+    check(&requirements, &requirements.contents().unwrap()).unwrap();
+
+    let playbook = VagrantPlaybook::new().docker().python("3.6.8").go("github.com/a/b", "1.11.4");
+    check(&playbook, &playbook.contents().unwrap()).unwrap();
+
+    let config = AnsibleConfig::new().role_path(".ansible/roles".into());
+    check(&config, &config.contents().unwrap()).unwrap();
+}
+
+#[test]
+fn test_invalid_yaml() {
+    let requirements = AnsibleRequirements::new()
+        .role(GalaxyRole::new("naftulikay.degoss", Some("degoss: latest"), None));
+
+    let error = check(&requirements, &requirements.contents().unwrap()).unwrap_err().to_string();
+
+    assert!(error.starts_with("invalid YAML at line 3"), "{}", error);
+    assert!(error.ends_with("check roles[0].name = \"degoss: latest\""), "{}", error);
+}
+
+#[test]
+fn test_schema() {
+    // versions YAML would read as numbers are quoted
+    let playbook = VagrantPlaybook::new().python("3.7").go("example.com/x", "1.10").node("10").ruby("2.5");
+    let contents = playbook.contents().unwrap();
+
+    assert!(check(&playbook, &contents).is_ok(), "{}", contents);
+    assert!(contents.contains("python_version: \"3.7\"\n"), "{}", contents);
+
+    // which an override forgetting to quote them would be
+    let error = yaml::<Playbook>("- name: build\n  hosts: all\n  roles:\n    - role: x\n      python_version: 3.7\n")
+        .unwrap_err().to_string();
+
+    assert!(error.contains("expected a string"), "{}", error);
+}
+
+#[test]
+fn test_invalid_ini() {
+    let config = AnsibleConfig::new().role_path("roles\n[defaults".into());
+    let error = check(&config, &config.contents().unwrap()).unwrap_err().to_string();
+
+    assert!(error.starts_with("invalid INI at line 4"), "{}", error);
+}
+
+#[test]
+fn test_by_extension() {
+    assert!(by_extension(Path::new("tests/hosts.yml"), "all: {hosts: {a: {}}}").is_ok());
+    assert!(by_extension(Path::new("tests/hosts.yml"), "all: [a: b: c]").is_err());
+    assert!(by_extension(Path::new("setup.cfg"), "[metadata\nname = a").is_err());
+    assert!(by_extension(Path::new("README.md"), "all: [a: b: c]").is_ok());
+}
+
+#[test]
+fn test_blame_without_culprit() {
+    let invalid = Invalid { format: "YAML", message: "did not find expected key".into(), line: Some(2), column: Some(1) };
+    let context = serde_yaml::from_str("name: fine").unwrap();
+
+    assert_eq!("invalid YAML at line 2 column 1: did not find expected key, in \"- a: b\"",
+        blame(&invalid, "all:\n  - a: b\n", &context));
+}
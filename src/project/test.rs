@@ -1,5 +1,7 @@
 use super::*;
 
+use crate::testing::Scratch;
+
 #[test]
 fn test_ssh_project_regex() {
     assert!(SSH_PROVIDER.is_match(&"naftulikay/titan"));
@@ -68,3 +70,32 @@ fn test_project_from_ssh() {
         assert_eq!(&project.repository(), repository);
     }
 }
+
+#[test]
+fn test_project_matches() {
+    let project = Project::from("github.com:naftulikay/ansible-role-vim-personal").unwrap();
+
+    assert_eq!("github.com/naftulikay/ansible-role-vim-personal", project.name());
+
+    assert!(project.matches(&Pattern::new("github.com/naftulikay/ansible-role-*").unwrap()));
+    assert!(project.matches(&Pattern::new("*/*/ansible-role-*").unwrap()));
+
+    assert!(!project.matches(&Pattern::new("github.com/naftulikay/nfty").unwrap()));
+    assert!(!project.matches(&Pattern::new("gitlab.com/naftulikay/*").unwrap()));
+}
+
+#[test]
+fn test_project_init() {
+    let scratch = Scratch::new("project-init");
+    let dir = scratch.join("github.com").join("naftulikay").join("nfty");
+
+    let project = Project::from("naftulikay/nfty").unwrap();
+    let repo = project.init_at(&dir, "trunk").unwrap();
+
+    // the directory is created, and its repository starts on the branch with nothing on it yet
+    assert_eq!(dir.canonicalize().unwrap(), repo.workdir().unwrap().canonicalize().unwrap());
+    assert_eq!(Some("refs/heads/trunk"), repo.find_reference("HEAD").unwrap().symbolic_target());
+    assert!(repo.head().is_err());
+
+    assert_eq!(Some("git@github.com:naftulikay/nfty"), repo.find_remote("origin").unwrap().url());
+}
@@ -0,0 +1,86 @@
+//! Fixtures shared by tests.
+
+use git2::{Oid, Repository, Signature};
+
+use std::fs;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Distinguishes scratch directories made by the same test, or tests with the same name.
+static SCRATCH_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// An empty directory for a test, removed when it is dropped whether the test passed or not.
+pub struct Scratch {
+    path: PathBuf,
+}
+
+impl Scratch {
+    /// A fresh scratch directory, named after the test for anyone looking at one left behind by a
+    /// test which was killed.
+    pub fn new(test: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("nfty-{}-{}-{}", test, process::id(),
+            SCRATCH_COUNT.fetch_add(1, Ordering::SeqCst)));
+
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+
+        Scratch { path }
+    }
+}
+
+impl Deref for Scratch {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl AsRef<Path> for Scratch {
+    fn as_ref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+/// Initialize a repository at the given path with a committer configured, so that it can be
+/// committed to without any global Git configuration.
+pub fn repository(path: &Path) -> Repository {
+    let repo = Repository::init(path).unwrap();
+
+    {
+        let mut config = repo.config().unwrap();
+        config.set_str("user.name", "nfty").unwrap();
+        config.set_str("user.email", "nfty@example.com").unwrap();
+    }
+
+    repo
+}
+
+/// Write a single file to the working tree of a repository and commit it on `HEAD`.
+pub fn commit_file(repo: &Repository, name: &str, contents: &str, message: &str) -> Oid {
+    let path = repo.workdir().unwrap().join(name);
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).unwrap();
+    }
+
+    fs::write(&path, contents).unwrap();
+
+    let mut index = repo.index().unwrap();
+    index.add_path(Path::new(name)).unwrap();
+    index.write().unwrap();
+
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let signature = Signature::now("nfty", "nfty@example.com").unwrap();
+    let parent = repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+
+    repo.commit(Some("HEAD"), &signature, &signature, message, &tree, &parent.iter().collect::<Vec<_>>()).unwrap()
+}
@@ -1,4 +1,6 @@
-use git2::Progress;
+use git2::{Config, Cred, CredentialType, Progress, RemoteCallbacks};
+
+use sha2::{Digest, Sha256};
 
 use std::cmp::min;
 
@@ -36,4 +38,31 @@ impl Git {
         ((download_progress * 0.5) + (index_progress * 0.5)).round() as u64
     }
 
+    /// Create remote callbacks which authenticate using the SSH agent for SSH remotes and the
+    /// configured credential helper for HTTPS remotes.
+    pub fn remote_callbacks<'a>() -> RemoteCallbacks<'a> {
+        let mut callbacks = RemoteCallbacks::new();
+
+        callbacks.credentials(|url, username, allowed| {
+            if allowed.contains(CredentialType::SSH_KEY) {
+                Cred::ssh_key_from_agent(username.unwrap_or(crate::project::DEFAULT_USER))
+            } else if allowed.contains(CredentialType::USER_PASS_PLAINTEXT) {
+                Cred::credential_helper(&Config::open_default()?, url, username)
+            } else {
+                Cred::default()
+            }
+        });
+
+        callbacks
+    }
+}
+
+/// Content checksums.
+pub struct Checksum;
+
+impl Checksum {
+    /// Compute the hex-encoded SHA-256 digest of the given bytes.
+    pub fn sha256(data: &[u8]) -> String {
+        format!("{:x}", Sha256::digest(data))
+    }
 }